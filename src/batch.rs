@@ -0,0 +1,247 @@
+use std::fs;
+
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::search::SearchResult;
+use crate::state::GameState;
+
+// A position file contains one position notation per line, optionally
+// followed by a label separated with ';'. Empty lines and lines starting
+// with '#' are ignored, e.g.
+//   # openings
+//   7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0; initial position
+pub struct BatchPosition {
+    pub label: String,
+    pub game_state: GameState,
+}
+
+pub struct BatchEntry {
+    pub label: String,
+    pub notation: String,
+    pub result: SearchResult,
+}
+
+pub fn read_position_file(path: &str) -> Result<Vec<BatchPosition>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|error| format!("could not read '{path}': {error}"))?;
+
+    parse_position_file(&contents).map_err(|error| format!("{path}:{error}"))
+}
+
+pub fn parse_position_file(contents: &str) -> Result<Vec<BatchPosition>, String> {
+    let mut positions = Vec::new();
+
+    for (line_index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (notation, label) = match line.split_once(';') {
+            Some((notation, label)) => (notation.trim(), label.trim().to_string()),
+            None => (line, format!("line {}", line_index + 1)),
+        };
+
+        let game_state = GameState::from_notation(notation)
+            .map_err(|error| format!("{}: {error}", line_index + 1))?;
+
+        positions.push(BatchPosition { label, game_state });
+    }
+
+    Ok(positions)
+}
+
+pub fn analyze_positions(positions: &[BatchPosition], limits: &SearchLimits) -> Vec<BatchEntry> {
+    let mut entries = Vec::new();
+
+    for (i, position) in positions.iter().enumerate() {
+        eprintln!("[{}/{}] {}", i + 1, positions.len(), position.label);
+
+        let result = think_with(&position.game_state, limits, &mut |_| {});
+
+        entries.push(BatchEntry {
+            label: position.label.clone(),
+            notation: position.game_state.to_notation(),
+            result,
+        });
+    }
+
+    entries
+}
+
+pub fn format_text(entries: &[BatchEntry]) -> String {
+    let mut text = String::new();
+
+    text.push_str("label                |  best | depth |      score |      nodes |      time | principal variation\n");
+    text.push_str("---------------------|-------|-------|------------|------------|-----------|---------------------------\n");
+
+    for entry in entries {
+        let result = &entry.result;
+        let best_move = format_best_move(result);
+        let score = result.score as f32 / 1000f32;
+        let time = result.time as f32 / 1000f32;
+
+        text.push_str(&format!(
+            "{:<20} | {best_move:>5} | {:>5} | {score:>10.3} | {:>10} | {time:>9.3} | {}\n",
+            entry.label,
+            result.depth,
+            result.nodes,
+            format_pv(result),
+        ));
+    }
+
+    text
+}
+
+pub fn format_json(entries: &[BatchEntry]) -> String {
+    let mut json = String::from("[\n");
+
+    for (i, entry) in entries.iter().enumerate() {
+        let result = &entry.result;
+        let pv: Vec<String> = result
+            .pv
+            .iter()
+            .map(|m| format!("\"{m}\""))
+            .collect();
+        let best_move = match result.best_move {
+            Some(mov) => format!("\"{mov}\""),
+            None => "null".to_string(),
+        };
+
+        json.push_str(&format!(
+            "  {{\"label\": {}, \"position\": {}, \"best_move\": {best_move}, \"score\": {}, \"depth\": {}, \"nodes\": {}, \"time_ms\": {}, \"pv\": [{}]}}",
+            json_string(&entry.label),
+            json_string(&entry.notation),
+            result.score,
+            result.depth,
+            result.nodes,
+            result.time,
+            pv.join(", "),
+        ));
+
+        if i + 1 < entries.len() {
+            json.push(',');
+        }
+        json.push('\n');
+    }
+
+    json.push_str("]\n");
+    json
+}
+
+pub fn format_csv(entries: &[BatchEntry]) -> String {
+    let mut csv = String::from("label,position,best_move,score,depth,nodes,time_ms,pv\n");
+
+    for entry in entries {
+        let result = &entry.result;
+
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            csv_field(&entry.label),
+            csv_field(&entry.notation),
+            format_best_move(result),
+            result.score,
+            result.depth,
+            result.nodes,
+            result.time,
+            format_pv(result),
+        ));
+    }
+
+    csv
+}
+
+fn format_best_move(result: &SearchResult) -> String {
+    match result.best_move {
+        Some(mov) => mov.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+fn format_pv(result: &SearchResult) -> String {
+    let pv: Vec<String> = result
+        .pv
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+
+    pv.join(" ")
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const POSITIONS: &str = "\
+        # comment\n\
+        \n\
+        7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0; initial, \"start\"\n\
+        7/2PP3/1PP1PK1/2pppp1/3pp2/7 b 1\n";
+
+    #[test]
+    fn test_parse_position_file() {
+        let positions = parse_position_file(POSITIONS).unwrap();
+
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].label, "initial, \"start\"");
+        assert_eq!(positions[1].label, "line 4");
+        assert!(
+            !positions[1]
+                .game_state
+                .is_active_player_white
+        );
+
+        assert!(parse_position_file("7/2PP3 w").is_err());
+    }
+
+    #[test]
+    fn test_report_formats() {
+        let positions = parse_position_file(POSITIONS).unwrap();
+        let entries = analyze_positions(&positions, &SearchLimits::new(3, u32::MAX));
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries
+            .iter()
+            .all(|e| e.result.depth == 3));
+
+        let text = format_text(&entries);
+        assert_eq!(text.lines().count(), 4);
+
+        let json = format_json(&entries);
+        assert!(json.starts_with("[\n  {\"label\": \"initial, \\\"start\\\"\", \"position\": \"7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0\""));
+        assert!(json.contains("\"depth\": 3"));
+        assert!(json.ends_with("}\n]\n"));
+
+        let csv = format_csv(&entries);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("\"initial, \"\"start\"\"\",7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0,"));
+        assert!(lines[2].starts_with("line 4,7/2PP3/1PP1PK1/2pppp1/3pp2/7 b 1,"));
+    }
+}
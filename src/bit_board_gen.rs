@@ -1,62 +1,71 @@
-use crate::bit_utils::print_bit_board;
-use crate::bit_utils::set_board_bit;
-use crate::constants::BOARD_HEIGHT;
-use crate::constants::BOARD_WIDTH;
-
-pub fn print_bit_board_code() {
-    print_king_moves();
-    println!();
-    print_jump_moves();
-}
-
-fn print_king_moves() {
-    println!("pub const KING_MOVES: [u64; 42] = [");
-
-    for y in 0..BOARD_HEIGHT {
-        for x in 0..BOARD_WIDTH {
-            let mut bits = 0;
-
-            bits = set_board_bit(bits, x.wrapping_sub(1), y.wrapping_sub(1));
-            bits = set_board_bit(bits, x, y.wrapping_sub(1));
-            bits = set_board_bit(bits, x + 1, y.wrapping_sub(1));
-
-            bits = set_board_bit(bits, x.wrapping_sub(1), y);
-            bits = set_board_bit(bits, x + 1, y);
-
-            bits = set_board_bit(bits, x.wrapping_sub(1), y + 1);
-            bits = set_board_bit(bits, x, y + 1);
-            bits = set_board_bit(bits, x + 1, y + 1);
-
-            println!("    0b{bits:042b},");
-
-            //println!("-------");
-            //print_bit_board(bits);
-        }
+use crate::constants::BitBoard;
+
+pub const KING_STEPS: [(i8, i8); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+pub const JUMP_STEPS: [(i8, i8); 4] = [(0, -2), (-2, 0), (2, 0), (0, 2)];
+
+// The move tables of the standard board in constants.rs are evaluated from
+// this at compile time, N is the number of squares.
+pub const fn make_move_table<const N: usize>(
+    width: u8,
+    height: u8,
+    steps: &[(i8, i8)],
+) -> [BitBoard; N] {
+    assert!(N == width as usize * height as usize);
+
+    let mut moves = [0; N];
+
+    let mut i = 0;
+    while i < N {
+        moves[i] = get_square_moves(width, height, steps, i);
+        i += 1;
     }
 
-    println!("];");
+    moves
 }
 
-fn print_jump_moves() {
-    println!("pub const JUMP_MOVES: [u64; 42] = [");
+// for every square the squares a king reaches with one step
+pub fn make_king_moves(width: u8, height: u8) -> Vec<BitBoard> {
+    make_moves(width, height, &KING_STEPS)
+}
 
-    for y in 0..BOARD_HEIGHT {
-        for x in 0..BOARD_WIDTH {
-            let mut bits = 0;
+// for every square the squares a piece reaches by jumping over a neighbour
+pub fn make_jump_moves(width: u8, height: u8) -> Vec<BitBoard> {
+    make_moves(width, height, &JUMP_STEPS)
+}
 
-            bits = set_board_bit(bits, x, y.wrapping_sub(2));
+fn make_moves(width: u8, height: u8, steps: &[(i8, i8)]) -> Vec<BitBoard> {
+    (0..width as usize * height as usize)
+        .map(|i| get_square_moves(width, height, steps, i))
+        .collect()
+}
 
-            bits = set_board_bit(bits, x.wrapping_sub(2), y);
-            bits = set_board_bit(bits, x + 2, y);
+const fn get_square_moves(width: u8, height: u8, steps: &[(i8, i8)], index: usize) -> BitBoard {
+    let (width, height) = (width as i32, height as i32);
+    let x = index as i32 % width;
+    let y = index as i32 / width;
 
-            bits = set_board_bit(bits, x, y + 2);
+    let mut bits = 0;
 
-            println!("    0b{bits:042b},");
+    let mut i = 0;
+    while i < steps.len() {
+        let (to_x, to_y) = (x + steps[i].0 as i32, y + steps[i].1 as i32);
 
-            //println!("-------");
-            //print_bit_board(bits);
+        if to_x >= 0 && to_x < width && to_y >= 0 && to_y < height {
+            bits |= 1 << (to_y * width + to_x);
         }
+
+        i += 1;
     }
 
-    println!("];");
+    bits
 }
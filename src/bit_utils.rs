@@ -1,7 +1,6 @@
 use crate::constants::BitBoard;
 use crate::constants::BoardIndex;
-use crate::constants::BOARD_HEIGHT;
-use crate::constants::BOARD_WIDTH;
+use crate::geometry::get_geometry;
 
 pub const fn make_board(bits: [[u8; 7]; 6]) -> BitBoard {
     let mut board: BitBoard = 0;
@@ -65,19 +64,23 @@ pub fn is_board_bit_set(bit_board: BitBoard, bit_index: BoardIndex) -> bool {
 // <----------+
 //  x
 pub fn is_board_coord_set(bit_board: BitBoard, x: BoardIndex, y: BoardIndex) -> bool {
-    if x >= BOARD_WIDTH || y >= BOARD_HEIGHT {
+    let geometry = get_geometry();
+
+    if x >= geometry.width || y >= geometry.height {
         return false;
     }
 
-    is_board_bit_set(bit_board, BOARD_WIDTH * y + x)
+    is_board_bit_set(bit_board, geometry.get_index(x, y))
 }
 
 pub fn set_board_bit(bit_board: BitBoard, x: BoardIndex, y: BoardIndex) -> BitBoard {
-    if x >= BOARD_WIDTH || y >= BOARD_HEIGHT {
+    let geometry = get_geometry();
+
+    if x >= geometry.width || y >= geometry.height {
         return bit_board;
     }
 
-    bit_board | 1 << (BOARD_WIDTH * y + x)
+    bit_board | 1 << geometry.get_index(x, y)
 }
 
 pub fn jump_bit(bit_board: BitBoard, from: BoardIndex, to: BoardIndex) -> BitBoard {
@@ -119,6 +122,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_is_board_bit_set() {
         let x = 0b0000000111001;
         assert_eq!(is_board_bit_set(x, 0), true);
@@ -131,6 +135,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_is_board_coord_set() {
         let x = make_board([
             [0, 0, 0, 1, 0, 0, 0],
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use crate::constants::SCORE_STEP;
+use crate::move_gen::Move;
+use crate::rng::Rng;
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::state::GameState;
+
+// moves that score at most this much worse than the best one go into the book
+pub const BOOK_MARGIN: i32 = SCORE_STEP / 20;
+pub const DEFAULT_BOOK_PLIES: u32 = 4;
+
+const BOOK_HEADER: &str = "# uisge opening book";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BookMove {
+    pub mov: Move,
+    pub weight: u32,
+}
+
+// Books are text files with one position per line: the hash of the position,
+// its moves with their weights and the notation as a comment, e.g.
+//   # uisge opening book
+//   4d4fce06b30b1aa3 c2e2:100 d2b2:87 ; 7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0
+#[derive(Default)]
+pub struct Book {
+    entries: HashMap<u64, Vec<BookMove>>,
+    notations: HashMap<u64, String>,
+}
+
+impl Book {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // symmetric positions share one entry, the one of the canonical position
+    pub fn insert(&mut self, game_state: &GameState, moves: Vec<BookMove>) {
+        let (canonical, symmetry) = game_state.get_canonical();
+        let hash = canonical.get_hash();
+
+        let moves = moves
+            .into_iter()
+            .map(|book_move| BookMove {
+                mov: symmetry.map_move(book_move.mov),
+                weight: book_move.weight,
+            })
+            .collect();
+
+        self.entries.insert(hash, moves);
+        self.notations
+            .insert(hash, canonical.to_notation());
+    }
+
+    // the moves of the canonical position, turned back
+    pub fn get_moves(&self, game_state: &GameState) -> Option<Vec<BookMove>> {
+        let (canonical, symmetry) = game_state.get_canonical();
+
+        self.entries
+            .get(&canonical.get_hash())
+            .map(|moves| {
+                moves
+                    .iter()
+                    .map(|book_move| BookMove {
+                        mov: symmetry
+                            .inverse()
+                            .map_move(book_move.mov),
+                        weight: book_move.weight,
+                    })
+                    .collect()
+            })
+    }
+
+    // picks one of the book moves with a probability proportional to its weight
+    pub fn choose_move(&self, game_state: &GameState, rng: &mut Rng) -> Option<Move> {
+        let legal_moves = game_state.generate_moves();
+
+        // a hash collision must never lead to an illegal move
+        let book_moves: Vec<BookMove> = self
+            .get_moves(game_state)?
+            .into_iter()
+            .filter(|book_move| legal_moves.contains(&book_move.mov))
+            .collect();
+
+        let weights: Vec<f64> = book_moves
+            .iter()
+            .map(|book_move| book_move.weight as f64)
+            .collect();
+
+        rng.pick_weighted(&weights)
+            .map(|i| book_moves[i].mov)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut hashes: Vec<&u64> = self.entries.keys().collect();
+        hashes.sort();
+
+        let mut text = format!("{BOOK_HEADER}\n");
+
+        for hash in hashes {
+            let moves: Vec<String> = self.entries[hash]
+                .iter()
+                .map(|book_move| format!("{}:{}", book_move.mov, book_move.weight))
+                .collect();
+
+            text.push_str(&format!("{hash:016x} {}", moves.join(" ")));
+            if let Some(notation) = self.notations.get(hash) {
+                text.push_str(&format!(" ; {notation}"));
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut book = Self::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {message}", i + 1);
+
+            let (data, notation) = match line.split_once(';') {
+                Some((data, notation)) => (data, Some(notation.trim())),
+                None => (line, None),
+            };
+
+            let data = data.trim();
+            if data.is_empty() || data.starts_with('#') {
+                continue;
+            }
+
+            let mut tokens = data.split_whitespace();
+            let hash = tokens
+                .next()
+                .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+                .ok_or(error("invalid position hash"))?;
+
+            let mut moves = Vec::new();
+            for token in tokens {
+                let (mov, weight) = token
+                    .split_once(':')
+                    .ok_or(error(&format!("expected <move>:<weight>, got '{token}'")))?;
+
+                moves.push(BookMove {
+                    mov: mov
+                        .parse()
+                        .map_err(|e: String| error(&e))?,
+                    weight: weight
+                        .parse()
+                        .map_err(|_| error(&format!("invalid weight '{weight}'")))?,
+                });
+            }
+
+            book.entries.insert(hash, moves);
+            if let Some(notation) = notation {
+                book.notations
+                    .insert(hash, notation.to_string());
+            }
+        }
+
+        Ok(book)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("could not read '{path}': {error}"))?;
+
+        Self::from_text(&text).map_err(|error| format!("{path}: {error}"))
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text())
+            .map_err(|error| format!("could not write '{path}': {error}"))
+    }
+}
+
+// Expands the book from a position by analyzing all moves of every position up
+// to the given number of plies. Moves close to the best one are kept, weighted
+// by their score, and the positions after them are analyzed in turn.
+// Positions symmetric to one that was already analyzed are skipped.
+pub fn build_book(
+    game_state: &GameState,
+    plies: u32,
+    limits: &SearchLimits,
+    on_position: &mut dyn FnMut(&GameState, &[BookMove]),
+) -> Book {
+    let mut book = Book::new();
+    let mut visited = HashSet::new();
+
+    let limits = SearchLimits {
+        max_depth: limits.max_depth,
+        min_time: limits.min_time,
+        max_time: limits.max_time,
+        max_nodes: limits.max_nodes,
+        stop: limits.stop.clone(),
+        multi_pv: usize::MAX,
+    };
+
+    let mut positions = vec![(game_state.clone(), 0)];
+
+    while let Some((game_state, ply)) = positions.pop() {
+        if ply >= plies
+            || game_state.get_result().is_some()
+            || !visited.insert(game_state.get_canonical_key())
+        {
+            continue;
+        }
+
+        let lines = think_with(&game_state, &limits, &mut |_| {}).lines;
+        let best_score = lines[0].score;
+
+        let moves: Vec<BookMove> = lines
+            .iter()
+            .filter(|line| best_score - line.score <= BOOK_MARGIN && !line.pv.is_empty())
+            .map(|line| BookMove {
+                mov: line.pv[0],
+                weight: get_weight(best_score - line.score),
+            })
+            .collect();
+
+        on_position(&game_state, &moves);
+
+        for book_move in &moves {
+            let mut next_game_state = game_state.clone();
+            next_game_state.make_move(book_move.mov);
+            positions.push((next_game_state, ply + 1));
+        }
+
+        book.insert(&game_state, moves);
+    }
+
+    book
+}
+
+// the best move gets a weight of 100, a move that loses the whole margin 1
+fn get_weight(loss: i32) -> u32 {
+    (1 + (BOOK_MARGIN - loss) * 99 / BOOK_MARGIN) as u32
+}
+
+static BOOK: OnceLock<(Book, Mutex<Rng>)> = OnceLock::new();
+
+// the book can only be loaded once, at startup, the seed makes the choices reproducible
+pub fn set_book(book: Book, seed: u64) {
+    let _ = BOOK.set((book, Mutex::new(Rng::new(seed))));
+}
+
+// a move from the loaded book, if there is one for the position
+pub fn probe_book(game_state: &GameState) -> Option<Move> {
+    let (book, rng) = BOOK.get()?;
+
+    book.choose_move(game_state, &mut rng.lock().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::symmetry::MIRRORS;
+
+    #[test]
+    fn test_book_round_trip() {
+        let game_state = GameState::new();
+        let moves = game_state.generate_moves();
+
+        let mut book = Book::new();
+        book.insert(
+            &game_state,
+            vec![
+                BookMove {
+                    mov: moves[0],
+                    weight: 100,
+                },
+                BookMove {
+                    mov: moves[1],
+                    weight: 3,
+                },
+            ],
+        );
+
+        let text = book.to_text();
+        assert!(text.starts_with(BOOK_HEADER));
+        assert!(text.contains(&format!(
+            " ; {}",
+            game_state
+                .get_canonical()
+                .0
+                .to_notation()
+        )));
+
+        let parsed = Book::from_text(&text).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed.get_moves(&game_state), book.get_moves(&game_state));
+        assert_eq!(parsed.to_text(), text);
+
+        // a mirrored position gets the mirrored moves
+        let mirror = MIRRORS[1];
+        let mirrored_moves: Vec<Move> = parsed
+            .get_moves(&mirror.map_game_state(&game_state))
+            .unwrap()
+            .iter()
+            .map(|book_move| book_move.mov)
+            .collect();
+        assert_eq!(
+            mirrored_moves,
+            vec![mirror.map_move(moves[0]), mirror.map_move(moves[1])]
+        );
+    }
+
+    #[test]
+    fn test_invalid_books() {
+        assert!(Book::from_text("xyz c2e2:1").is_err());
+        assert!(Book::from_text("00000000000000ff c2e2").is_err());
+        assert!(Book::from_text("00000000000000ff c2e2:heavy").is_err());
+        assert!(Book::from_text("00000000000000ff z9e2:1").is_err());
+        assert_eq!(
+            Book::from_text("# comment\n\n")
+                .unwrap()
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_choose_move() {
+        let game_state = GameState::new();
+        let moves = game_state.generate_moves();
+        let mut rng = Rng::new(5);
+
+        let mut book = Book::new();
+        assert_eq!(book.choose_move(&game_state, &mut rng), None);
+
+        // moves that are not legal in the position are never chosen
+        book.insert(
+            &game_state,
+            vec![
+                BookMove {
+                    mov: moves[2],
+                    weight: 1,
+                },
+                BookMove {
+                    mov: Move::new(0, 1),
+                    weight: 1000,
+                },
+            ],
+        );
+        for _ in 0..10 {
+            assert_eq!(book.choose_move(&game_state, &mut rng), Some(moves[2]));
+        }
+    }
+
+    #[test]
+    fn test_build_book() {
+        let game_state = GameState::new();
+        let mut positions = 0;
+
+        let book = build_book(
+            &game_state,
+            2,
+            &SearchLimits::new(3, u32::MAX),
+            &mut |_, _| positions += 1,
+        );
+
+        assert_eq!(book.len(), positions);
+        let moves = book.get_moves(&game_state).unwrap();
+        assert!(moves.iter().any(|m| m.weight == 100));
+        assert!(moves
+            .iter()
+            .all(|m| (1..=100).contains(&m.weight)));
+
+        // every reply to a book move is in the book as well
+        for book_move in moves {
+            let mut next_game_state = game_state.clone();
+            next_game_state.make_move(book_move.mov);
+            assert!(book
+                .get_moves(&next_game_state)
+                .is_some());
+        }
+    }
+}
@@ -0,0 +1,1412 @@
+use std::fs;
+use std::io::IsTerminal;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::batch::analyze_positions;
+use crate::batch::format_csv;
+use crate::batch::format_json;
+use crate::batch::format_text;
+use crate::batch::read_position_file;
+use crate::book::build_book;
+use crate::book::set_book;
+use crate::book::Book;
+use crate::book::DEFAULT_BOOK_PLIES;
+use crate::clock::TimeControl;
+use crate::coach::DEFAULT_COACH_THRESHOLD;
+use crate::constants::BOARD_HEIGHT;
+use crate::constants::BOARD_WIDTH;
+use crate::constants::PIECES_PER_SIDE;
+use crate::constants::SCORE_STEP;
+use crate::difficulty::Level;
+use crate::game_record::GameRecord;
+use crate::geometry::parse_board_size;
+use crate::geometry::set_geometry;
+use crate::geometry::BoardGeometry;
+use crate::gui::start_gui;
+use crate::gui::Configuration;
+use crate::gui::GameMode;
+use crate::gui::UiMode;
+use crate::proof::prove_win;
+use crate::proof::Proof;
+use crate::proof::ProofLimits;
+use crate::proof::ProofOutcome;
+use crate::protocol::start_protocol;
+use crate::random_position::generate_random_positions;
+use crate::random_position::PositionFilter;
+use crate::random_position::PositionSource;
+use crate::random_position::DEFAULT_MAX_PLIES;
+use crate::random_position::DEFAULT_MIN_PLIES;
+use crate::render::View;
+use crate::rng::random_seed;
+use crate::rules::parse_connectivity;
+use crate::rules::parse_start_layout;
+use crate::rules::Ruleset;
+use crate::search::think;
+use crate::search::think_with_limits;
+use crate::search::SearchLimits;
+use crate::shape_index::ShapeIndex;
+use crate::state::GameState;
+use crate::tablebase::get_tablebases;
+use crate::tablebase::parse_region;
+use crate::tablebase::set_tablebases;
+use crate::tablebase::solve;
+use crate::tablebase::Outcome;
+use crate::tablebase::Subset;
+use crate::tablebase::Tablebase;
+use crate::tablebase::DEFAULT_MAX_POSITIONS;
+use crate::test_suite::format_test_report;
+use crate::test_suite::read_test_suite;
+use crate::test_suite::run_test_suite;
+use crate::theme::find_theme;
+use crate::theme::resolve_theme;
+use crate::theme::set_theme;
+use crate::theme::ColorMode;
+use crate::theme::Theme;
+use crate::theme::CLASSIC;
+use crate::valid_board_gen::generate_valid_boards;
+use crate::valid_board_gen::load_valid_boards;
+use crate::valid_board_gen::BoardGenerator;
+use crate::valid_board_gen::VALID_BOARDS_PATH;
+
+pub const DEFAULT_MAX_SEARCH_DEPTH: u32 = 20;
+pub const DEFAULT_MIN_SEARCH_TIME: u32 = 1000;
+pub const DEFAULT_SELFPLAY_MAX_MOVES: u32 = 200;
+
+pub const USAGE: &str = "\
+Usage: uisge [OPTIONS] [COMMAND]
+
+Commands:
+  play                      Play in the terminal (default)
+  analyze <position>        Search the position and print the best move
+  batch <file>              Analyze every position of a file (one notation per line, optional '; label')
+  perft <depth>             Count the leaf nodes of the move tree up to depth
+  selfplay                  Let the computer play against itself
+  suite <file>              Run a test suite of best move puzzles, e.g. suites/tactics.suite
+  engine                    Speak the line based engine protocol on stdin/stdout (also --engine)
+  build-book <file>         Build an opening book by analyzing all moves of the first plies
+  solve <file>              Solve all positions within a region into a tablebase file
+  prove                     Try to prove that a side can force a win with proof-number search
+  verify-proof <file>       Check a proof written by prove with the move generator
+  generate-boards [file]    Enumerate all connected boards into a file [default: connected_boards.dat]
+  index-boards <boards> <index>
+                            Number the boards of a generate-boards file densely in an index file
+  random-positions <count>  Print random legal positions, one notation per line, e.g. for batch
+
+Options:
+  -d, --max-search-depth <n>  Maximum search depth [env: MAX_SEARCH_DEPTH] [default: 20]
+  -t, --min-search-time <ms>  Minimum search time [env: MIN_SEARCH_TIME] [default: 1000]
+  -p, --position <position>   Start from this position instead of the initial one
+  -m, --mode <mode>           Game mode for play: black, white or both (skips the menu)
+  -u, --ui <ui>               Interface for play: full (full screen) or line [default: full on terminals]
+  -c, --clock <time control>  Play with clocks, minutes per game and seconds per move,
+                              e.g. 5 (sudden death), 3+2 (increment) or 40/90+30 (moves per period)
+  -l, --level <level>         Strength of the computer: beginner, casual, intermediate, advanced
+                              or master, or 1..5 [default: master]
+      --seed <n>              Seed for the computer's random move choice [default: random]
+      --save <path>           Save the game with its level and seed when it ends
+      --load <path>           Continue a saved game with its players, level, seed and clock
+      --coach                 Warn after moves that are much worse than the engine's choice
+      --coach-threshold <n>   Score loss that triggers a warning, 1 is about a king [default: 0.5]
+      --flip                  Turn the board around (automatic when playing black)
+      --indices               Show the square indices on the board
+      --theme <theme>         Colours of the board: classic, ocean, light or ascii [default: classic]
+      --color <when>          Use colours: auto, always or never [default: auto, honours NO_COLOR]
+      --no-color              Same as '--color never'
+      --book <path>           Play the first moves from an opening book made with build-book
+      --plies <n>             Number of plies analyzed by build-book [default: 4]
+      --no-ponder             Do not let the computer think while it is your turn
+      --tablebase <path>      Use the exact values of a tablebase made with solve in the search,
+                              and for the moves of solve that leave the region (repeatable)
+      --region <squares>      Rectangle of squares the pieces must stay in for solve, e.g. a1:d4
+                              [default: a1:g6]
+      --min-kings <n>         Kings each side must keep for solve [default: 0]
+      --max-positions <n>     Give up solving beyond this many positions [default: 5000000]
+      --winner <side>         Side whose win prove tries to show: white or black
+                              [default: the side to move]
+      --max-nodes <n>         Stop prove after this many nodes, including resumed runs
+                              [default: no limit]
+      --table-size <n>        Positions prove keeps in memory [default: 10000000]
+      --checkpoint <path>     Resume prove from this file and save its table there regularly
+      --checkpoint-interval <s>  Seconds between checkpoints [default: 600]
+      --proof <path>          Write the proof tree found by prove
+      --multi-pv <n>          Number of ranked lines shown when analyzing [default: 1]
+      --max-moves <n>         Move limit for selfplay [default: 200]
+      --json <path>           Also write the batch report as JSON
+      --csv <path>            Also write the batch report as CSV
+      --canonical             Keep only one of the mirrored boards for generate-boards
+      --generator <generator> How generate-boards finds the boards: combinations (tests all choices
+                              of squares in parallel, resumes from <file>.parts) or polyominoes
+                              (grows the connected shapes directly) [default: combinations]
+      --board <width>x<height>  Play on a board of another size, up to 26x9 and 64 squares
+                              [default: 7x6]
+      --pieces <n>            Pieces of each side, 1..7, a side wins with all of them kings
+                              [default: 6]
+      --connectivity <n>      Pieces form a group through their 4 edges or also their corners (8)
+                              [default: 4]
+      --kings-to-win <n>      Kings that win the game [default: all pieces of a side]
+      --no-king-steps         Kings only move by jumping
+      --min-plies <n>         Fewest random moves random-positions plays from the position [default: 4]
+      --max-plies <n>         Most random moves random-positions plays [default: 40]
+      --shapes <file>         Let random-positions colour random boards of a generate-boards file
+                              instead of playing random moves
+      --min-moves <n>         Moves the side to move must have in random positions [default: 1]
+      --allow-won             Also give random positions in which a side has won
+      --start <layout>        How the sides share the start squares: block (white below black),
+                              sides (white left of black) or alternating [default: block]
+  -h, --help                  Print this help
+
+Positions are given in the notation also used by the engine protocol, e.g.
+  \"7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0\"";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Play,
+    Analyze,
+    Batch(String),
+    Perft(u32),
+    SelfPlay,
+    Suite(String),
+    Engine,
+    BuildBook(String),
+    Solve(String),
+    Prove,
+    VerifyProof(String),
+    GenerateBoards(String),
+    IndexBoards(String, String),
+    RandomPositions(usize),
+    Help,
+}
+
+#[derive(Debug)]
+pub struct Arguments {
+    pub command: Command,
+    pub max_search_depth: u32,
+    pub min_search_time: u32,
+    pub position: Option<String>,
+    pub game_mode: Option<GameMode>,
+    pub ui_mode: UiMode,
+    pub theme: &'static Theme,
+    pub view: View,
+    pub time_control: Option<TimeControl>,
+    pub coach_threshold: Option<i32>,
+    pub level: Option<Level>,
+    pub seed: Option<u64>,
+    pub save_path: Option<String>,
+    pub load_path: Option<String>,
+    pub multi_pv: usize,
+    pub ponder: bool,
+    pub book_path: Option<String>,
+    pub plies: u32,
+    pub tablebase_paths: Vec<String>,
+    pub subset: Subset,
+    pub max_positions: usize,
+    pub winner_is_white: Option<bool>,
+    pub proof_limits: ProofLimits,
+    pub proof_path: Option<String>,
+    pub max_moves: u32,
+    pub json_output: Option<String>,
+    pub csv_output: Option<String>,
+    pub canonical_only: bool,
+    pub board_generator: BoardGenerator,
+    pub geometry: BoardGeometry,
+    pub min_plies: u32,
+    pub max_plies: u32,
+    pub shapes_path: Option<String>,
+    pub position_filter: PositionFilter,
+}
+
+pub fn parse_arguments(args: &[String]) -> Result<Arguments, String> {
+    let mut command = None;
+    let mut positionals = Vec::new();
+    let mut max_search_depth = None;
+    let mut min_search_time = None;
+    let mut position = None;
+    let mut game_mode = None;
+    let mut ui_mode = None;
+    let mut theme = &CLASSIC;
+    let mut color_mode = ColorMode::Auto;
+    let mut view = View::default();
+    let mut time_control = None;
+    let mut is_coach_enabled = false;
+    let mut coach_threshold = DEFAULT_COACH_THRESHOLD;
+    let mut level = None;
+    let mut seed = None;
+    let mut save_path = None;
+    let mut load_path = None;
+    let mut multi_pv = 1;
+    let mut ponder = true;
+    let mut book_path = None;
+    let mut plies = DEFAULT_BOOK_PLIES;
+    let mut tablebase_paths = Vec::new();
+    let mut subset = Subset::default();
+    let mut max_positions = DEFAULT_MAX_POSITIONS;
+    let mut winner_is_white = None;
+    let mut proof_limits = ProofLimits::default();
+    let mut proof_path = None;
+    let mut max_moves = DEFAULT_SELFPLAY_MAX_MOVES;
+    let mut json_output = None;
+    let mut csv_output = None;
+    let mut canonical_only = false;
+    let mut board_generator = BoardGenerator::Combinations;
+    let mut board_size = (BOARD_WIDTH, BOARD_HEIGHT);
+    let mut pieces_per_side = PIECES_PER_SIDE;
+    let mut rules = Ruleset::default();
+    let mut min_plies = DEFAULT_MIN_PLIES;
+    let mut max_plies = DEFAULT_MAX_PLIES;
+    let mut shapes_path = None;
+    let mut position_filter = PositionFilter::default();
+    let mut is_engine = false;
+    let mut options = Vec::new();
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        // both "--option value" and "--option=value" are accepted
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or(format!("missing value for '{name}'"))
+        };
+
+        if name.starts_with('-') {
+            options.push(name.to_string());
+        }
+
+        match name {
+            "-h" | "--help" => command = Some(Command::Help),
+            "--engine" => is_engine = true,
+            "-d" | "--max-search-depth" => {
+                max_search_depth = Some(parse_number(name, &value()?)?);
+            }
+            "-t" | "--min-search-time" => {
+                min_search_time = Some(parse_number(name, &value()?)?);
+            }
+            "-p" | "--position" => position = Some(value()?),
+            "-m" | "--mode" => game_mode = Some(parse_game_mode(&value()?)?),
+            "-u" | "--ui" => ui_mode = Some(parse_ui_mode(&value()?)?),
+            "-c" | "--clock" => time_control = Some(value()?.parse::<TimeControl>()?),
+            "-l" | "--level" => level = Some(value()?.parse::<Level>()?),
+            "--seed" => {
+                let value = value()?;
+                seed = Some(value.parse::<u64>().map_err(|_| {
+                    format!("invalid value '{value}' for '{name}', expected a non-negative integer")
+                })?);
+            }
+            "--save" => save_path = Some(value()?),
+            "--load" => load_path = Some(value()?),
+            "--coach" => is_coach_enabled = true,
+            "--coach-threshold" => {
+                is_coach_enabled = true;
+                coach_threshold = parse_score(name, &value()?)?;
+            }
+            "--flip" => view.is_flipped = true,
+            "--indices" => view.show_indices = true,
+            "--theme" => theme = parse_theme(&value()?)?,
+            "--color" => color_mode = parse_color_mode(&value()?)?,
+            "--no-color" => color_mode = ColorMode::Never,
+            "--no-ponder" => ponder = false,
+            "--book" => book_path = Some(value()?),
+            "--plies" => plies = parse_number(name, &value()?)?,
+            "--tablebase" => tablebase_paths.push(value()?),
+            "--region" => subset.region = parse_region(&value()?)?,
+            "--min-kings" => subset.min_kings = parse_number(name, &value()?)? as usize,
+            "--max-positions" => max_positions = parse_number(name, &value()?)? as usize,
+            "--winner" => winner_is_white = Some(parse_winner(&value()?)?),
+            "--max-nodes" => {
+                let value = value()?;
+                proof_limits.max_nodes = Some(value.parse::<u64>().map_err(|_| {
+                    format!("invalid value '{value}' for '{name}', expected a non-negative integer")
+                })?);
+            }
+            "--table-size" => proof_limits.max_entries = parse_number(name, &value()?)? as usize,
+            "--checkpoint" => proof_limits.checkpoint_path = Some(value()?),
+            "--checkpoint-interval" => {
+                proof_limits.checkpoint_interval =
+                    Duration::from_secs(parse_number(name, &value()?)?.into());
+            }
+            "--proof" => proof_path = Some(value()?),
+            "--multi-pv" => multi_pv = parse_number(name, &value()?)? as usize,
+            "--max-moves" => max_moves = parse_number(name, &value()?)?,
+            "--json" => json_output = Some(value()?),
+            "--csv" => csv_output = Some(value()?),
+            "--canonical" => canonical_only = true,
+            "--generator" => board_generator = parse_board_generator(&value()?)?,
+            "--board" => board_size = parse_board_size(&value()?)?,
+            "--pieces" => pieces_per_side = parse_number(name, &value()?)? as usize,
+            "--connectivity" => rules.connectivity = parse_connectivity(&value()?)?,
+            "--kings-to-win" => {
+                rules.kings_to_win = Some(parse_number(name, &value()?)? as usize);
+            }
+            "--no-king-steps" => rules.king_steps = false,
+            "--start" => rules.start_layout = parse_start_layout(&value()?)?,
+            "--min-plies" => min_plies = parse_number(name, &value()?)?,
+            "--max-plies" => max_plies = parse_number(name, &value()?)?,
+            "--shapes" => shapes_path = Some(value()?),
+            "--min-moves" => position_filter.min_moves = parse_number(name, &value()?)? as usize,
+            "--allow-won" => position_filter.allow_won = true,
+            _ if name.starts_with('-') => return Err(format!("unknown option '{name}'")),
+            _ => positionals.push(arg.clone()),
+        }
+    }
+
+    let mut positionals = positionals.into_iter();
+
+    if command == Some(Command::Help) {
+        positionals = Vec::new().into_iter();
+    } else {
+        let name = positionals.next();
+
+        // '--engine' is kept from before there were subcommands
+        if is_engine && !matches!(name.as_deref(), None | Some("engine")) {
+            return Err(format!(
+                "'--engine' cannot be combined with '{}'",
+                name.unwrap()
+            ));
+        }
+
+        command = match name.as_deref() {
+            None if is_engine => Some(Command::Engine),
+            None | Some("play") => Some(Command::Play),
+            Some("analyze") => {
+                let p = positionals
+                    .next()
+                    .ok_or("missing position for 'analyze'")?;
+                if position.is_some() {
+                    return Err("the position for 'analyze' was given twice".to_string());
+                }
+                position = Some(p);
+                Some(Command::Analyze)
+            }
+            Some("batch") => {
+                let path = positionals
+                    .next()
+                    .ok_or("missing position file for 'batch'")?;
+                Some(Command::Batch(path))
+            }
+            Some("perft") => {
+                let depth = positionals
+                    .next()
+                    .ok_or("missing depth for 'perft'")?;
+                Some(Command::Perft(parse_number("perft", &depth)?))
+            }
+            Some("selfplay") => Some(Command::SelfPlay),
+            Some("suite") => {
+                let path = positionals
+                    .next()
+                    .ok_or("missing test suite file for 'suite'")?;
+                Some(Command::Suite(path))
+            }
+            Some("engine") => Some(Command::Engine),
+            Some("build-book") => {
+                let path = positionals
+                    .next()
+                    .ok_or("missing book file for 'build-book'")?;
+                Some(Command::BuildBook(path))
+            }
+            Some("solve") => {
+                let path = positionals
+                    .next()
+                    .ok_or("missing tablebase file for 'solve'")?;
+                Some(Command::Solve(path))
+            }
+            Some("prove") => Some(Command::Prove),
+            Some("verify-proof") => {
+                let path = positionals
+                    .next()
+                    .ok_or("missing proof file for 'verify-proof'")?;
+                Some(Command::VerifyProof(path))
+            }
+            Some("generate-boards") => {
+                let path = positionals
+                    .next()
+                    .unwrap_or(VALID_BOARDS_PATH.to_string());
+                Some(Command::GenerateBoards(path))
+            }
+            Some("index-boards") => {
+                let boards_path = positionals
+                    .next()
+                    .ok_or("missing boards file for 'index-boards'")?;
+                let index_path = positionals
+                    .next()
+                    .ok_or("missing index file for 'index-boards'")?;
+                Some(Command::IndexBoards(boards_path, index_path))
+            }
+            Some("random-positions") => {
+                let count = positionals
+                    .next()
+                    .ok_or("missing count for 'random-positions'")?;
+                Some(Command::RandomPositions(
+                    parse_number("random-positions", &count)? as usize,
+                ))
+            }
+            Some(c) => return Err(format!("unknown command '{c}'")),
+        };
+    }
+
+    if let Some(p) = positionals.next() {
+        return Err(format!("unexpected argument '{p}'"));
+    }
+
+    let command = command.unwrap();
+    check_options(&command, &options)?;
+
+    let max_search_depth = match max_search_depth {
+        Some(d) => d,
+        None => read_env_number("MAX_SEARCH_DEPTH", DEFAULT_MAX_SEARCH_DEPTH)?,
+    };
+
+    let min_search_time = match min_search_time {
+        Some(t) => t,
+        None => read_env_number("MIN_SEARCH_TIME", DEFAULT_MIN_SEARCH_TIME)?,
+    };
+
+    if max_search_depth == 0 {
+        return Err("the maximum search depth must be at least 1".to_string());
+    }
+
+    if proof_limits.max_entries == 0 {
+        return Err("the table size must be at least 1".to_string());
+    }
+
+    if multi_pv == 0 {
+        return Err("the number of lines must be at least 1".to_string());
+    }
+
+    let geometry = BoardGeometry::new(board_size.0, board_size.1, pieces_per_side, rules)?;
+
+    Ok(Arguments {
+        command,
+        max_search_depth,
+        min_search_time,
+        position,
+        game_mode,
+        ui_mode: ui_mode.unwrap_or_else(detect_ui_mode),
+        theme: resolve_theme(theme, color_mode),
+        view,
+        time_control,
+        coach_threshold: is_coach_enabled.then_some(coach_threshold),
+        level,
+        seed,
+        save_path,
+        load_path,
+        multi_pv,
+        ponder,
+        book_path,
+        plies,
+        tablebase_paths,
+        subset,
+        max_positions,
+        winner_is_white,
+        proof_limits,
+        proof_path,
+        max_moves,
+        json_output,
+        csv_output,
+        canonical_only,
+        board_generator,
+        geometry,
+        min_plies,
+        max_plies,
+        shapes_path,
+        position_filter,
+    })
+}
+
+pub fn run_command(arguments: Arguments) -> Result<(), String> {
+    // the position is read on the chosen board
+    check_geometry(&arguments)?;
+    set_geometry(arguments.geometry.clone());
+
+    let mut game_state = match &arguments.position {
+        Some(notation) => GameState::from_notation(notation)?,
+        None => GameState::new(),
+    };
+
+    set_theme(arguments.theme);
+
+    if !arguments.tablebase_paths.is_empty() {
+        set_tablebases(
+            arguments
+                .tablebase_paths
+                .iter()
+                .map(|path| Tablebase::load(path))
+                .collect::<Result<_, _>>()?,
+        );
+    }
+
+    match arguments.command {
+        Command::Help => println!("{USAGE}"),
+        Command::Play => run_play(&mut game_state, &arguments)?,
+        Command::Analyze => {
+            println!("{game_state}");
+            let mut limits =
+                SearchLimits::new(arguments.max_search_depth, arguments.min_search_time);
+            limits.multi_pv = arguments.multi_pv;
+            think_with_limits(&game_state, &limits);
+        }
+        Command::Batch(ref path) => run_batch(path, &arguments)?,
+        Command::Perft(depth) => run_perft(&game_state, depth),
+        Command::SelfPlay => run_selfplay(&mut game_state, &arguments)?,
+        Command::Suite(ref path) => {
+            let positions = read_test_suite(path)?;
+            let limits = SearchLimits::new(arguments.max_search_depth, arguments.min_search_time);
+
+            print!(
+                "{}",
+                format_test_report(&run_test_suite(&positions, &limits))
+            );
+        }
+        Command::Engine => start_protocol(arguments.max_search_depth, arguments.min_search_time),
+        Command::BuildBook(ref path) => run_build_book(&game_state, path, &arguments)?,
+        Command::Solve(ref path) => run_solve(path, &arguments)?,
+        Command::Prove => run_prove(&game_state, &arguments)?,
+        Command::VerifyProof(ref path) => {
+            let proof = Proof::load(path)?;
+            let positions = proof.verify()?;
+            println!(
+                "the proof that {} is valid, it covers {positions} positions",
+                proof.get_winner()
+            );
+        }
+        Command::GenerateBoards(ref path) => {
+            generate_valid_boards(path, arguments.board_generator, arguments.canonical_only)?
+        }
+        Command::IndexBoards(ref boards_path, ref index_path) => {
+            run_index_boards(boards_path, index_path)?
+        }
+        Command::RandomPositions(count) => run_random_positions(&game_state, count, &arguments)?,
+    }
+
+    Ok(())
+}
+
+// Tablebases, proofs and board files number the squares of the standard
+// board, so they are only made and used there.
+fn check_geometry(arguments: &Arguments) -> Result<(), String> {
+    if arguments.geometry.is_standard() {
+        return Ok(());
+    }
+
+    let name = match arguments.command {
+        Command::Solve(_) => "solve",
+        Command::Prove => "prove",
+        Command::VerifyProof(_) => "verify-proof",
+        Command::GenerateBoards(_) => "generate-boards",
+        Command::IndexBoards(..) => "index-boards",
+        Command::RandomPositions(_) if arguments.shapes_path.is_some() => "--shapes",
+        _ if !arguments.tablebase_paths.is_empty() => "--tablebase",
+        _ => return Ok(()),
+    };
+
+    Err(format!(
+        "'{name}' only works with the standard rules on the board of 7x6 squares with 6 pieces each"
+    ))
+}
+
+// options given on the command line take precedence over a loaded game
+fn run_play(game_state: &mut GameState, arguments: &Arguments) -> Result<(), String> {
+    let mut game_mode = arguments.game_mode;
+    let mut level = arguments.level;
+    let mut seed = arguments.seed;
+    let mut time_control = arguments.time_control;
+
+    if let Some(path) = &arguments.load_path {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("could not read '{path}': {error}"))?;
+        let record = GameRecord::from_text(&text)?;
+
+        *game_state = record.replay()?;
+        game_mode = game_mode.or(Some(record.game_mode));
+        level = level.or(Some(record.level));
+        seed = seed.or(Some(record.seed));
+        time_control = time_control.or(record.time_control);
+    }
+
+    let seed = seed.unwrap_or_else(random_seed);
+    load_book(arguments, seed)?;
+
+    start_gui(
+        game_state,
+        game_mode,
+        Configuration {
+            game_mode: GameMode::NoComputer,
+            max_search_depth: arguments.max_search_depth,
+            min_search_time: arguments.min_search_time,
+            ui_mode: arguments.ui_mode,
+            view: arguments.view,
+            time_control,
+            coach_threshold: arguments.coach_threshold,
+            level: level.unwrap_or(Level::Master),
+            seed,
+            save_path: arguments.save_path.clone(),
+            multi_pv: arguments.multi_pv,
+            ponder: arguments.ponder,
+        },
+    );
+
+    Ok(())
+}
+
+// the same seed also makes the choices from the book reproducible
+fn load_book(arguments: &Arguments, seed: u64) -> Result<(), String> {
+    if let Some(path) = &arguments.book_path {
+        set_book(Book::load(path)?, seed);
+    }
+
+    Ok(())
+}
+
+fn run_build_book(game_state: &GameState, path: &str, arguments: &Arguments) -> Result<(), String> {
+    let limits = SearchLimits::new(arguments.max_search_depth, arguments.min_search_time);
+
+    let book = build_book(
+        game_state,
+        arguments.plies,
+        &limits,
+        &mut |game_state, moves| {
+            let moves: Vec<String> = moves
+                .iter()
+                .map(|book_move| format!("{}:{}", book_move.mov, book_move.weight))
+                .collect();
+
+            println!("{:<34} {}", game_state.to_notation(), moves.join(" "));
+        },
+    );
+
+    book.write(path)?;
+    println!("{} positions written to {path}", book.len());
+
+    Ok(())
+}
+
+fn run_solve(path: &str, arguments: &Arguments) -> Result<(), String> {
+    let start_instant = Instant::now();
+    let tablebase = solve(&arguments.subset, get_tablebases(), arguments.max_positions)?;
+    let elapsed = start_instant.elapsed().as_millis();
+
+    let (mut wins, mut losses, mut draws) = (0, 0, 0);
+    for outcome in tablebase.get_outcomes() {
+        match outcome {
+            Outcome::Win(_) => wins += 1,
+            Outcome::Loss(_) => losses += 1,
+            Outcome::Draw => draws += 1,
+        }
+    }
+
+    tablebase.write(path)?;
+    println!(
+        "{} positions solved in {elapsed} ms ({wins} wins, {losses} losses, {draws} draws), written to {path}",
+        tablebase.len()
+    );
+
+    Ok(())
+}
+
+fn run_prove(game_state: &GameState, arguments: &Arguments) -> Result<(), String> {
+    let winner_is_white = arguments
+        .winner_is_white
+        .unwrap_or(game_state.is_active_player_white);
+    let winner = if winner_is_white { "white" } else { "black" };
+
+    println!("{game_state}");
+    println!("trying to prove that {winner} wins");
+
+    let outcome = prove_win(
+        game_state,
+        winner_is_white,
+        &arguments.proof_limits,
+        &mut |progress| {
+            println!(
+                "{:>8.1} s {:>12} nodes {:>10} positions  proof {} disproof {}",
+                progress.elapsed.as_secs_f64(),
+                progress.nodes,
+                progress.entries,
+                format_proof_number(progress.proof_number),
+                format_proof_number(progress.disproof_number),
+            );
+        },
+    )?;
+
+    match outcome {
+        ProofOutcome::Proven(proof) => {
+            println!("proven: {winner} wins, the proof has {} moves", proof.len());
+            if let Some(path) = &arguments.proof_path {
+                proof.write(path)?;
+                println!("proof written to {path}");
+            }
+        }
+        ProofOutcome::UnverifiedDisproof => {
+            println!("not proven (unverified disproof): no forced win for {winner} was found");
+        }
+        ProofOutcome::Unknown => {
+            println!("undecided, run again with a checkpoint to continue");
+        }
+    }
+
+    Ok(())
+}
+
+// The boards of a boards file are sorted, so their ranks are their positions
+// in it, which is checked in the written index.
+fn run_index_boards(boards_path: &str, index_path: &str) -> Result<(), String> {
+    let boards = load_valid_boards(boards_path)?;
+    ShapeIndex::new(&boards)?.write(index_path)?;
+
+    let index = ShapeIndex::open(index_path)?;
+    for (rank, board) in boards.iter().enumerate() {
+        let rank = rank as u64;
+        if index.get_rank(*board) != Some(rank) || index.get_shape(rank) != Some(*board) {
+            return Err(format!(
+                "{index_path}: board {rank} is not found in the index"
+            ));
+        }
+    }
+
+    println!("{} boards numbered in {index_path}", index.len());
+
+    Ok(())
+}
+
+// solved positions have infinite proof or disproof numbers
+// the positions are reproducible with --seed
+fn run_random_positions(
+    game_state: &GameState,
+    count: usize,
+    arguments: &Arguments,
+) -> Result<(), String> {
+    let source = match &arguments.shapes_path {
+        Some(path) => PositionSource::Shapes(load_valid_boards(path)?),
+        None => PositionSource::Playout {
+            start: game_state.clone(),
+            min_plies: arguments.min_plies,
+            max_plies: arguments.max_plies,
+        },
+    };
+    let seed = arguments
+        .seed
+        .unwrap_or_else(random_seed);
+
+    for game_state in generate_random_positions(&source, &arguments.position_filter, count, seed)? {
+        println!("{}", game_state.to_notation());
+    }
+
+    Ok(())
+}
+
+fn format_proof_number(number: u64) -> String {
+    match number {
+        n if n >= u64::MAX / 4 => "inf".to_string(),
+        n => n.to_string(),
+    }
+}
+
+fn run_batch(path: &str, arguments: &Arguments) -> Result<(), String> {
+    let positions = read_position_file(path)?;
+    let limits = SearchLimits::new(arguments.max_search_depth, arguments.min_search_time);
+
+    let entries = analyze_positions(&positions, &limits);
+
+    print!("{}", format_text(&entries));
+
+    if let Some(json_path) = &arguments.json_output {
+        fs::write(json_path, format_json(&entries))
+            .map_err(|error| format!("could not write '{json_path}': {error}"))?;
+    }
+
+    if let Some(csv_path) = &arguments.csv_output {
+        fs::write(csv_path, format_csv(&entries))
+            .map_err(|error| format!("could not write '{csv_path}': {error}"))?;
+    }
+
+    Ok(())
+}
+
+fn run_perft(game_state: &GameState, depth: u32) {
+    for d in 1..depth + 1 {
+        let start_instant = Instant::now();
+        let nodes = game_state.perft(d);
+        let elapsed = start_instant.elapsed().as_millis();
+
+        println!("perft {d:>2}: {nodes:>15} nodes in {elapsed} ms");
+    }
+}
+
+fn run_selfplay(game_state: &mut GameState, arguments: &Arguments) -> Result<(), String> {
+    load_book(
+        arguments,
+        arguments
+            .seed
+            .unwrap_or_else(random_seed),
+    )?;
+
+    let start_move_count = game_state.move_count;
+
+    loop {
+        println!("*******************************************************");
+        println!("{game_state}");
+
+        if let Some(result) = game_state.get_result() {
+            println!("{result}");
+            return Ok(());
+        }
+
+        if game_state.move_count - start_move_count >= arguments.max_moves {
+            println!("draw by move limit");
+            return Ok(());
+        }
+
+        let mov = think(
+            game_state,
+            arguments.max_search_depth,
+            arguments.min_search_time,
+        );
+        println!("computer moves {mov:?}");
+
+        game_state.make_move(mov);
+    }
+}
+
+// options for the board and the rules and how things look apply to every command
+const GLOBAL_OPTIONS: &[&str] = &[
+    "-h",
+    "--help",
+    "--theme",
+    "--color",
+    "--no-color",
+    "--board",
+    "--pieces",
+    "--connectivity",
+    "--kings-to-win",
+    "--no-king-steps",
+    "--start",
+];
+const SEARCH_OPTIONS: &[&str] = &[
+    "-d",
+    "--max-search-depth",
+    "-t",
+    "--min-search-time",
+    "--tablebase",
+];
+const POSITION_OPTIONS: &[&str] = &["-p", "--position"];
+
+fn get_command_options(command: &Command) -> Vec<&'static str> {
+    let (uses_search, uses_position, options): (bool, bool, &[&str]) = match command {
+        Command::Help => return Vec::new(),
+        Command::Play => (
+            true,
+            true,
+            &[
+                "-m",
+                "--mode",
+                "-u",
+                "--ui",
+                "-c",
+                "--clock",
+                "-l",
+                "--level",
+                "--seed",
+                "--save",
+                "--load",
+                "--coach",
+                "--coach-threshold",
+                "--flip",
+                "--indices",
+                "--no-ponder",
+                "--book",
+                "--multi-pv",
+            ],
+        ),
+        Command::Analyze => (true, true, &["--multi-pv"]),
+        Command::Batch(_) => (true, false, &["--json", "--csv"]),
+        Command::Perft(_) => (false, true, &[]),
+        Command::SelfPlay => (true, true, &["--seed", "--book", "--max-moves"]),
+        Command::Suite(_) => (true, false, &[]),
+        Command::Engine => (true, false, &["--engine"]),
+        Command::BuildBook(_) => (true, true, &["--plies"]),
+        Command::Solve(_) => (
+            false,
+            false,
+            &["--region", "--min-kings", "--max-positions", "--tablebase"],
+        ),
+        Command::Prove => (
+            false,
+            true,
+            &[
+                "--winner",
+                "--max-nodes",
+                "--table-size",
+                "--checkpoint",
+                "--checkpoint-interval",
+                "--proof",
+            ],
+        ),
+        Command::VerifyProof(_) => (false, false, &[]),
+        Command::GenerateBoards(_) => (false, false, &["--canonical", "--generator"]),
+        Command::IndexBoards(..) => (false, false, &[]),
+        Command::RandomPositions(_) => (
+            false,
+            true,
+            &[
+                "--seed",
+                "--min-plies",
+                "--max-plies",
+                "--shapes",
+                "--min-moves",
+                "--allow-won",
+            ],
+        ),
+    };
+
+    let mut command_options = options.to_vec();
+    if uses_search {
+        command_options.extend_from_slice(SEARCH_OPTIONS);
+    }
+    if uses_position {
+        command_options.extend_from_slice(POSITION_OPTIONS);
+    }
+
+    command_options
+}
+
+// options that the command would silently ignore are mistakes
+fn check_options(command: &Command, options: &[String]) -> Result<(), String> {
+    if *command == Command::Help {
+        return Ok(());
+    }
+
+    let command_options = get_command_options(command);
+
+    match options.iter().find(|option| {
+        !GLOBAL_OPTIONS.contains(&option.as_str()) && !command_options.contains(&option.as_str())
+    }) {
+        Some(option) => Err(format!(
+            "'{option}' cannot be used with '{}'",
+            get_command_name(command)
+        )),
+        None => Ok(()),
+    }
+}
+
+fn get_command_name(command: &Command) -> &'static str {
+    match command {
+        Command::Play => "play",
+        Command::Analyze => "analyze",
+        Command::Batch(_) => "batch",
+        Command::Perft(_) => "perft",
+        Command::SelfPlay => "selfplay",
+        Command::Suite(_) => "suite",
+        Command::Engine => "engine",
+        Command::BuildBook(_) => "build-book",
+        Command::Solve(_) => "solve",
+        Command::Prove => "prove",
+        Command::VerifyProof(_) => "verify-proof",
+        Command::GenerateBoards(_) => "generate-boards",
+        Command::IndexBoards(..) => "index-boards",
+        Command::RandomPositions(_) => "random-positions",
+        Command::Help => "help",
+    }
+}
+
+fn parse_number(name: &str, value: &str) -> Result<u32, String> {
+    value.parse::<u32>().map_err(|_| {
+        format!("invalid value '{value}' for '{name}', expected a non-negative integer")
+    })
+}
+
+// scores are entered like they are shown, in units of SCORE_STEP
+fn parse_score(name: &str, value: &str) -> Result<i32, String> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|score| *score >= 0.0 && score.is_finite())
+        .map(|score| (score * SCORE_STEP as f32) as i32)
+        .ok_or(format!(
+            "invalid value '{value}' for '{name}', expected a non-negative number"
+        ))
+}
+
+fn parse_winner(value: &str) -> Result<bool, String> {
+    match value {
+        "w" | "white" => Ok(true),
+        "b" | "black" => Ok(false),
+        _ => Err(format!("invalid winner '{value}', expected white or black")),
+    }
+}
+
+fn parse_board_generator(value: &str) -> Result<BoardGenerator, String> {
+    match value {
+        "combinations" => Ok(BoardGenerator::Combinations),
+        "polyominoes" => Ok(BoardGenerator::Polyominoes),
+        _ => Err(format!(
+            "invalid generator '{value}', expected combinations or polyominoes"
+        )),
+    }
+}
+
+fn parse_game_mode(value: &str) -> Result<GameMode, String> {
+    match value {
+        "b" | "black" => Ok(GameMode::ComputerWhite),
+        "w" | "white" => Ok(GameMode::ComputerBlack),
+        "h" | "both" => Ok(GameMode::NoComputer),
+        _ => Err(format!(
+            "invalid game mode '{value}', expected black, white or both"
+        )),
+    }
+}
+
+fn parse_ui_mode(value: &str) -> Result<UiMode, String> {
+    match value {
+        "full" => Ok(UiMode::Full),
+        "line" => Ok(UiMode::Line),
+        _ => Err(format!("invalid ui '{value}', expected full or line")),
+    }
+}
+
+fn parse_theme(value: &str) -> Result<&'static Theme, String> {
+    find_theme(value).ok_or(format!(
+        "invalid theme '{value}', expected classic, ocean, light or ascii"
+    ))
+}
+
+fn parse_color_mode(value: &str) -> Result<ColorMode, String> {
+    match value {
+        "auto" => Ok(ColorMode::Auto),
+        "always" => Ok(ColorMode::Always),
+        "never" => Ok(ColorMode::Never),
+        _ => Err(format!(
+            "invalid color mode '{value}', expected auto, always or never"
+        )),
+    }
+}
+
+// the full screen interface needs an interactive terminal which understands escape sequences
+fn detect_ui_mode() -> UiMode {
+    let is_dumb = std::env::var("TERM").map_or(true, |term| term == "dumb");
+
+    if !is_dumb && std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        UiMode::Full
+    } else {
+        UiMode::Line
+    }
+}
+
+// the environment variables are still supported for backwards compatibility,
+// command line options take precedence
+fn read_env_number(name: &str, default: u32) -> Result<u32, String> {
+    match std::env::var(name) {
+        Ok(value) => parse_number(name, &value),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proof::DEFAULT_MAX_ENTRIES;
+    use crate::rules::Connectivity;
+    use crate::rules::StartLayout;
+
+    fn parse(args: &str) -> Result<Arguments, String> {
+        let args: Vec<String> = args
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+        parse_arguments(&args)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse("").unwrap().command, Command::Play);
+        assert_eq!(parse("play").unwrap().command, Command::Play);
+        assert_eq!(parse("perft 5").unwrap().command, Command::Perft(5));
+        assert_eq!(parse("selfplay").unwrap().command, Command::SelfPlay);
+        assert_eq!(
+            parse("batch positions.txt")
+                .unwrap()
+                .command,
+            Command::Batch("positions.txt".to_string())
+        );
+        assert_eq!(parse("engine").unwrap().command, Command::Engine);
+        assert_eq!(parse("--engine").unwrap().command, Command::Engine);
+        assert_eq!(
+            parse("engine --engine -d 5")
+                .unwrap()
+                .command,
+            Command::Engine
+        );
+        assert_eq!(
+            parse("generate-boards")
+                .unwrap()
+                .command,
+            Command::GenerateBoards(VALID_BOARDS_PATH.to_string())
+        );
+        let arguments = parse("generate-boards canonical.dat --canonical").unwrap();
+        assert_eq!(
+            arguments.command,
+            Command::GenerateBoards("canonical.dat".to_string())
+        );
+        assert!(arguments.canonical_only);
+        assert_eq!(arguments.board_generator, BoardGenerator::Combinations);
+        assert_eq!(
+            parse("generate-boards --generator polyominoes")
+                .unwrap()
+                .board_generator,
+            BoardGenerator::Polyominoes
+        );
+        assert!(parse("generate-boards --generator random").is_err());
+        assert_eq!(
+            parse("index-boards boards.dat boards.idx")
+                .unwrap()
+                .command,
+            Command::IndexBoards("boards.dat".to_string(), "boards.idx".to_string())
+        );
+        assert!(parse("index-boards boards.dat").is_err());
+        let arguments =
+            parse("random-positions 100 --min-plies 2 --max-plies 8 --min-moves 3").unwrap();
+        assert_eq!(arguments.command, Command::RandomPositions(100));
+        assert_eq!((arguments.min_plies, arguments.max_plies), (2, 8));
+        assert_eq!(arguments.position_filter.min_moves, 3);
+        assert!(!arguments.position_filter.allow_won);
+        let arguments = parse("random-positions 5 --shapes boards.dat --allow-won").unwrap();
+        assert_eq!(arguments.shapes_path, Some("boards.dat".to_string()));
+        assert!(arguments.position_filter.allow_won);
+        assert!(check_geometry(&parse("random-positions 5 --pieces 5").unwrap()).is_ok());
+        assert!(
+            check_geometry(&parse("random-positions 5 --pieces 5 --shapes b.dat").unwrap())
+                .is_err()
+        );
+        assert_eq!(
+            parse("build-book book.txt --plies 6")
+                .unwrap()
+                .command,
+            Command::BuildBook("book.txt".to_string())
+        );
+        assert_eq!(
+            parse("solve small.tb --region a1:d4 --min-kings 1")
+                .unwrap()
+                .command,
+            Command::Solve("small.tb".to_string())
+        );
+        assert_eq!(parse("prove").unwrap().command, Command::Prove);
+        assert_eq!(
+            parse("verify-proof proof.txt")
+                .unwrap()
+                .command,
+            Command::VerifyProof("proof.txt".to_string())
+        );
+        assert_eq!(parse("--help").unwrap().command, Command::Help);
+        assert_eq!(parse("perft 5 -h").unwrap().command, Command::Help);
+
+        let arguments = parse("analyze 7/2PP3/1PPPP2/2pppp1/3pp2/7").unwrap();
+        assert_eq!(arguments.command, Command::Analyze);
+        assert_eq!(
+            arguments.position,
+            Some("7/2PP3/1PPPP2/2pppp1/3pp2/7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let arguments = parse("play -d 7 --min-search-time=250 --mode black").unwrap();
+        assert_eq!(arguments.max_search_depth, 7);
+        assert_eq!(arguments.min_search_time, 250);
+        assert_eq!(arguments.game_mode, Some(GameMode::ComputerWhite));
+
+        let arguments = parse("--ui line").unwrap();
+        assert_eq!(arguments.ui_mode, UiMode::Line);
+        let arguments = parse("-u full").unwrap();
+        assert_eq!(arguments.ui_mode, UiMode::Full);
+
+        let arguments = parse("--theme ocean --color always").unwrap();
+        assert_eq!(arguments.theme.name, "ocean");
+        let arguments = parse("--theme=light --no-color").unwrap();
+        assert_eq!(arguments.theme.name, "ascii");
+        assert_eq!(arguments.view, View::default());
+
+        let arguments = parse("play --clock 3+2").unwrap();
+        assert_eq!(arguments.time_control, Some("3+2".parse().unwrap()));
+
+        assert_eq!(arguments.coach_threshold, None);
+        let arguments = parse("--coach").unwrap();
+        assert_eq!(arguments.coach_threshold, Some(DEFAULT_COACH_THRESHOLD));
+        let arguments = parse("--coach-threshold 2").unwrap();
+        assert_eq!(arguments.coach_threshold, Some(2 * SCORE_STEP));
+
+        let arguments = parse("--level casual --seed 42 --save game.txt").unwrap();
+        assert_eq!(arguments.level, Some(Level::Casual));
+        assert_eq!(arguments.seed, Some(42));
+        assert_eq!(arguments.save_path, Some("game.txt".to_string()));
+        assert_eq!(parse("-l 1").unwrap().level, Some(Level::Beginner));
+        assert_eq!(
+            parse("--load game.txt")
+                .unwrap()
+                .load_path,
+            Some("game.txt".to_string())
+        );
+
+        let arguments = parse("--flip --indices").unwrap();
+        assert!(arguments.view.is_flipped);
+        assert!(arguments.view.show_indices);
+
+        assert_eq!(parse("").unwrap().multi_pv, 1);
+        assert!(parse("").unwrap().ponder);
+        assert!(!parse("--no-ponder").unwrap().ponder);
+        assert_eq!(
+            parse("--book book.txt")
+                .unwrap()
+                .book_path,
+            Some("book.txt".to_string())
+        );
+        assert_eq!(parse("").unwrap().plies, DEFAULT_BOOK_PLIES);
+        assert_eq!(
+            parse("analyze 7/7/7/7/7/7 --multi-pv 3")
+                .unwrap()
+                .multi_pv,
+            3
+        );
+
+        let arguments =
+            parse("solve small.tb --region=d4:a1 --min-kings 2 --max-positions 1000").unwrap();
+        assert_eq!(arguments.subset.region, parse_region("a1:d4").unwrap());
+        assert_eq!(arguments.subset.min_kings, 2);
+        assert_eq!(arguments.max_positions, 1000);
+        assert_eq!(parse("").unwrap().subset, Subset::default());
+        assert_eq!(
+            parse("--tablebase small.tb")
+                .unwrap()
+                .tablebase_paths,
+            vec!["small.tb".to_string()]
+        );
+        assert_eq!(
+            parse("solve large.tb --tablebase kings.tb --tablebase small.tb")
+                .unwrap()
+                .tablebase_paths
+                .len(),
+            2
+        );
+
+        let arguments = parse(
+            "prove --winner black --max-nodes 100 --table-size 50 --checkpoint run.pn \
+             --checkpoint-interval 60 --proof proof.txt",
+        )
+        .unwrap();
+        assert_eq!(arguments.winner_is_white, Some(false));
+        assert_eq!(arguments.proof_limits.max_nodes, Some(100));
+        assert_eq!(arguments.proof_limits.max_entries, 50);
+        assert_eq!(
+            arguments.proof_limits.checkpoint_path,
+            Some("run.pn".to_string())
+        );
+        assert_eq!(
+            arguments
+                .proof_limits
+                .checkpoint_interval,
+            Duration::from_secs(60)
+        );
+        assert_eq!(arguments.proof_path, Some("proof.txt".to_string()));
+        assert_eq!(parse("prove").unwrap().winner_is_white, None);
+        assert_eq!(
+            parse("prove")
+                .unwrap()
+                .proof_limits
+                .max_entries,
+            DEFAULT_MAX_ENTRIES
+        );
+
+        let arguments = parse("selfplay --max-moves 10 -d 4").unwrap();
+        assert_eq!(arguments.max_moves, 10);
+        assert_eq!(arguments.max_search_depth, 4);
+
+        let arguments = parse("batch in.txt --json out.json --csv=out.csv").unwrap();
+        assert_eq!(arguments.json_output, Some("out.json".to_string()));
+        assert_eq!(arguments.csv_output, Some("out.csv".to_string()));
+
+        assert!(parse("play")
+            .unwrap()
+            .geometry
+            .is_standard());
+        let arguments = parse("perft 3 --board 8x8 --pieces 7").unwrap();
+        assert_eq!(
+            (arguments.geometry.width, arguments.geometry.height),
+            (8, 8)
+        );
+        assert_eq!(arguments.geometry.pieces_per_side, 7);
+        assert!(check_geometry(&arguments).is_ok());
+        assert!(check_geometry(&parse("solve small.tb").unwrap()).is_ok());
+        assert!(check_geometry(&parse("solve small.tb --pieces 5").unwrap()).is_err());
+        assert!(check_geometry(&parse("play --board 8x7 --tablebase small.tb").unwrap()).is_err());
+
+        let arguments =
+            parse("play --connectivity 8 --kings-to-win 4 --no-king-steps --start=sides").unwrap();
+        assert_eq!(arguments.geometry.get_kings_to_win(), 4);
+        assert_eq!(
+            arguments.geometry.rules,
+            Ruleset {
+                connectivity: Connectivity::Eight,
+                kings_to_win: Some(4),
+                king_steps: false,
+                start_layout: StartLayout::Sides,
+            }
+        );
+        assert!(check_geometry(&parse("prove --connectivity 8").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("fly").is_err());
+        assert!(parse("play extra").is_err());
+        assert!(parse("perft").is_err());
+        assert!(parse("perft five").is_err());
+        assert!(parse("analyze").is_err());
+        assert!(parse("batch").is_err());
+        assert!(parse("build-book").is_err());
+        assert!(parse("solve").is_err());
+        assert!(parse("verify-proof").is_err());
+        assert!(parse("prove --winner red").is_err());
+        assert!(parse("prove --table-size 0").is_err());
+        assert!(parse("solve small.tb --region a1").is_err());
+        assert!(parse("--depth 5").is_err());
+        assert!(parse("-d").is_err());
+        assert!(parse("-d 0").is_err());
+        assert!(parse("-d -3").is_err());
+        assert!(parse("-t 1s").is_err());
+        assert!(parse("--mode red").is_err());
+        assert!(parse("--ui fancy").is_err());
+        assert!(parse("--theme neon").is_err());
+        assert!(parse("--clock 3-2").is_err());
+        assert!(parse("--coach-threshold -1").is_err());
+        assert!(parse("--color sometimes").is_err());
+        assert!(parse("--level 9").is_err());
+        assert!(parse("--seed -1").is_err());
+        assert!(parse("--multi-pv 0").is_err());
+        assert!(parse("--board 8").is_err());
+        assert!(parse("--board 9x9").is_err());
+        assert!(parse("--pieces 8").is_err());
+        assert!(parse("--board 3x3 --pieces 5").is_err());
+        assert!(parse("--connectivity 6").is_err());
+        assert!(parse("--kings-to-win 0").is_err());
+        assert!(parse("--pieces 4 --kings-to-win 5").is_err());
+        assert!(parse("--start random").is_err());
+        assert!(parse("random-positions").is_err());
+        assert!(parse("random-positions many").is_err());
+        assert!(parse("--engine perft 3").is_err());
+
+        // options the command does not use
+        assert!(parse("perft 3 --book x")
+            .unwrap_err()
+            .contains("'--book' cannot be used with 'perft'"));
+        assert!(parse("batch in.txt --no-ponder").is_err());
+        assert!(parse("selfplay -m both").is_err());
+        assert!(parse("verify-proof p.txt -p 7/2PP3/1PPPP2/2pppp1/3pp2/7").is_err());
+        assert!(parse("perft 3 -p 7/2PP3/1PPPP2/2pppp1/3pp2/7").is_ok());
+        assert!(parse("--help --book x").is_ok());
+        assert!(parse("perft 3 --theme ascii --board 8x8").is_ok());
+    }
+}
@@ -0,0 +1,331 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::state::GameResult;
+
+// the engine plans with this many moves when the time control does not say
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// kept in reserve so that the engine does not lose on time because of overhead
+const SAFETY_MARGIN: u64 = 50;
+
+// Time controls are written like in chess, the times are given in minutes and
+// the increment in seconds:
+//   "5"       5 minutes for the whole game (sudden death)
+//   "3+2"     3 minutes and 2 seconds added after every move
+//   "40/90+30" 90 minutes for every 40 moves and 30 seconds per move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+    // all times are in milliseconds
+    pub base_time: u64,
+    pub increment: u64,
+    pub moves_per_period: Option<u32>,
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error =
+            || format!("invalid time control '{s}', expected e.g. '5', '3+2' or '40/90+30'");
+
+        let (moves_per_period, rest) = match s.split_once('/') {
+            Some((moves, rest)) => (
+                Some(
+                    moves
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|m| *m > 0)
+                        .ok_or_else(error)?,
+                ),
+                rest,
+            ),
+            None => (None, s),
+        };
+
+        let (minutes, seconds) = rest
+            .split_once('+')
+            .unwrap_or((rest, "0"));
+
+        let minutes = minutes
+            .parse::<f64>()
+            .ok()
+            .filter(|m| *m > 0.0 && m.is_finite())
+            .ok_or_else(error)?;
+        let seconds = seconds
+            .parse::<f64>()
+            .ok()
+            .filter(|s| *s >= 0.0 && s.is_finite())
+            .ok_or_else(error)?;
+
+        Ok(Self {
+            base_time: (minutes * 60_000.0) as u64,
+            increment: (seconds * 1000.0) as u64,
+            moves_per_period,
+        })
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(moves) = self.moves_per_period {
+            write!(f, "{moves}/")?;
+        }
+
+        write!(f, "{}", self.base_time as f64 / 60_000.0)?;
+
+        if self.increment > 0 {
+            write!(f, "+{}", self.increment as f64 / 1000.0)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Clock {
+    pub time_control: TimeControl,
+    // remaining time in milliseconds, negative once a side has run out of time
+    pub white_time: i64,
+    pub black_time: i64,
+    white_moves: u32,
+    black_moves: u32,
+    // the side whose clock is running and since when
+    turn: Option<(bool, Instant)>,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Self {
+        Self {
+            time_control,
+            white_time: time_control.base_time as i64,
+            black_time: time_control.base_time as i64,
+            white_moves: 0,
+            black_moves: 0,
+            turn: None,
+        }
+    }
+
+    // starts the clock of the side to move, does nothing if a clock is already running
+    pub fn start_turn(&mut self, is_white: bool) {
+        self.turn
+            .get_or_insert_with(|| (is_white, Instant::now()));
+    }
+
+    // time left for a side, including the running turn
+    pub fn get_remaining(&self, is_white: bool) -> i64 {
+        let time = match is_white {
+            true => self.white_time,
+            false => self.black_time,
+        };
+
+        match self.turn {
+            Some((is_running, start)) if is_running == is_white => {
+                time - start.elapsed().as_millis() as i64
+            }
+            _ => time,
+        }
+    }
+
+    // a side that has run out of time loses the game
+    pub fn get_result(&self) -> Option<GameResult> {
+        if self.get_remaining(true) <= 0 {
+            Some(GameResult::BlackWins)
+        } else if self.get_remaining(false) <= 0 {
+            Some(GameResult::WhiteWins)
+        } else {
+            None
+        }
+    }
+
+    // stops the running clock after a move and adds the increment
+    pub fn finish_turn(&mut self) -> Option<GameResult> {
+        let elapsed = self
+            .turn
+            .map_or(0, |(_, start)| start.elapsed().as_millis() as u64);
+
+        self.finish_turn_after(elapsed)
+    }
+
+    // Thinking time for the engine: the remaining time is shared among the moves
+    // still to be played in the period, most of the increment is used as well.
+    // Returns the soft limit after which no new iteration is started and the hard
+    // limit at which the search is aborted.
+    pub fn allocate_time(&self, is_white: bool) -> (u32, u32) {
+        let remaining = self.get_remaining(is_white).max(0) as u64;
+        let available = remaining.saturating_sub(SAFETY_MARGIN);
+
+        let moves_to_go = match self.time_control.moves_per_period {
+            Some(moves) => moves - self.get_moves(is_white) % moves,
+            None => DEFAULT_MOVES_TO_GO,
+        } as u64;
+
+        let target = (available / moves_to_go + self.time_control.increment * 3 / 4).min(available);
+        let maximum = (target * 3)
+            .min(available / 2)
+            .max(target);
+
+        (target as u32, maximum as u32)
+    }
+
+    pub fn format_status(&self, is_white_to_move: bool) -> String {
+        let marker = |is_white| {
+            if is_white == is_white_to_move {
+                '*'
+            } else {
+                ' '
+            }
+        };
+
+        format!(
+            "{}white {}  {}black {}",
+            marker(true),
+            format_time(self.get_remaining(true)),
+            marker(false),
+            format_time(self.get_remaining(false))
+        )
+    }
+
+    fn get_moves(&self, is_white: bool) -> u32 {
+        match is_white {
+            true => self.white_moves,
+            false => self.black_moves,
+        }
+    }
+
+    fn finish_turn_after(&mut self, elapsed: u64) -> Option<GameResult> {
+        let (is_white, _) = self.turn.take()?;
+
+        let (time, moves) = match is_white {
+            true => (&mut self.white_time, &mut self.white_moves),
+            false => (&mut self.black_time, &mut self.black_moves),
+        };
+
+        *time -= elapsed as i64;
+        if *time <= 0 {
+            return self.get_result();
+        }
+
+        *time += self.time_control.increment as i64;
+        *moves += 1;
+
+        if let Some(moves_per_period) = self.time_control.moves_per_period {
+            if moves.is_multiple_of(moves_per_period) {
+                *time += self.time_control.base_time as i64;
+            }
+        }
+
+        None
+    }
+}
+
+// minutes and seconds, tenths of seconds are shown when time gets short
+pub fn format_time(milliseconds: i64) -> String {
+    let milliseconds = milliseconds.max(0);
+    let minutes = milliseconds / 60_000;
+    let seconds = milliseconds % 60_000 / 1000;
+
+    if milliseconds < 10_000 {
+        format!("{minutes}:{seconds:02}.{}", milliseconds % 1000 / 100)
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_control() {
+        let time_control = "5".parse::<TimeControl>().unwrap();
+        assert_eq!(time_control.base_time, 300_000);
+        assert_eq!(time_control.increment, 0);
+        assert_eq!(time_control.moves_per_period, None);
+
+        let time_control = "3+2".parse::<TimeControl>().unwrap();
+        assert_eq!(time_control.base_time, 180_000);
+        assert_eq!(time_control.increment, 2000);
+        assert_eq!(time_control.to_string(), "3+2");
+
+        let time_control = "40/90+30"
+            .parse::<TimeControl>()
+            .unwrap();
+        assert_eq!(time_control.moves_per_period, Some(40));
+        assert_eq!(time_control.to_string(), "40/90+30");
+
+        assert_eq!(
+            "0.5"
+                .parse::<TimeControl>()
+                .unwrap()
+                .base_time,
+            30_000
+        );
+
+        assert!("".parse::<TimeControl>().is_err());
+        assert!("0".parse::<TimeControl>().is_err());
+        assert!("3+".parse::<TimeControl>().is_err());
+        assert!("0/5".parse::<TimeControl>().is_err());
+        assert!("fast".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn test_clock_increment_and_periods() {
+        let mut clock = Clock::new("2/1+1".parse().unwrap());
+
+        clock.start_turn(true);
+        assert_eq!(clock.finish_turn_after(10_000), None);
+        assert_eq!(clock.white_time, 51_000);
+        assert_eq!(clock.black_time, 60_000);
+
+        // a new period starts after two moves
+        clock.start_turn(true);
+        assert_eq!(clock.finish_turn_after(1000), None);
+        assert_eq!(clock.white_time, 111_000);
+
+        // without a running clock nothing changes
+        assert_eq!(clock.finish_turn_after(1000), None);
+        assert_eq!(clock.white_time, 111_000);
+    }
+
+    #[test]
+    fn test_clock_loss_on_time() {
+        let mut clock = Clock::new("1+5".parse().unwrap());
+
+        clock.start_turn(true);
+        assert_eq!(clock.get_result(), None);
+        assert_eq!(clock.finish_turn_after(1000), None);
+
+        // the increment does not save a side that has already run out of time
+        clock.start_turn(false);
+        assert_eq!(clock.finish_turn_after(60_000), Some(GameResult::WhiteWins));
+        assert_eq!(clock.get_result(), Some(GameResult::WhiteWins));
+    }
+
+    #[test]
+    fn test_allocate_time() {
+        let clock = Clock::new("1".parse().unwrap());
+        let (target, maximum) = clock.allocate_time(true);
+        assert_eq!(
+            target,
+            (60_000 - SAFETY_MARGIN as u32) / DEFAULT_MOVES_TO_GO
+        );
+        assert_eq!(maximum, target * 3);
+
+        let mut clock = Clock::new("10/1".parse().unwrap());
+        for _ in 0..9 {
+            clock.start_turn(true);
+            clock.finish_turn_after(0);
+        }
+        // the whole rest of the period may be used for the last move before the time control
+        let (target, maximum) = clock.allocate_time(true);
+        assert_eq!(target, 60_000 - SAFETY_MARGIN as u32);
+        assert_eq!(maximum, target);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(185_000), "3:05");
+        assert_eq!(format_time(9_870), "0:09.8");
+        assert_eq!(format_time(-20), "0:00.0");
+    }
+}
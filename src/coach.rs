@@ -0,0 +1,126 @@
+use crate::constants::SCORE_STEP;
+use crate::move_gen::Move;
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::search::SearchResult;
+use crate::state::GameState;
+
+// hints and reviews have to be quick, so they search with a fixed small depth
+pub const HINT_SEARCH_DEPTH: u32 = 8;
+pub const COACH_SEARCH_DEPTH: u32 = 8;
+// losing about half a king is worth a warning
+pub const DEFAULT_COACH_THRESHOLD: i32 = SCORE_STEP / 2;
+
+// scores are given from the point of view of the side that moved
+pub struct MoveReview {
+    pub played_move: Move,
+    pub played_score: i32,
+    pub best_move: Move,
+    pub best_score: i32,
+    pub best_pv: Vec<Move>,
+}
+
+impl MoveReview {
+    pub fn get_loss(&self) -> i32 {
+        self.best_score - self.played_score
+    }
+
+    pub fn is_blunder(&self, threshold: i32) -> bool {
+        self.played_move != self.best_move && self.get_loss() > threshold
+    }
+}
+
+// suggests a move for the side to move
+pub fn get_hint(game_state: &GameState, max_depth: u32) -> SearchResult {
+    let limits = SearchLimits::new(max_depth.min(HINT_SEARCH_DEPTH), u32::MAX);
+
+    think_with(game_state, &limits, &mut |_| {})
+}
+
+// Compares a move with the engine's choice in the position before the move.
+// The position after the move is searched one ply less deep, so that both
+// scores look equally far ahead.
+pub fn review_move(game_state: &GameState, mov: Move, max_depth: u32) -> MoveReview {
+    let depth = max_depth.clamp(2, COACH_SEARCH_DEPTH);
+
+    let best = think_with(game_state, &SearchLimits::new(depth, u32::MAX), &mut |_| {});
+    let best_move = best.best_move.unwrap_or(mov);
+
+    let played_score = if best_move == mov {
+        best.score
+    } else {
+        let mut next_game_state = game_state.clone();
+        next_game_state.make_move(mov);
+
+        let reply = think_with(
+            &next_game_state,
+            &SearchLimits::new(depth - 1, u32::MAX),
+            &mut |_| {},
+        );
+
+        -reply.score
+    };
+
+    MoveReview {
+        played_move: mov,
+        played_score,
+        best_move,
+        best_score: best.score,
+        best_pv: best.pv,
+    }
+}
+
+pub fn format_review_warning(review: &MoveReview) -> String {
+    let pv: Vec<String> = review
+        .best_pv
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+
+    format!(
+        "{} loses {:.3} compared to {} (pv {})",
+        review.played_move,
+        review.get_loss() as f32 / 1000f32,
+        review.best_move,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_hint() {
+        // c3c1 makes the sixth king
+        let game_state = GameState::from_notation("7/KKKK3/1KPpp2/1pppp2/7/7 w 0").unwrap();
+        let hint = get_hint(&game_state, 20);
+
+        assert!([Move::new(16, 2), Move::new(16, 14)].contains(&hint.best_move.unwrap()));
+    }
+
+    #[test]
+    fn test_review_move() {
+        let game_state = GameState::from_notation("7/KKKK3/1KPpp2/1pppp2/7/7 w 0").unwrap();
+
+        let review = review_move(&game_state, Move::new(16, 2), 6);
+        assert!(!review.is_blunder(DEFAULT_COACH_THRESHOLD));
+
+        // a slower win is no blunder
+        let review = review_move(&game_state, Move::new(7, 1), 6);
+        assert!(review.get_loss() > 0);
+        assert!(!review.is_blunder(DEFAULT_COACH_THRESHOLD));
+
+        // c2e2 throws the win away
+        let blunder = review_move(&game_state, Move::new(9, 11), 6);
+        assert!(blunder.is_blunder(DEFAULT_COACH_THRESHOLD));
+        assert_eq!(blunder.best_move, Move::new(16, 2));
+        assert_eq!(
+            format_review_warning(&blunder),
+            format!(
+                "c2e2 loses {:.3} compared to c3c1 (pv c3c1)",
+                blunder.get_loss() as f32 / 1000f32
+            )
+        );
+    }
+}
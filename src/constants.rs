@@ -1,124 +1,123 @@
+use crate::bit_board_gen::make_move_table;
+use crate::bit_board_gen::JUMP_STEPS;
+use crate::bit_board_gen::KING_STEPS;
+
 pub type BitBoard = u64;
 pub type BoardIndex = u8;
 pub type BoardIndexList = [BoardIndex; 8];
 
-pub const COLOR_RESET: &str = "\x1b[0m";
-//pub const COLOR_BLACK: &str = "\x1b[31m";
-pub const COLOR_RED: &str = "\x1b[31m";
-pub const COLOR_GREEN: &str = "\x1b[32m";
-pub const COLOR_YELLOW: &str = "\x1b[33m";
-//pub const COLOR_BLUE: &str = "\x1b[34m";
-//pub const COLOR_MAGENTA: &str = "\x1b[35m";
-//pub const COLOR_CYAN: &str = "\x1b[36m";
-//pub const COLOR_WHITE: &str = "\x1b[37m";
-pub const COLOR_WHITE_ON_MAGENTA: &str = "\x1b[37;45m";
-pub const COLOR_BLACK_ON_MAGENTA: &str = "\x1b[30;45m";
-
 pub const SCORE_STEP: i32 = 1000;
 pub const SCORE_MAX: i32 = 1000 * SCORE_STEP;
 pub const SCORE_MIN: i32 = -SCORE_MAX;
 
-#[allow(clippy::erasing_op, clippy::identity_op)]
-pub const SCORE_KING_COUNT: [i32; 7] = [
-    0 * SCORE_STEP,
-    1 * SCORE_STEP,
-    2 * SCORE_STEP,
-    3 * SCORE_STEP,
-    4 * SCORE_STEP,
-    5 * SCORE_STEP,
-    SCORE_MAX,
-];
-
+// the standard board, other boards are described by a BoardGeometry
 pub const BOARD_WIDTH: u8 = 7;
 pub const BOARD_HEIGHT: u8 = 6;
 pub const BOARD_TOTAL_PIECES: u8 = BOARD_HEIGHT * BOARD_WIDTH;
+pub const PIECES_PER_SIDE: usize = 6;
+
+// for every square the squares reached with one step and with a jump
+pub const KING_MOVES: [u64; 42] = make_move_table(BOARD_WIDTH, BOARD_HEIGHT, &KING_STEPS);
+pub const JUMP_MOVES: [u64; 42] = make_move_table(BOARD_WIDTH, BOARD_HEIGHT, &JUMP_STEPS);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the tables as they were written out before they were generated
+    const LITERAL_KING_MOVES: [u64; 42] = [
+        0b000000000000000000000000000000000110000010,
+        0b000000000000000000000000000000001110000101,
+        0b000000000000000000000000000000011100001010,
+        0b000000000000000000000000000000111000010100,
+        0b000000000000000000000000000001110000101000,
+        0b000000000000000000000000000011100001010000,
+        0b000000000000000000000000000011000000100000,
+        0b000000000000000000000000001100000100000011,
+        0b000000000000000000000000011100001010000111,
+        0b000000000000000000000000111000010100001110,
+        0b000000000000000000000001110000101000011100,
+        0b000000000000000000000011100001010000111000,
+        0b000000000000000000000111000010100001110000,
+        0b000000000000000000000110000001000001100000,
+        0b000000000000000000011000001000000110000000,
+        0b000000000000000000111000010100001110000000,
+        0b000000000000000001110000101000011100000000,
+        0b000000000000000011100001010000111000000000,
+        0b000000000000000111000010100001110000000000,
+        0b000000000000001110000101000011100000000000,
+        0b000000000000001100000010000011000000000000,
+        0b000000000000110000010000001100000000000000,
+        0b000000000001110000101000011100000000000000,
+        0b000000000011100001010000111000000000000000,
+        0b000000000111000010100001110000000000000000,
+        0b000000001110000101000011100000000000000000,
+        0b000000011100001010000111000000000000000000,
+        0b000000011000000100000110000000000000000000,
+        0b000001100000100000011000000000000000000000,
+        0b000011100001010000111000000000000000000000,
+        0b000111000010100001110000000000000000000000,
+        0b001110000101000011100000000000000000000000,
+        0b011100001010000111000000000000000000000000,
+        0b111000010100001110000000000000000000000000,
+        0b110000001000001100000000000000000000000000,
+        0b000001000000110000000000000000000000000000,
+        0b000010100001110000000000000000000000000000,
+        0b000101000011100000000000000000000000000000,
+        0b001010000111000000000000000000000000000000,
+        0b010100001110000000000000000000000000000000,
+        0b101000011100000000000000000000000000000000,
+        0b010000011000000000000000000000000000000000,
+    ];
 
-pub const KING_MOVES: [u64; 42] = [
-    0b000000000000000000000000000000000110000010,
-    0b000000000000000000000000000000001110000101,
-    0b000000000000000000000000000000011100001010,
-    0b000000000000000000000000000000111000010100,
-    0b000000000000000000000000000001110000101000,
-    0b000000000000000000000000000011100001010000,
-    0b000000000000000000000000000011000000100000,
-    0b000000000000000000000000001100000100000011,
-    0b000000000000000000000000011100001010000111,
-    0b000000000000000000000000111000010100001110,
-    0b000000000000000000000001110000101000011100,
-    0b000000000000000000000011100001010000111000,
-    0b000000000000000000000111000010100001110000,
-    0b000000000000000000000110000001000001100000,
-    0b000000000000000000011000001000000110000000,
-    0b000000000000000000111000010100001110000000,
-    0b000000000000000001110000101000011100000000,
-    0b000000000000000011100001010000111000000000,
-    0b000000000000000111000010100001110000000000,
-    0b000000000000001110000101000011100000000000,
-    0b000000000000001100000010000011000000000000,
-    0b000000000000110000010000001100000000000000,
-    0b000000000001110000101000011100000000000000,
-    0b000000000011100001010000111000000000000000,
-    0b000000000111000010100001110000000000000000,
-    0b000000001110000101000011100000000000000000,
-    0b000000011100001010000111000000000000000000,
-    0b000000011000000100000110000000000000000000,
-    0b000001100000100000011000000000000000000000,
-    0b000011100001010000111000000000000000000000,
-    0b000111000010100001110000000000000000000000,
-    0b001110000101000011100000000000000000000000,
-    0b011100001010000111000000000000000000000000,
-    0b111000010100001110000000000000000000000000,
-    0b110000001000001100000000000000000000000000,
-    0b000001000000110000000000000000000000000000,
-    0b000010100001110000000000000000000000000000,
-    0b000101000011100000000000000000000000000000,
-    0b001010000111000000000000000000000000000000,
-    0b010100001110000000000000000000000000000000,
-    0b101000011100000000000000000000000000000000,
-    0b010000011000000000000000000000000000000000,
-];
+    const LITERAL_JUMP_MOVES: [u64; 42] = [
+        0b000000000000000000000000000100000000000100,
+        0b000000000000000000000000001000000000001000,
+        0b000000000000000000000000010000000000010001,
+        0b000000000000000000000000100000000000100010,
+        0b000000000000000000000001000000000001000100,
+        0b000000000000000000000010000000000000001000,
+        0b000000000000000000000100000000000000010000,
+        0b000000000000000000001000000000001000000000,
+        0b000000000000000000010000000000010000000000,
+        0b000000000000000000100000000000100010000000,
+        0b000000000000000001000000000001000100000000,
+        0b000000000000000010000000000010001000000000,
+        0b000000000000000100000000000000010000000000,
+        0b000000000000001000000000000000100000000000,
+        0b000000000000010000000000010000000000000001,
+        0b000000000000100000000000100000000000000010,
+        0b000000000001000000000001000100000000000100,
+        0b000000000010000000000010001000000000001000,
+        0b000000000100000000000100010000000000010000,
+        0b000000001000000000000000100000000000100000,
+        0b000000010000000000000001000000000001000000,
+        0b000000100000000000100000000000000010000000,
+        0b000001000000000001000000000000000100000000,
+        0b000010000000000010001000000000001000000000,
+        0b000100000000000100010000000000010000000000,
+        0b001000000000001000100000000000100000000000,
+        0b010000000000000001000000000001000000000000,
+        0b100000000000000010000000000010000000000000,
+        0b000000000001000000000000000100000000000000,
+        0b000000000010000000000000001000000000000000,
+        0b000000000100010000000000010000000000000000,
+        0b000000001000100000000000100000000000000000,
+        0b000000010001000000000001000000000000000000,
+        0b000000000010000000000010000000000000000000,
+        0b000000000100000000000100000000000000000000,
+        0b000010000000000000001000000000000000000000,
+        0b000100000000000000010000000000000000000000,
+        0b001000100000000000100000000000000000000000,
+        0b010001000000000001000000000000000000000000,
+        0b100010000000000010000000000000000000000000,
+        0b000100000000000100000000000000000000000000,
+        0b001000000000001000000000000000000000000000,
+    ];
 
-pub const JUMP_MOVES: [u64; 42] = [
-    0b000000000000000000000000000100000000000100,
-    0b000000000000000000000000001000000000001000,
-    0b000000000000000000000000010000000000010001,
-    0b000000000000000000000000100000000000100010,
-    0b000000000000000000000001000000000001000100,
-    0b000000000000000000000010000000000000001000,
-    0b000000000000000000000100000000000000010000,
-    0b000000000000000000001000000000001000000000,
-    0b000000000000000000010000000000010000000000,
-    0b000000000000000000100000000000100010000000,
-    0b000000000000000001000000000001000100000000,
-    0b000000000000000010000000000010001000000000,
-    0b000000000000000100000000000000010000000000,
-    0b000000000000001000000000000000100000000000,
-    0b000000000000010000000000010000000000000001,
-    0b000000000000100000000000100000000000000010,
-    0b000000000001000000000001000100000000000100,
-    0b000000000010000000000010001000000000001000,
-    0b000000000100000000000100010000000000010000,
-    0b000000001000000000000000100000000000100000,
-    0b000000010000000000000001000000000001000000,
-    0b000000100000000000100000000000000010000000,
-    0b000001000000000001000000000000000100000000,
-    0b000010000000000010001000000000001000000000,
-    0b000100000000000100010000000000010000000000,
-    0b001000000000001000100000000000100000000000,
-    0b010000000000000001000000000001000000000000,
-    0b100000000000000010000000000010000000000000,
-    0b000000000001000000000000000100000000000000,
-    0b000000000010000000000000001000000000000000,
-    0b000000000100010000000000010000000000000000,
-    0b000000001000100000000000100000000000000000,
-    0b000000010001000000000001000000000000000000,
-    0b000000000010000000000010000000000000000000,
-    0b000000000100000000000100000000000000000000,
-    0b000010000000000000001000000000000000000000,
-    0b000100000000000000010000000000000000000000,
-    0b001000100000000000100000000000000000000000,
-    0b010001000000000001000000000000000000000000,
-    0b100010000000000010000000000000000000000000,
-    0b000100000000000100000000000000000000000000,
-    0b001000000000001000000000000000000000000000,
-];
+    #[test]
+    fn test_generated_move_tables() {
+        assert_eq!(KING_MOVES, LITERAL_KING_MOVES);
+        assert_eq!(JUMP_MOVES, LITERAL_JUMP_MOVES);
+    }
+}
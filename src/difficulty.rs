@@ -0,0 +1,237 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::move_gen::Move;
+use crate::rng::Rng;
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::state::GameState;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Beginner,
+    Casual,
+    Intermediate,
+    Advanced,
+    Master,
+}
+
+pub const LEVELS: [Level; 5] = [
+    Level::Beginner,
+    Level::Casual,
+    Level::Intermediate,
+    Level::Advanced,
+    Level::Master,
+];
+
+impl Level {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Level::Beginner => "beginner",
+            Level::Casual => "casual",
+            Level::Intermediate => "intermediate",
+            Level::Advanced => "advanced",
+            Level::Master => "master",
+        }
+    }
+
+    // None means no limit beyond the configured search depth
+    pub fn get_max_depth(&self) -> Option<u32> {
+        match self {
+            Level::Beginner => Some(2),
+            Level::Casual => Some(3),
+            Level::Intermediate => Some(5),
+            Level::Advanced => Some(8),
+            Level::Master => None,
+        }
+    }
+
+    pub fn get_max_nodes(&self) -> Option<u64> {
+        match self {
+            Level::Beginner => Some(2_000),
+            Level::Casual => Some(20_000),
+            Level::Intermediate => Some(200_000),
+            Level::Advanced => Some(2_000_000),
+            Level::Master => None,
+        }
+    }
+
+    // Moves are picked with a probability of exp(-loss / temperature), where
+    // loss is how much worse than the best move they score. A temperature of
+    // 0 always plays the best move.
+    pub fn get_temperature(&self) -> i32 {
+        match self {
+            Level::Beginner => 1500,
+            Level::Casual => 600,
+            Level::Intermediate => 250,
+            Level::Advanced => 60,
+            Level::Master => 0,
+        }
+    }
+
+    // the search limits of the level, they never exceed the given ones. Weakened
+    // levels ignore the time limits so that only the depth and node caps end the
+    // search, which keeps their moves reproducible on any machine.
+    pub fn limit(&self, limits: &SearchLimits) -> SearchLimits {
+        let is_weakened = self.is_weakened();
+
+        SearchLimits {
+            max_depth: self
+                .get_max_depth()
+                .map_or(limits.max_depth, |d| d.min(limits.max_depth)),
+            min_time: if is_weakened {
+                u32::MAX
+            } else {
+                limits.min_time
+            },
+            max_time: limits.max_time.filter(|_| !is_weakened),
+            max_nodes: match (self.get_max_nodes(), limits.max_nodes) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+            stop: limits.stop.clone(),
+            multi_pv: limits.multi_pv,
+        }
+    }
+
+    // every level but the strongest one plays sub-optimal moves now and then
+    pub fn is_weakened(&self) -> bool {
+        self.get_temperature() > 0
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    // levels can be given by name or by number, starting with 1 for beginner
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LEVELS
+            .iter()
+            .enumerate()
+            .find(|(i, level)| level.get_name() == s || (i + 1).to_string() == s)
+            .map(|(_, level)| *level)
+            .ok_or(format!(
+                "invalid level '{s}', expected one of beginner, casual, intermediate, advanced or master"
+            ))
+    }
+}
+
+// Scores all moves with a MultiPV search within the limits of the level and
+// picks one of them weighted by how good it is.
+pub fn choose_weakened_move(
+    game_state: &GameState,
+    level: Level,
+    limits: &SearchLimits,
+    rng: &mut Rng,
+) -> Option<Move> {
+    let mut limits = level.limit(limits);
+    limits.multi_pv = usize::MAX;
+
+    let lines = think_with(game_state, &limits, &mut |_| {}).lines;
+    let best_score = lines.first()?.score;
+    let temperature = level.get_temperature().max(1) as f64;
+
+    let weights: Vec<f64> = lines
+        .iter()
+        .map(|line| (-((best_score - line.score) as f64) / temperature).exp())
+        .collect();
+
+    let i = rng.pick_weighted(&weights)?;
+
+    lines[i].pv.first().copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        for level in LEVELS {
+            assert_eq!(level.get_name().parse::<Level>(), Ok(level));
+        }
+        assert_eq!("1".parse::<Level>(), Ok(Level::Beginner));
+        assert_eq!("5".parse::<Level>(), Ok(Level::Master));
+        assert!("6".parse::<Level>().is_err());
+        assert!("grandmaster".parse::<Level>().is_err());
+    }
+
+    #[test]
+    fn test_level_limits() {
+        let limits = SearchLimits::new(20, 1000);
+
+        let beginner = Level::Beginner.limit(&limits);
+        assert_eq!(beginner.max_depth, 2);
+        assert_eq!(beginner.max_nodes, Some(2_000));
+
+        assert_eq!(beginner.min_time, u32::MAX);
+        assert_eq!(beginner.max_time, None);
+
+        let master = Level::Master.limit(&limits);
+        assert_eq!(master.min_time, 1000);
+        assert_eq!(master.max_depth, 20);
+        assert_eq!(master.max_nodes, None);
+        assert!(!Level::Master.is_weakened());
+    }
+
+    #[test]
+    fn test_choose_weakened_move() {
+        let game_state = GameState::new();
+        let limits = SearchLimits::new(20, u32::MAX);
+
+        let choose = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..10)
+                .map(|_| choose_weakened_move(&game_state, Level::Beginner, &limits, &mut rng))
+                .collect::<Vec<Option<Move>>>()
+        };
+
+        // the same seed plays the same moves
+        assert_eq!(choose(1), choose(1));
+
+        let moves = choose(1);
+        assert!(moves.iter().all(|m| game_state
+            .generate_moves()
+            .contains(&m.unwrap())));
+
+        // a beginner does not always play the same move
+        assert!(moves.iter().any(|m| *m != moves[0]));
+    }
+
+    #[test]
+    fn test_weakened_move_still_wins() {
+        // there is a large gap between the immediate win and the other moves
+        let game_state = GameState::from_notation("7/KKKK3/1KPpp2/1pppp2/7/7 w 0").unwrap();
+        let limits = SearchLimits::new(20, u32::MAX);
+        let mut rng = Rng::new(3);
+
+        let mov = choose_weakened_move(&game_state, Level::Advanced, &limits, &mut rng).unwrap();
+        let mut next_game_state = game_state.clone();
+        next_game_state.make_move(mov);
+
+        assert!(next_game_state.get_result().is_some());
+    }
+
+    #[test]
+    fn test_weakened_move_ignores_time() {
+        let game_state = GameState::new();
+
+        let choose = |min_time, max_time| {
+            let mut limits = SearchLimits::new(20, min_time);
+            limits.max_time = max_time;
+            let mut rng = Rng::new(5);
+            (0..3)
+                .map(|_| choose_weakened_move(&game_state, Level::Advanced, &limits, &mut rng))
+                .collect::<Vec<Option<Move>>>()
+        };
+
+        // a search that would time out at once still plays the same moves
+        assert_eq!(choose(1, Some(1)), choose(u32::MAX, None));
+    }
+}
@@ -0,0 +1,212 @@
+use std::fs;
+
+use crate::clock::TimeControl;
+use crate::difficulty::Level;
+use crate::gui::GameMode;
+use crate::move_gen::Move;
+use crate::state::GameState;
+
+// Saved games are written in a format similar to PGN, tag pairs followed by
+// the moves, e.g.
+//   [Position "7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0"]
+//   [White "human"]
+//   [Black "computer"]
+//   [Level "casual"]
+//   [Seed "42"]
+//   [Result "black wins"]
+//
+//   c2e2 c4c2 d2d4
+pub struct GameRecord {
+    pub start_position: String,
+    pub game_mode: GameMode,
+    pub level: Level,
+    pub seed: u64,
+    pub time_control: Option<TimeControl>,
+    pub moves: Vec<Move>,
+    pub result: Option<String>,
+}
+
+impl GameRecord {
+    pub fn new(game_state: &GameState, game_mode: GameMode, level: Level, seed: u64) -> Self {
+        Self {
+            start_position: game_state.to_notation(),
+            game_mode,
+            level,
+            seed,
+            time_control: None,
+            moves: Vec::new(),
+            result: None,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let player = |is_computer| {
+            if is_computer {
+                "computer"
+            } else {
+                "human"
+            }
+        };
+
+        let mut text = String::new();
+
+        text.push_str(&format!("[Position \"{}\"]\n", self.start_position));
+        text.push_str(&format!(
+            "[White \"{}\"]\n",
+            player(self.game_mode == GameMode::ComputerWhite)
+        ));
+        text.push_str(&format!(
+            "[Black \"{}\"]\n",
+            player(self.game_mode == GameMode::ComputerBlack)
+        ));
+        text.push_str(&format!("[Level \"{}\"]\n", self.level));
+        text.push_str(&format!("[Seed \"{}\"]\n", self.seed));
+        if let Some(time_control) = self.time_control {
+            text.push_str(&format!("[TimeControl \"{time_control}\"]\n"));
+        }
+        if let Some(result) = &self.result {
+            text.push_str(&format!("[Result \"{result}\"]\n"));
+        }
+
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        text.push_str(&format!("\n{}\n", moves.join(" ")));
+
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut start_position = None;
+        let mut is_white_computer = false;
+        let mut is_black_computer = false;
+        let mut level = Level::Master;
+        let mut seed = 0;
+        let mut time_control = None;
+        let mut result = None;
+        let mut moves = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+
+            if let Some(tag) = line.strip_prefix('[') {
+                let (name, value) = tag
+                    .strip_suffix(']')
+                    .and_then(|tag| tag.split_once(' '))
+                    .ok_or(format!("invalid tag '{line}'"))?;
+                let value = value.trim_matches('"');
+
+                match name {
+                    "Position" => start_position = Some(value.to_string()),
+                    "White" => is_white_computer = value == "computer",
+                    "Black" => is_black_computer = value == "computer",
+                    "Level" => level = value.parse()?,
+                    "Seed" => {
+                        seed = value
+                            .parse()
+                            .map_err(|_| format!("invalid seed '{value}'"))?
+                    }
+                    "TimeControl" => time_control = Some(value.parse()?),
+                    "Result" => result = Some(value.to_string()),
+                    // unknown tags are kept for compatibility with newer versions
+                    _ => {}
+                }
+            } else {
+                for token in line.split_whitespace() {
+                    moves.push(token.parse::<Move>()?);
+                }
+            }
+        }
+
+        let game_mode = match (is_white_computer, is_black_computer) {
+            (true, false) => GameMode::ComputerWhite,
+            (false, true) => GameMode::ComputerBlack,
+            _ => GameMode::NoComputer,
+        };
+
+        let record = Self {
+            start_position: start_position.ok_or("missing 'Position' tag")?,
+            game_mode,
+            level,
+            seed,
+            time_control,
+            moves,
+            result,
+        };
+        record.replay()?;
+
+        Ok(record)
+    }
+
+    // the position at the end of the game, every move is checked to be legal
+    pub fn replay(&self) -> Result<GameState, String> {
+        let mut game_state = GameState::from_notation(&self.start_position)?;
+
+        for mov in &self.moves {
+            if !game_state
+                .generate_moves()
+                .contains(mov)
+            {
+                return Err(format!("illegal move '{mov}'"));
+            }
+            game_state.make_move(*mov);
+        }
+
+        Ok(game_state)
+    }
+}
+
+pub fn write_game_record(path: &str, record: &GameRecord) -> Result<(), String> {
+    fs::write(path, record.to_text()).map_err(|error| format!("could not write '{path}': {error}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_game_record_round_trip() {
+        let mut record = GameRecord::new(
+            &GameState::new(),
+            GameMode::ComputerWhite,
+            Level::Casual,
+            42,
+        );
+        record.time_control = Some("3+2".parse().unwrap());
+        let mut game_state = GameState::new();
+        for _ in 0..2 {
+            let mov = game_state.generate_moves()[0];
+            game_state.make_move(mov);
+            record.moves.push(mov);
+        }
+        record.result = Some("white wins".to_string());
+
+        let text = record.to_text();
+        assert!(text.contains("[White \"computer\"]\n[Black \"human\"]\n"));
+        assert!(text.contains("[Level \"casual\"]\n[Seed \"42\"]\n"));
+        assert!(text.ends_with(&format!("\n{} {}\n", record.moves[0], record.moves[1])));
+
+        let parsed = GameRecord::from_text(&text).unwrap();
+        assert_eq!(parsed.start_position, record.start_position);
+        assert_eq!(parsed.game_mode, GameMode::ComputerWhite);
+        assert_eq!(parsed.level, Level::Casual);
+        assert_eq!(parsed.seed, 42);
+        assert_eq!(parsed.time_control, record.time_control);
+        assert_eq!(parsed.moves, record.moves);
+        assert_eq!(parsed.result, record.result);
+    }
+
+    #[test]
+    fn test_invalid_game_records() {
+        assert!(GameRecord::from_text("c3c1").is_err());
+        assert!(
+            GameRecord::from_text("[Position \"7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0\"]\na1a3").is_err()
+        );
+        assert!(GameRecord::from_text(
+            "[Position \"7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0\"]\n[Level \"god\"]"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,468 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use crate::bit_board_gen::make_jump_moves;
+use crate::bit_board_gen::make_king_moves;
+use crate::constants::BitBoard;
+use crate::constants::BoardIndex;
+use crate::constants::BOARD_HEIGHT;
+use crate::constants::BOARD_WIDTH;
+use crate::constants::JUMP_MOVES;
+use crate::constants::KING_MOVES;
+use crate::constants::PIECES_PER_SIDE;
+use crate::rules::Ruleset;
+use crate::rules::STANDARD_RULES;
+
+// Files are named a..z and ranks by a single digit. A jump needs three
+// squares in a row, on smaller boards pawns could never move.
+pub const MIN_SIZE: u8 = 3;
+pub const MAX_WIDTH: u8 = 26;
+pub const MAX_HEIGHT: u8 = 9;
+pub const MAX_SQUARES: u8 = 64;
+pub const MAX_PIECES_PER_SIDE: usize = 7;
+
+// The board the rules are played on: its size, the number of pieces of each
+// side, where they start, the squares the pieces can move to and the rules of
+// the variant. The standard board uses the move tables of constants.rs, other
+// boards generate theirs at startup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardGeometry {
+    pub width: u8,
+    pub height: u8,
+    pub pieces_per_side: usize,
+    pub white_start: BitBoard,
+    pub black_start: BitBoard,
+    pub first_file: BitBoard,
+    pub last_file: BitBoard,
+    pub king_moves: Cow<'static, [BitBoard]>,
+    pub jump_moves: Cow<'static, [BitBoard]>,
+    pub rules: Ruleset,
+}
+
+// 7/2PP3/1PPPP2/2pppp1/3pp2/7
+pub static STANDARD: BoardGeometry = BoardGeometry {
+    width: BOARD_WIDTH,
+    height: BOARD_HEIGHT,
+    pieces_per_side: PIECES_PER_SIDE,
+    white_start: 1 << 9 | 1 << 10 | 1 << 15 | 1 << 16 | 1 << 17 | 1 << 18,
+    black_start: 1 << 23 | 1 << 24 | 1 << 25 | 1 << 26 | 1 << 31 | 1 << 32,
+    first_file: make_file(BOARD_WIDTH, BOARD_HEIGHT, 0),
+    last_file: make_file(BOARD_WIDTH, BOARD_HEIGHT, BOARD_WIDTH - 1),
+    king_moves: Cow::Borrowed(&KING_MOVES),
+    jump_moves: Cow::Borrowed(&JUMP_MOVES),
+    rules: STANDARD_RULES,
+};
+
+impl BoardGeometry {
+    pub fn new(
+        width: u8,
+        height: u8,
+        pieces_per_side: usize,
+        mut rules: Ruleset,
+    ) -> Result<Self, String> {
+        if rules.kings_to_win == Some(pieces_per_side) {
+            rules.kings_to_win = None;
+        }
+
+        let is_standard_board =
+            (width, height, pieces_per_side) == (BOARD_WIDTH, BOARD_HEIGHT, PIECES_PER_SIDE);
+        if is_standard_board && rules == STANDARD_RULES {
+            return Ok(STANDARD.clone());
+        }
+
+        if !(MIN_SIZE..=MAX_WIDTH).contains(&width) || !(MIN_SIZE..=MAX_HEIGHT).contains(&height) {
+            return Err(format!(
+                "a board is {MIN_SIZE} to {MAX_WIDTH} squares wide and {MIN_SIZE} to {MAX_HEIGHT} \
+                 squares high, found {width}x{height}"
+            ));
+        }
+
+        if width as u32 * height as u32 > MAX_SQUARES as u32 {
+            return Err(format!(
+                "a board has at most {MAX_SQUARES} squares, {width}x{height} has {}",
+                width as u32 * height as u32
+            ));
+        }
+
+        if !(1..=MAX_PIECES_PER_SIDE).contains(&pieces_per_side) {
+            return Err(format!(
+                "each side has 1 to {MAX_PIECES_PER_SIDE} pieces, found {pieces_per_side}"
+            ));
+        }
+
+        if 2 * pieces_per_side > (width * height) as usize {
+            return Err(format!(
+                "{} pieces do not fit on a {width}x{height} board",
+                2 * pieces_per_side
+            ));
+        }
+
+        if let Some(kings_to_win) = rules.kings_to_win {
+            if !(1..=pieces_per_side).contains(&kings_to_win) {
+                return Err(format!(
+                    "a side wins with 1 to {pieces_per_side} kings, found {kings_to_win}"
+                ));
+            }
+        }
+
+        // other layouts share the squares of the standard start differently
+        let start_block = match is_standard_board {
+            true => STANDARD.white_start | STANDARD.black_start,
+            false => make_start_block(width, height, pieces_per_side),
+        };
+        let (white_start, black_start) =
+            rules
+                .start_layout
+                .assign(start_block, width, pieces_per_side);
+
+        Ok(Self {
+            width,
+            height,
+            pieces_per_side,
+            white_start,
+            black_start,
+            first_file: make_file(width, height, 0),
+            last_file: make_file(width, height, width - 1),
+            king_moves: Cow::Owned(make_king_moves(width, height)),
+            jump_moves: Cow::Owned(make_jump_moves(width, height)),
+            rules,
+        })
+    }
+
+    pub fn is_standard(&self) -> bool {
+        *self == STANDARD
+    }
+
+    pub fn get_kings_to_win(&self) -> usize {
+        self.rules
+            .kings_to_win
+            .unwrap_or(self.pieces_per_side)
+    }
+
+    pub fn get_square_count(&self) -> u8 {
+        self.width * self.height
+    }
+
+    pub fn get_index(&self, x: BoardIndex, y: BoardIndex) -> BoardIndex {
+        y * self.width + x
+    }
+}
+
+// the squares of file x
+const fn make_file(width: u8, height: u8, x: u8) -> BitBoard {
+    let mut file = 0;
+
+    let mut y = 0;
+    while y < height {
+        file |= 1 << (y * width + x);
+        y += 1;
+    }
+
+    file
+}
+
+impl Display for BoardGeometry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{}, {} pieces each, {}, {} kings win",
+            self.width,
+            self.height,
+            self.pieces_per_side,
+            self.rules.connectivity,
+            self.get_kings_to_win()
+        )?;
+
+        if !self.rules.king_steps {
+            write!(f, ", kings only jump")?;
+        }
+
+        write!(f, ", {} start", self.rules.start_layout)
+    }
+}
+
+// The pieces start as a block in the middle of the board, as square as the
+// board allows.
+fn make_start_block(width: u8, height: u8, pieces_per_side: usize) -> BitBoard {
+    let pieces = 2 * pieces_per_side;
+
+    let mut block_width = 1;
+    while block_width * block_width < pieces {
+        block_width += 1;
+    }
+    let block_width = block_width
+        .max(pieces.div_ceil(height as usize))
+        .min(width as usize);
+    let block_height = pieces.div_ceil(block_width);
+
+    let x0 = (width as usize - block_width) / 2;
+    let y0 = (height as usize - block_height) / 2;
+
+    let mut block = 0;
+
+    for n in 0..pieces {
+        let x = x0 + n % block_width;
+        let y = y0 + n / block_width;
+        block |= 1 << (y * width as usize + x);
+    }
+
+    block
+}
+
+// Parses a board size like "8x7".
+pub fn parse_board_size(value: &str) -> Result<(u8, u8), String> {
+    let error = || format!("invalid board size '{value}', expected e.g. 8x7");
+
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(error)?;
+
+    Ok((
+        width.parse().map_err(|_| error())?,
+        height.parse().map_err(|_| error())?,
+    ))
+}
+
+static GEOMETRY: OnceLock<&'static BoardGeometry> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    static TEST_GEOMETRY: std::cell::Cell<Option<&'static BoardGeometry>> =
+        const { std::cell::Cell::new(None) };
+}
+
+// the geometry can only be chosen once, at startup, before any position exists
+pub fn set_geometry(geometry: BoardGeometry) {
+    let _ = GEOMETRY.set(Box::leak(Box::new(geometry)));
+}
+
+pub fn get_geometry() -> &'static BoardGeometry {
+    #[cfg(test)]
+    if let Some(geometry) = TEST_GEOMETRY.get() {
+        return geometry;
+    }
+
+    GEOMETRY
+        .get()
+        .copied()
+        .unwrap_or(&STANDARD)
+}
+
+// Tests run in threads of one process, so a test plays on another board by
+// replacing the geometry of its own thread only.
+#[cfg(test)]
+pub fn set_test_geometry(geometry: BoardGeometry) {
+    TEST_GEOMETRY.set(Some(Box::leak(Box::new(geometry))));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constants::SCORE_MAX;
+    use crate::move_gen::Move;
+    use crate::rules::Connectivity;
+    use crate::rules::StartLayout;
+    use crate::search::evaluate;
+    use crate::state::GameResult;
+    use crate::state::GameState;
+    use crate::valid_board_gen::is_connected;
+
+    #[test]
+    fn test_standard_geometry() {
+        assert_eq!(KING_MOVES.to_vec(), make_king_moves(7, 6));
+        assert_eq!(JUMP_MOVES.to_vec(), make_jump_moves(7, 6));
+
+        let geometry = BoardGeometry::new(7, 6, 6, STANDARD_RULES).unwrap();
+        assert!(geometry.is_standard());
+        assert!(matches!(geometry.king_moves, Cow::Borrowed(_)));
+        assert_eq!(
+            geometry.first_file,
+            0b000000100000010000001000000100000010000001
+        );
+        assert_eq!(geometry.last_file, geometry.first_file << 6);
+
+        assert!(!BoardGeometry::new(7, 6, 5, STANDARD_RULES)
+            .unwrap()
+            .is_standard());
+        assert_eq!(
+            GameState::new().to_notation(),
+            "7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0"
+        );
+    }
+
+    #[test]
+    fn test_invalid_geometries() {
+        assert!(BoardGeometry::new(0, 6, 6, STANDARD_RULES).is_err());
+        assert!(BoardGeometry::new(2, 6, 1, STANDARD_RULES).is_err());
+        assert!(BoardGeometry::new(27, 2, 1, STANDARD_RULES).is_err());
+        assert!(BoardGeometry::new(7, 10, 6, STANDARD_RULES).is_err());
+        assert!(BoardGeometry::new(9, 8, 6, STANDARD_RULES)
+            .unwrap_err()
+            .contains("at most 64"));
+        assert!(BoardGeometry::new(8, 8, 8, STANDARD_RULES).is_err());
+        assert!(BoardGeometry::new(8, 8, 0, STANDARD_RULES).is_err());
+        assert!(BoardGeometry::new(3, 3, 5, STANDARD_RULES)
+            .unwrap_err()
+            .contains("do not fit"));
+
+        assert_eq!(parse_board_size("8x7"), Ok((8, 7)));
+        assert!(parse_board_size("8").is_err());
+        assert!(parse_board_size("8x-1").is_err());
+    }
+
+    #[test]
+    fn test_start_layouts() {
+        for (width, height, pieces_per_side) in
+            [(8, 8, 7), (8, 7, 6), (16, 4, 7), (21, 3, 2), (3, 3, 4)]
+        {
+            let geometry =
+                BoardGeometry::new(width, height, pieces_per_side, STANDARD_RULES).unwrap();
+            let (white, black) = (geometry.white_start, geometry.black_start);
+            let all_squares = BitBoard::MAX >> (64 - geometry.get_square_count());
+
+            assert_eq!(white & black, 0);
+            assert_eq!((white | black) & !all_squares, 0);
+            assert_eq!(white.count_ones() as usize, pieces_per_side);
+            assert_eq!(black.count_ones() as usize, pieces_per_side);
+            assert_eq!(geometry.king_moves.len(), (width * height) as usize);
+
+            set_test_geometry(geometry);
+            let pieces = white | black;
+            assert!(is_connected(pieces, pieces.trailing_zeros() as BoardIndex));
+        }
+    }
+
+    #[test]
+    fn test_larger_board() {
+        set_test_geometry(BoardGeometry::new(8, 8, 7, STANDARD_RULES).unwrap());
+
+        let game_state = GameState::new();
+        assert_eq!(
+            game_state.to_notation(),
+            "8/8/2PPPP2/2PPPp2/2pppp2/2pp4/8/8 w 0"
+        );
+        assert_eq!(
+            GameState::from_notation(&game_state.to_notation())
+                .unwrap()
+                .to_notation(),
+            game_state.to_notation()
+        );
+
+        // jumps over two ranks of 8 squares and steps to h8 exist on this board
+        assert_eq!(
+            "a1h8"
+                .parse::<crate::move_gen::Move>()
+                .map(|mov| (mov.get_from(), mov.get_to())),
+            Ok((0, 63))
+        );
+        assert!(!game_state.generate_moves().is_empty());
+        assert!(game_state.perft(3) > 0);
+
+        // 6 kings do not win when each side has 7 pieces
+        let game_state = GameState::from_notation("8/8/2KKKK2/2KKPp2/2pppp2/2pp4/8/8 w 9").unwrap();
+        assert_eq!(game_state.get_result(), None);
+        let game_state = GameState::from_notation("8/8/2KKKK2/2KKKp2/2pppp2/2pp4/8/8 b 9").unwrap();
+        assert_eq!(game_state.get_result(), Some(GameResult::WhiteWins));
+    }
+
+    #[test]
+    fn test_narrow_board() {
+        set_test_geometry(BoardGeometry::new(3, 6, 4, STANDARD_RULES).unwrap());
+
+        // the diagonal step c5b6 has the index difference of a jump along a rank
+        let mut game_state = GameState::from_notation("3/3/KPP/ppp/1pK/3 w 0").unwrap();
+        let step: Move = "c5b6".parse().unwrap();
+        assert!(!step.is_jump());
+        assert!(game_state
+            .generate_moves()
+            .contains(&step));
+
+        game_state.make_move(step);
+        assert_eq!(game_state.board.white_kings.len(), 2);
+        assert!(game_state
+            .board
+            .white_kings
+            .contains(16));
+        assert_eq!(game_state.to_notation(), "3/3/KPP/ppp/1p1/1K1 b 1");
+
+        assert!("a1c1"
+            .parse::<Move>()
+            .unwrap()
+            .is_jump());
+        assert!("a1a3"
+            .parse::<Move>()
+            .unwrap()
+            .is_jump());
+    }
+
+    fn set_test_rules(rules: Ruleset) {
+        set_test_geometry(BoardGeometry::new(7, 6, 6, rules).unwrap());
+    }
+
+    #[test]
+    fn test_rule_variants() {
+        let diagonal_pair = 1 | 1 << 8;
+        assert!(!is_connected(diagonal_pair, 0));
+        let four_moves = GameState::new().generate_moves().len();
+
+        set_test_rules(Ruleset {
+            connectivity: Connectivity::Eight,
+            ..STANDARD_RULES
+        });
+        assert!(is_connected(diagonal_pair, 0));
+        assert!(GameState::new().generate_moves().len() > four_moves);
+
+        // two kings win, evaluated as a win by the search as well
+        set_test_rules(Ruleset {
+            kings_to_win: Some(2),
+            ..STANDARD_RULES
+        });
+        let game_state = GameState::from_notation("7/2KK3/1PPPP2/2pppp1/3pp2/7 b 2").unwrap();
+        assert_eq!(game_state.get_result(), Some(GameResult::WhiteWins));
+        assert!(game_state.generate_moves().is_empty());
+        assert_eq!(evaluate(&game_state), -SCORE_MAX + 2);
+
+        set_test_rules(Ruleset {
+            king_steps: false,
+            ..STANDARD_RULES
+        });
+        let game_state = GameState::from_notation("7/2KK3/1PPPP2/2pppp1/3pp2/7 w 2").unwrap();
+        assert!(game_state
+            .generate_moves()
+            .iter()
+            .all(|mov| mov.is_jump()));
+
+        set_test_rules(Ruleset {
+            start_layout: StartLayout::Sides,
+            ..STANDARD_RULES
+        });
+        assert_eq!(
+            GameState::new().to_notation(),
+            "7/2PP3/1PPPp2/2Pppp1/3pp2/7 w 0"
+        );
+        assert!(!get_geometry().is_standard());
+
+        assert!(BoardGeometry::new(
+            7,
+            6,
+            6,
+            Ruleset {
+                kings_to_win: Some(7),
+                ..STANDARD_RULES
+            }
+        )
+        .is_err());
+        assert!(BoardGeometry::new(
+            7,
+            6,
+            6,
+            Ruleset {
+                kings_to_win: Some(6),
+                ..STANDARD_RULES
+            }
+        )
+        .unwrap()
+        .is_standard());
+    }
+}
@@ -2,30 +2,84 @@ use std::io::stdin;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
-use crate::constants::COLOR_GREEN;
-use crate::constants::COLOR_RED;
-use crate::constants::COLOR_RESET;
+use crate::book::probe_book;
+use crate::clock::Clock;
+use crate::clock::TimeControl;
+use crate::coach::format_review_warning;
+use crate::coach::get_hint;
+use crate::coach::review_move;
+use crate::coach::DEFAULT_COACH_THRESHOLD;
+use crate::difficulty::choose_weakened_move;
+use crate::difficulty::Level;
+use crate::difficulty::LEVELS;
+use crate::game_record::write_game_record;
+use crate::game_record::GameRecord;
+use crate::geometry::get_geometry;
 use crate::move_gen::Move;
-use crate::search::think;
+use crate::ponder::Ponder;
+use crate::render::render_game_state;
+use crate::render::render_legend;
+use crate::render::Overlay;
+use crate::render::View;
+use crate::rng::Rng;
+use crate::search::think_with_limits;
+use crate::search::SearchLimits;
+use crate::setup::run_setup;
+use crate::setup::SetupAction;
 use crate::state::GameState;
+use crate::theme::get_theme;
+use crate::tui::run_tui_game;
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GameMode {
     ComputerBlack,
     ComputerWhite,
     NoComputer,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UiMode {
+    // full screen terminal user interface with cursor driven move entry
+    Full,
+    // line based interface for dumb terminals and scripting
+    Line,
+}
+
 pub struct Configuration {
     pub game_mode: GameMode,
     pub max_search_depth: u32,
     pub min_search_time: u32,
+    pub ui_mode: UiMode,
+    pub view: View,
+    pub time_control: Option<TimeControl>,
+    // warn about moves that lose more than this score
+    pub coach_threshold: Option<i32>,
+    pub level: Level,
+    // the computer's random choices are reproducible with the same seed
+    pub seed: u64,
+    // the game is saved here when it ends
+    pub save_path: Option<String>,
+    // the number of ranked lines shown when analyzing
+    pub multi_pv: usize,
+    // search the expected reply while the human thinks
+    pub ponder: bool,
 }
 
-pub fn start_gui(game_state: &mut GameState) {
-    let game_mode: GameMode;
-    let max_search_depth = get_max_search_depth();
-    let min_search_time = get_min_search_time();
+// The game mode is selected in a menu unless it is already given.
+#[allow(clippy::manual_is_multiple_of)]
+pub fn start_gui(
+    game_state: &mut GameState,
+    game_mode: Option<GameMode>,
+    mut config: Configuration,
+) {
+    if let Some(game_mode) = game_mode {
+        config.game_mode = game_mode;
+        run_game(game_state, config);
+        return;
+    }
+
+    let max_search_depth = config.max_search_depth;
+    let min_search_time = config.min_search_time;
 
     println!();
 
@@ -57,11 +111,19 @@ pub fn start_gui(game_state: &mut GameState) {
     println!("Configuration:");
     println!("    Computer search depth: {max_search_depth}");
     println!("    Computer search time: {min_search_time}");
+    if let Some(time_control) = config.time_control {
+        println!("    Time control: {time_control}");
+    }
+    println!("    Computer level: {}", config.level);
+    println!("    Seed: {}", config.seed);
+    println!("    Rules: {}", get_geometry());
     println!();
     println!("Select game mode:");
     println!("    b - play against computer as black");
     println!("    w - play against computer as white");
     println!("    h - play both sides");
+    println!("    l - choose the computer level");
+    println!("    s - set up a position");
     println!("    q - quit");
     loop {
         let mut buffer = String::new();
@@ -71,35 +133,102 @@ pub fn start_gui(game_state: &mut GameState) {
 
         match buffer.as_str() {
             "b" => {
-                game_mode = GameMode::ComputerWhite;
+                config.game_mode = GameMode::ComputerWhite;
                 break;
             }
             "w" => {
-                game_mode = GameMode::ComputerBlack;
+                config.game_mode = GameMode::ComputerBlack;
                 break;
             }
             "h" => {
-                game_mode = GameMode::NoComputer;
+                config.game_mode = GameMode::NoComputer;
                 break;
             }
+            "s" => match run_setup(game_state) {
+                Some(SetupAction::Play(mode)) => {
+                    config.game_mode = mode;
+                    break;
+                }
+                Some(SetupAction::Analyze) => {
+                    println!(
+                        "{} (quit with ctrl+c)",
+                        get_theme().success("analyze position")
+                    );
+                    analyze(game_state, &config);
+                    return;
+                }
+                None => println!("Select game mode: b/w/h/l/s/q"),
+            },
+            "l" => {
+                config.level = select_level(config.level);
+                println!("Select game mode: b/w/h/l/s/q");
+            }
             "q" => return,
-            _ => println!("Please enter one of the options: b/w/h/q"),
+            _ => println!("Please enter one of the options: b/w/h/l/s/q"),
         }
     }
 
-    let config = Configuration {
-        game_mode,
-        max_search_depth,
-        min_search_time,
-    };
-
     run_game(game_state, config);
 }
 
-fn run_game(game_state: &mut GameState, config: Configuration) {
+fn run_game(game_state: &mut GameState, mut config: Configuration) {
+    // the board is turned around so that the human's pieces are at the bottom
+    if config.game_mode == GameMode::ComputerWhite {
+        config.view.is_flipped = !config.view.is_flipped;
+    }
+
+    let mut record = GameRecord::new(game_state, config.game_mode, config.level, config.seed);
+    record.time_control = config.time_control;
+    let mut rng = Rng::new(config.seed);
+
+    if config.ui_mode == UiMode::Full {
+        run_tui_game(game_state, &config, &mut record, &mut rng);
+        if let Some(path) = &config.save_path {
+            save_game(&record, path);
+        }
+        return;
+    }
+
+    let theme = get_theme();
+    let mut last_move = None;
+    let mut clock = config.time_control.map(Clock::new);
+    let mut ponder: Option<Ponder> = None;
+
     loop {
         println!("*******************************************************");
-        println!("{game_state}");
+        println!(
+            "{}",
+            render_game_state(
+                game_state,
+                &Overlay::new(game_state, last_move),
+                theme,
+                &config.view
+            )
+        );
+        println!("{}", render_legend(theme));
+        if let Some(clock) = &clock {
+            println!("{}", clock.format_status(game_state.is_active_player_white));
+        }
+
+        if let Some(result) = game_state.get_result() {
+            println!("{}", theme.success(&result.to_string()));
+            record.result = Some(result.to_string());
+            break;
+        }
+
+        if let Some(result) = clock
+            .as_ref()
+            .and_then(Clock::get_result)
+        {
+            println!("{}", theme.success(&format!("{result} on time")));
+            record.result = Some(format!("{result} on time"));
+            break;
+        }
+
+        if let Some(clock) = &mut clock {
+            clock.start_turn(game_state.is_active_player_white);
+        }
+
         let moves = game_state.generate_moves();
         println!("{moves:?}");
         print_move_list_indices(&moves);
@@ -107,13 +236,47 @@ fn run_game(game_state: &mut GameState, config: Configuration) {
         //println!("{:?}", game_state.board);
 
         if is_computers_turn(game_state, &config.game_mode) {
-            let mov = think(game_state, config.max_search_depth, config.min_search_time);
-            println!("{COLOR_GREEN}computer moves {mov:?}{COLOR_RESET}");
+            let limits = get_search_limits(game_state, &config, clock.as_ref());
+            let mut predicted_move = None;
+            let mov = if let Some(mov) = probe_book(game_state) {
+                println!("{}", theme.highlight(&format!("book move {mov:?}")));
+                mov
+            } else if config.level.is_weakened() {
+                choose_weakened_move(game_state, config.level, &limits, &mut rng).unwrap()
+            } else {
+                let result = match ponder.take() {
+                    Some(ponder) if Some(ponder.get_predicted_move()) == last_move => {
+                        let result = ponder.hit(&limits);
+                        println!(
+                            "{}",
+                            theme.highlight(&format!(
+                                "ponder hit, depth {} score {:.3}",
+                                result.depth,
+                                result.score as f32 / 1000f32
+                            ))
+                        );
+                        result
+                    }
+                    _ => think_with_limits(game_state, &limits),
+                };
+                predicted_move = result.pv.get(1).copied();
+                result.best_move.unwrap()
+            };
+            println!("{}", theme.success(&format!("computer moves {mov:?}")));
 
             game_state.make_move(mov);
+            record.moves.push(mov);
+            last_move = Some(mov);
+            if let Some(clock) = &mut clock {
+                clock.finish_turn();
+            }
+
+            if let Some(predicted_move) = predicted_move.filter(|_| config.ponder) {
+                ponder = Ponder::start(game_state, predicted_move, limits.max_depth);
+            }
             continue;
         } else {
-            println!("Select a move number, type 'a' to analyze position or 'q' to quit:");
+            println!("Select a move number, type 'hint' for a suggestion, 'a' to analyze position, 'coach' to toggle the coach, 'f' to flip the board, 'i' to toggle square indices, 'save <file>' to save the game or 'q' to quit:");
         }
 
         let mut buffer = String::new();
@@ -122,48 +285,145 @@ fn run_game(game_state: &mut GameState, config: Configuration) {
 
         buffer.pop();
 
+        // the move does not count when the time ran out while entering it
+        if clock
+            .as_ref()
+            .and_then(Clock::get_result)
+            .is_some()
+        {
+            continue;
+        }
+
         if let Ok(move_list_index) = buffer.parse::<usize>() {
             if move_list_index == 0 || move_list_index > moves.len() {
-                println!("{COLOR_RED}invalid move number{COLOR_RESET}");
+                println!("{}", theme.error("invalid move number"));
             } else {
                 let mov = moves[move_list_index - 1];
-                println!("{COLOR_GREEN}you move {mov:?}{COLOR_RESET}");
+                println!("{}", theme.success(&format!("you move {mov:?}")));
+
+                // a wrong guess is stopped at once so that it does not slow down the next search
+                if ponder
+                    .as_ref()
+                    .is_some_and(|p| p.get_predicted_move() != mov)
+                {
+                    ponder = None;
+                }
 
+                let previous_game_state = game_state.clone();
                 game_state.make_move(mov);
+                record.moves.push(mov);
+                last_move = Some(mov);
+                if let Some(clock) = &mut clock {
+                    clock.finish_turn();
+                }
+
+                if let Some(threshold) = config.coach_threshold {
+                    // the review is a search of its own, it must not run next to the ponder
+                    ponder = None;
+                    let review = review_move(&previous_game_state, mov, config.max_search_depth);
+                    if review.is_blunder(threshold) {
+                        println!(
+                            "{}",
+                            theme.error(&format!("coach: {}", format_review_warning(&review)))
+                        );
+                    }
+                }
             }
+        } else if buffer == "hint" {
+            ponder = None;
+            let hint = get_hint(game_state, config.max_search_depth);
+            if let Some(mov) = hint.best_move {
+                println!("{}", theme.highlight(&format!("hint: {mov:?}")));
+            }
+        } else if buffer == "coach" {
+            config.coach_threshold = match config.coach_threshold {
+                Some(_) => None,
+                None => Some(DEFAULT_COACH_THRESHOLD),
+            };
+            let state = if config.coach_threshold.is_some() {
+                "on"
+            } else {
+                "off"
+            };
+            println!("{}", theme.success(&format!("coach {state}")));
         } else if buffer == "a" {
-            println!("{COLOR_GREEN}analyze position{COLOR_RESET} (quit with ctrl+c)");
-            think(game_state, 100, 1_000_000_000);
+            println!("{} (quit with ctrl+c)", theme.success("analyze position"));
+            ponder = None;
+            analyze(game_state, &config);
+        } else if buffer == "f" {
+            config.view.is_flipped = !config.view.is_flipped;
+        } else if buffer == "i" {
+            config.view.show_indices = !config.view.show_indices;
+        } else if let Some(path) = buffer.strip_prefix("save ") {
+            save_game(&record, path.trim());
         } else if buffer == "q" {
-            println!("{COLOR_GREEN}quit{COLOR_RESET}");
+            println!("{}", theme.success("quit"));
             break;
         } else {
-            println!("{COLOR_RED}invalid input{COLOR_RESET}");
+            println!("{}", theme.error("invalid input"));
         }
     }
+
+    if let Some(path) = &config.save_path {
+        save_game(&record, path);
+    }
 }
 
-fn get_max_search_depth() -> u32 {
-    if let Ok(msd_str) = std::env::var("MAX_SEARCH_DEPTH") {
-        if let Ok(msd_num) = msd_str.parse::<u32>() {
-            return msd_num;
-        }
-    };
+fn analyze(game_state: &GameState, config: &Configuration) {
+    let mut limits = SearchLimits::new(100, 1_000_000_000);
+    limits.multi_pv = config.multi_pv;
 
-    20
+    think_with_limits(game_state, &limits);
 }
 
-fn get_min_search_time() -> u32 {
-    if let Ok(mst_str) = std::env::var("MIN_SEARCH_TIME") {
-        if let Ok(mst_num) = mst_str.parse::<u32>() {
-            return mst_num;
+fn select_level(level: Level) -> Level {
+    println!("Select level:");
+    for (i, level) in LEVELS.iter().enumerate() {
+        println!("    {} - {level}", i + 1);
+    }
+
+    let mut buffer = String::new();
+    stdin().read_line(&mut buffer).unwrap();
+
+    match buffer.trim().parse::<Level>() {
+        Ok(level) => {
+            println!("{}", get_theme().success(&format!("level {level}")));
+            level
+        }
+        Err(error) => {
+            println!("{}", get_theme().error(&error));
+            level
         }
-    };
+    }
+}
+
+pub fn save_game(record: &GameRecord, path: &str) {
+    let theme = get_theme();
+
+    match write_game_record(path, record) {
+        Ok(()) => println!("{}", theme.success(&format!("game saved to {path}"))),
+        Err(error) => println!("{}", theme.error(&error)),
+    }
+}
+
+// with a clock the engine decides itself how long to think, lower levels search less
+pub fn get_search_limits(
+    game_state: &GameState,
+    config: &Configuration,
+    clock: Option<&Clock>,
+) -> SearchLimits {
+    let mut limits = SearchLimits::new(config.max_search_depth, config.min_search_time);
+
+    if let Some(clock) = clock {
+        let (min_time, max_time) = clock.allocate_time(game_state.is_active_player_white);
+        limits.min_time = min_time;
+        limits.max_time = Some(max_time);
+    }
 
-    1000
+    config.level.limit(&limits)
 }
 
-fn is_computers_turn(game_state: &GameState, game_mode: &GameMode) -> bool {
+pub fn is_computers_turn(game_state: &GameState, game_mode: &GameMode) -> bool {
     if *game_mode == GameMode::ComputerWhite && game_state.is_active_player_white {
         return true;
     }
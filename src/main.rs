@@ -1,20 +1,54 @@
-use self::gui::start_gui;
-use crate::state::GameState;
+use self::cli::parse_arguments;
+use self::cli::run_command;
 
-#[allow(unused)]
+mod batch;
 mod bit_board_gen;
 #[allow(unused)]
 mod bit_utils;
+mod book;
+mod cli;
+mod clock;
+mod coach;
 mod constants;
+mod difficulty;
+mod game_record;
+mod geometry;
 mod gui;
 mod move_gen;
+mod notation;
 mod piece_list;
+mod ponder;
+mod proof;
+mod protocol;
+mod random_position;
+mod render;
+mod rng;
+mod rules;
 mod search;
+mod setup;
+mod shape_index;
 mod state;
+mod symmetry;
+mod tablebase;
+mod test_suite;
+mod theme;
+mod tui;
 mod valid_board_gen;
+mod zobrist;
 
 fn main() {
-    let mut game_state = GameState::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let arguments = match parse_arguments(&args) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            eprintln!("error: {error}\n\nFor more information, try '--help'.");
+            std::process::exit(2);
+        }
+    };
 
-    start_gui(&mut game_state);
+    if let Err(error) = run_command(arguments) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
 }
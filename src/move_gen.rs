@@ -1,16 +1,16 @@
 use std::fmt::Debug;
+use std::fmt::Display;
+use std::str::FromStr;
 
 use crate::bit_utils::get_bit_indices;
 use crate::bit_utils::is_board_bit_set;
 use crate::bit_utils::jump_bit;
 use crate::constants::BitBoard;
 use crate::constants::BoardIndex;
-use crate::constants::BOARD_WIDTH;
-use crate::constants::COLOR_RESET;
-use crate::constants::COLOR_YELLOW;
-use crate::constants::JUMP_MOVES;
-use crate::constants::KING_MOVES;
+use crate::geometry::get_geometry;
+use crate::geometry::BoardGeometry;
 use crate::state::GameState;
+use crate::theme::get_theme;
 use crate::valid_board_gen::is_connected;
 
 // note that 'move' is a rust keyword, so when intended as variable 'mov' is used instead here
@@ -25,30 +25,84 @@ impl Move {
         Self { from, to }
     }
 
+    // A jump goes two squares along a rank or a file. The index difference
+    // alone is not enough, on 3 files a diagonal step also differs by 2.
     pub fn is_jump(&self) -> bool {
-        let from = self.from as i8;
-        let to = self.to as i8;
+        let width = get_geometry().width;
+        let files = (self.from % width).abs_diff(self.to % width);
+        let ranks = (self.from / width).abs_diff(self.to / width);
 
-        let diff = from - to;
+        matches!((files, ranks), (2, 0) | (0, 2))
+    }
+
+    pub fn get_from(&self) -> BoardIndex {
+        self.from
+    }
+
+    pub fn get_to(&self) -> BoardIndex {
+        self.to
+    }
+}
+
+// Moves are written as the coordinates of both squares, e.g. "c3e3".
+// When parsing, "c3-e3" and "c3->e3" are accepted as well.
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", square_name(self.from), square_name(self.to))
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let squares = s.replace("->", "").replace('-', "");
+
+        if squares.len() != 4 || !squares.is_ascii() {
+            return Err(format!("invalid move '{s}', expected e.g. 'c3e3'"));
+        }
+
+        let from = parse_square(&squares[0..2]).ok_or(format!("invalid square in move '{s}'"))?;
+        let to = parse_square(&squares[2..4]).ok_or(format!("invalid square in move '{s}'"))?;
+
+        Ok(Move::new(from, to))
+    }
+}
+
+pub fn square_name(i: BoardIndex) -> String {
+    let width = get_geometry().width;
+    let file = (b'a' + i % width) as char;
+    let rank = i / width + 1;
+
+    format!("{file}{rank}")
+}
+
+pub fn parse_square(s: &str) -> Option<BoardIndex> {
+    let mut chars = s.chars();
+
+    let file = chars.next()? as u8;
+    let rank = chars.next()?.to_digit(10)? as u8;
 
-        matches!(diff, 2 | -2 | 14 | -14)
+    if chars.next().is_some() {
+        return None;
     }
+
+    let geometry = get_geometry();
+    if !(b'a'..b'a' + geometry.width).contains(&file) || !(1..=geometry.height).contains(&rank) {
+        return None;
+    }
+
+    Some(geometry.get_index(file - b'a', rank - 1))
 }
 
 impl Debug for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let from_file = (b'a' + self.from % BOARD_WIDTH) as char;
-        let from_rank = self.from / BOARD_WIDTH + 1;
-        let to_file = (b'a' + self.to % BOARD_WIDTH) as char;
-        let to_rank = self.to / BOARD_WIDTH + 1;
+        let text = format!("{}->{}", square_name(self.from), square_name(self.to));
 
         if self.is_jump() {
-            write!(f, "{from_file}{from_rank}->{to_file}{to_rank}")
+            write!(f, "{text}")
         } else {
-            write!(
-                f,
-                "{COLOR_YELLOW}{from_file}{from_rank}->{to_file}{to_rank}{COLOR_RESET}"
-            )
+            write!(f, "{}", get_theme().highlight(&text))
         }
     }
 }
@@ -56,8 +110,13 @@ impl Debug for Move {
 impl GameState {
     pub fn generate_moves(&self) -> Vec<Move> {
         let mut moves = Vec::<Move>::new();
+        let geometry = get_geometry();
 
-        if self.board.white_kings.len() == 6 || self.board.black_kings.len() == 6 {
+        let kings_to_win = geometry.get_kings_to_win();
+
+        if self.board.white_kings.len() >= kings_to_win
+            || self.board.black_kings.len() >= kings_to_win
+        {
             return moves;
         }
 
@@ -65,19 +124,44 @@ impl GameState {
 
         // note that this move order improves the alpha-beta search
         for pawn in pawns {
-            self.append_jump_moves(&mut moves, pawn);
+            self.append_jump_moves(&mut moves, geometry, pawn);
         }
 
         for king in kings {
-            self.append_jump_moves(&mut moves, king);
-            self.append_king_moves(&mut moves, king);
+            self.append_jump_moves(&mut moves, geometry, king);
+            if geometry.rules.king_steps {
+                self.append_king_moves(&mut moves, geometry, king);
+            }
         }
 
         moves
     }
 
-    fn append_king_moves(&self, moves: &mut Vec<Move>, from: BoardIndex) {
-        let king_moves = KING_MOVES[from as usize];
+    // counts the leaf nodes of the move tree, useful to verify and benchmark the move generator
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.generate_moves();
+
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+
+        for mov in moves {
+            let mut game_state = self.clone();
+            game_state.make_move(mov);
+            nodes += game_state.perft(depth - 1);
+        }
+
+        nodes
+    }
+
+    fn append_king_moves(&self, moves: &mut Vec<Move>, geometry: &BoardGeometry, from: BoardIndex) {
+        let king_moves = geometry.king_moves[from as usize];
         let allowed_jump_bits = king_moves & !self.board.piece_bits;
         let allowed_jump_indices = get_bit_indices(allowed_jump_bits);
 
@@ -91,13 +175,13 @@ impl GameState {
         }
     }
 
-    fn append_jump_moves(&self, moves: &mut Vec<Move>, from: BoardIndex) {
-        let jump_moves = JUMP_MOVES[from as usize];
+    fn append_jump_moves(&self, moves: &mut Vec<Move>, geometry: &BoardGeometry, from: BoardIndex) {
+        let jump_moves = geometry.jump_moves[from as usize];
         let allowed_jump_bits = jump_moves & !self.board.piece_bits;
         let allowed_jump_indices = get_bit_indices(allowed_jump_bits);
 
         for to in allowed_jump_indices {
-            if !is_between_occupied(self.board.piece_bits, from, to) {
+            if !is_between_occupied(self.board.piece_bits, geometry.width, from, to) {
                 continue;
             }
 
@@ -136,13 +220,13 @@ impl GameState {
     }
 }
 
-fn is_between_occupied(board: BitBoard, a1: BoardIndex, a2: BoardIndex) -> bool {
-    is_board_bit_set(board, calculate_between_index(a1, a2))
+fn is_between_occupied(board: BitBoard, width: u8, a1: BoardIndex, a2: BoardIndex) -> bool {
+    is_board_bit_set(board, calculate_between_index(width, a1, a2))
 }
 
-fn calculate_between_index(a1: BoardIndex, a2: BoardIndex) -> BoardIndex {
+fn calculate_between_index(width: u8, a1: BoardIndex, a2: BoardIndex) -> BoardIndex {
     if a1 > a2 {
-        return calculate_between_index(a2, a1);
+        return calculate_between_index(width, a2, a1);
     }
 
     let diff = a2 - a1;
@@ -151,8 +235,8 @@ fn calculate_between_index(a1: BoardIndex, a2: BoardIndex) -> BoardIndex {
         return a1 + 1;
     }
 
-    if diff == 14 {
-        return a1 + 7;
+    if diff == 2 * width {
+        return a1 + width;
     }
 
     panic!();
@@ -179,6 +263,17 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_perft_from_initial_position() {
+        let game_state = GameState::new();
+
+        assert_eq!(game_state.perft(0), 1);
+        assert_eq!(game_state.perft(1), 4);
+        assert_eq!(game_state.perft(2), 18);
+        assert_eq!(game_state.perft(3), 114);
+        assert_eq!(game_state.perft(4), 781);
+    }
+
     #[test]
     fn test_is_jump() {
         assert!(!Move::new(0, 0).is_jump());
@@ -191,4 +286,20 @@ mod test {
         assert!(Move::new(3, 17).is_jump());
         assert!(Move::new(17, 3).is_jump());
     }
+
+    #[test]
+    fn test_move_text() {
+        assert_eq!(Move::new(17, 19).to_string(), "d3f3");
+        assert_eq!(Move::new(0, 41).to_string(), "a1g6");
+
+        assert_eq!("d3f3".parse::<Move>(), Ok(Move::new(17, 19)));
+        assert_eq!("d3-f3".parse::<Move>(), Ok(Move::new(17, 19)));
+        assert_eq!("d3->f3".parse::<Move>(), Ok(Move::new(17, 19)));
+        assert_eq!("g6a1".parse::<Move>(), Ok(Move::new(41, 0)));
+
+        assert!("d3f".parse::<Move>().is_err());
+        assert!("d3h3".parse::<Move>().is_err());
+        assert!("d0f3".parse::<Move>().is_err());
+        assert!("d3f7".parse::<Move>().is_err());
+    }
 }
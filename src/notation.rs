@@ -0,0 +1,268 @@
+use crate::constants::BoardIndex;
+use crate::geometry::get_geometry;
+use crate::piece_list::PieceList;
+use crate::state::Board;
+use crate::state::GameState;
+
+// A position is written similar to the FEN notation known from chess:
+// the ranks from 1 up (top to bottom as displayed) separated by '/', followed by
+// the active player ('w' or 'b') and optionally the move count.
+//   'P' white pawn, 'K' white king, 'p' black pawn, 'k' black king,
+//   digits are runs of empty squares
+// The initial position is "7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0".
+pub const START_POSITION: &str = "7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0";
+
+impl GameState {
+    pub fn from_notation(notation: &str) -> Result<Self, String> {
+        let mut fields = notation.split_whitespace();
+
+        let ranks = fields
+            .next()
+            .ok_or("empty position notation")?;
+        let board = Board::from_notation(ranks)?;
+
+        let is_active_player_white = match fields.next() {
+            Some("w") | None => true,
+            Some("b") => false,
+            Some(s) => return Err(format!("invalid active player '{s}', expected 'w' or 'b'")),
+        };
+
+        let move_count = match fields.next() {
+            Some(s) => s
+                .parse::<u32>()
+                .map_err(|_| format!("invalid move count '{s}'"))?,
+            None => 0,
+        };
+
+        if let Some(s) = fields.next() {
+            return Err(format!("unexpected trailing input '{s}'"));
+        }
+
+        Ok(Self {
+            board,
+            is_active_player_white,
+            move_count,
+        })
+    }
+
+    pub fn to_notation(&self) -> String {
+        let active_player = if self.is_active_player_white {
+            'w'
+        } else {
+            'b'
+        };
+
+        format!(
+            "{} {active_player} {}",
+            self.board.to_notation(),
+            self.move_count
+        )
+    }
+}
+
+impl Board {
+    pub fn from_notation(ranks: &str) -> Result<Self, String> {
+        let geometry = get_geometry();
+        let (width, height) = (geometry.width, geometry.height);
+        let pieces_per_side = geometry.pieces_per_side;
+
+        let rank_strs: Vec<&str> = ranks.split('/').collect();
+        if rank_strs.len() != height as usize {
+            return Err(format!(
+                "expected {height} ranks separated by '/', found {}",
+                rank_strs.len()
+            ));
+        }
+
+        let mut black_kings = Vec::new();
+        let mut black_pawns = Vec::new();
+        let mut white_kings = Vec::new();
+        let mut white_pawns = Vec::new();
+
+        for (y, rank_str) in rank_strs.iter().enumerate() {
+            let mut x: u8 = 0;
+            let mut chars = rank_str.chars().peekable();
+
+            while let Some(c) = chars.next() {
+                // runs of more than 9 empty squares on wide boards have several digits
+                if let Some(mut empty) = c.to_digit(10) {
+                    while let Some(digit) = chars
+                        .peek()
+                        .and_then(|c| c.to_digit(10))
+                    {
+                        empty = (empty * 10 + digit).min(u8::MAX as u32);
+                        chars.next();
+                    }
+                    if empty > (width - x) as u32 {
+                        return Err(format!("rank {} is longer than {width}", y + 1));
+                    }
+                    x += empty as u8;
+                    continue;
+                }
+
+                if x >= width {
+                    return Err(format!("rank {} is longer than {width}", y + 1));
+                }
+
+                let i = geometry.get_index(x, y as BoardIndex);
+                match c {
+                    'k' => black_kings.push(i),
+                    'p' => black_pawns.push(i),
+                    'K' => white_kings.push(i),
+                    'P' => white_pawns.push(i),
+                    _ => return Err(format!("invalid piece character '{c}'")),
+                }
+
+                x += 1;
+            }
+
+            if x != width {
+                return Err(format!("rank {} has {x} squares, expected {width}", y + 1));
+            }
+        }
+
+        if black_kings.len() + black_pawns.len() > pieces_per_side
+            || white_kings.len() + white_pawns.len() > pieces_per_side
+        {
+            return Err(format!(
+                "each side must have exactly {pieces_per_side} pieces"
+            ));
+        }
+
+        let piece_bits = black_kings
+            .iter()
+            .chain(&black_pawns)
+            .chain(&white_kings)
+            .chain(&white_pawns)
+            .fold(0, |bits, i| bits | 1 << i);
+
+        let board = Self {
+            piece_bits,
+            black_kings: PieceList::from(black_kings),
+            black_pawns: PieceList::from(black_pawns),
+            white_kings: PieceList::from(white_kings),
+            white_pawns: PieceList::from(white_pawns),
+        };
+
+        if let Some(problem) = board.validate().first() {
+            return Err(problem.clone());
+        }
+
+        Ok(board)
+    }
+
+    pub fn to_notation(&self) -> String {
+        let geometry = get_geometry();
+        let mut squares = vec![None; geometry.get_square_count() as usize];
+
+        for i in self.black_kings {
+            squares[i as usize] = Some('k');
+        }
+        for i in self.black_pawns {
+            squares[i as usize] = Some('p');
+        }
+        for i in self.white_kings {
+            squares[i as usize] = Some('K');
+        }
+        for i in self.white_pawns {
+            squares[i as usize] = Some('P');
+        }
+
+        let mut ranks = Vec::new();
+
+        for rank_squares in squares.chunks(geometry.width as usize) {
+            let mut rank = String::new();
+            let mut empty = 0;
+
+            for square in rank_squares {
+                match square {
+                    Some(c) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        rank.push(*c);
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+
+            ranks.push(rank);
+        }
+
+        ranks.join("/")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::set_test_geometry;
+    use crate::geometry::BoardGeometry;
+    use crate::move_gen::Move;
+    use crate::rules::STANDARD_RULES;
+
+    #[test]
+    fn test_start_position_notation() {
+        let game_state = GameState::new();
+
+        assert_eq!(game_state.to_notation(), START_POSITION);
+
+        let parsed = GameState::from_notation(START_POSITION).unwrap();
+        assert_eq!(parsed.board.piece_bits, game_state.board.piece_bits);
+        assert_eq!(parsed.generate_moves(), game_state.generate_moves());
+    }
+
+    #[test]
+    fn test_notation_round_trip() {
+        let mut game_state = GameState::new();
+        game_state.make_move(Move::new(17, 19));
+        assert!(game_state
+            .generate_moves()
+            .contains(&Move::new(25, 11)));
+        game_state.make_move(Move::new(25, 11));
+
+        let notation = game_state.to_notation();
+        assert_eq!(notation, "7/2PPk2/1PP1PK1/2pp1p1/3pp2/7 w 2");
+
+        let parsed = GameState::from_notation(&notation).unwrap();
+        assert_eq!(parsed.to_notation(), notation);
+        assert_eq!(parsed.move_count, 2);
+        assert!(parsed.is_active_player_white);
+    }
+
+    #[test]
+    fn test_wide_board_notation() {
+        set_test_geometry(BoardGeometry::new(12, 5, 7, STANDARD_RULES).unwrap());
+
+        let notation = GameState::new().to_notation();
+        assert_eq!(notation, "4PPPP4/4PPPp4/4pppp4/4pp6/12 w 0");
+        assert_eq!(
+            GameState::from_notation(&notation)
+                .unwrap()
+                .to_notation(),
+            notation
+        );
+        assert!(GameState::from_notation("4PPPP4/4PPPp4/4pppp4/4pp6/13 w 0").is_err());
+    }
+
+    #[test]
+    fn test_invalid_notation() {
+        assert!(GameState::from_notation("").is_err());
+        assert!(GameState::from_notation("7/2PP3/1PPPP2/2pppp1/3pp2 w").is_err());
+        assert!(GameState::from_notation("7/2PP3/1PPPP2/2pppp1/3pp2/7 x").is_err());
+        assert!(GameState::from_notation("7/2PP3/1PPPP2/2pppp1/3pp3/7 w").is_err());
+        assert!(GameState::from_notation("7/2PP3/1PPPP2/2pppq1/3pp2/7 w").is_err());
+        assert!(GameState::from_notation("7/2PP3/1PPPP2/2ppp2/3pp2/7 w").is_err());
+        assert!(GameState::from_notation("P6/3P3/1PPPP2/2pppp1/3pp2/7 w").is_err());
+        assert!(GameState::from_notation(&format!(
+            "{}/2PP3/1PPPP2/2pppp1/3pp2/7 w 0",
+            "9".repeat(40)
+        ))
+        .is_err());
+    }
+}
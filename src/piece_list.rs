@@ -3,6 +3,7 @@ use std::fmt::Display;
 
 use crate::constants::BoardIndex;
 use crate::constants::BoardIndexList;
+use crate::geometry::MAX_PIECES_PER_SIDE;
 
 const END_OF_LIST: BoardIndex = 0xff;
 const EMPTY_PIECE_LIST: u64 = 0xffffffffffffffff;
@@ -95,7 +96,7 @@ impl PieceList {
         let mut new_piece_list = PieceList::new();
         let mut has_changed = false;
 
-        for i in 0..6 {
+        for i in 0..MAX_PIECES_PER_SIDE {
             let v = ((self.pieces >> (i * 8)) & 0xff) as u8;
 
             if v == END_OF_LIST {
@@ -115,7 +116,7 @@ impl PieceList {
     }
 
     pub fn contains(&self, value: BoardIndex) -> bool {
-        for i in 0..6 {
+        for i in 0..MAX_PIECES_PER_SIDE {
             let v = ((self.pieces >> (i * 8)) & 0xff) as u8;
             if v == value {
                 return true;
@@ -233,6 +234,17 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_seven_pieces() {
+        let mut piece_list = PieceList::from(vec![1, 2, 3, 4, 5, 6, 7]);
+
+        assert_eq!(piece_list.len(), 7);
+        assert!(piece_list.contains(7));
+        assert!(piece_list.find_and_remove(7));
+        assert!(!piece_list.contains(7));
+        assert_eq!(piece_list.len(), 6);
+    }
+
     #[test]
     fn test_remove() {
         let mut piece_list = PieceList::from(0xffff060504030201);
@@ -0,0 +1,132 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::move_gen::Move;
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::search::SearchResult;
+use crate::state::GameState;
+
+// how often a ponder hit checks whether the search had enough time
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// While the human thinks, the engine already searches the position after the
+// reply it expects. Dropping a ponder search stops it, so a wrong guess costs
+// nothing once the human has moved.
+pub struct Ponder {
+    predicted_move: Move,
+    start_instant: Instant,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<SearchResult>>,
+}
+
+impl Ponder {
+    // game_state is the position after the engine's move, there is nothing to
+    // ponder when the predicted reply ends the game
+    pub fn start(game_state: &GameState, predicted_move: Move, max_depth: u32) -> Option<Self> {
+        if game_state.get_result().is_some()
+            || !game_state
+                .generate_moves()
+                .contains(&predicted_move)
+        {
+            return None;
+        }
+
+        let mut ponder_game_state = game_state.clone();
+        ponder_game_state.make_move(predicted_move);
+
+        if ponder_game_state.get_result().is_some() {
+            return None;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut limits = SearchLimits::new(max_depth, u32::MAX);
+        limits.stop = Some(stop.clone());
+
+        let handle = thread::spawn(move || think_with(&ponder_game_state, &limits, &mut |_| {}));
+
+        Some(Self {
+            predicted_move,
+            start_instant: Instant::now(),
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn get_predicted_move(&self) -> Move {
+        self.predicted_move
+    }
+
+    // The human played the predicted move. The time spent pondering counts as
+    // thinking time, so the search only continues until it has had min_time
+    // of the limits in total.
+    pub fn hit(mut self, limits: &SearchLimits) -> SearchResult {
+        let handle = self.handle.take().unwrap();
+
+        while !handle.is_finished()
+            && self.start_instant.elapsed().as_millis() < limits.min_time.into()
+        {
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        self.stop.store(true, Ordering::Relaxed);
+        handle.join().unwrap()
+    }
+}
+
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ponder_hit() {
+        let mut game_state = GameState::new();
+        let predicted_move = game_state.generate_moves()[0];
+
+        let ponder = Ponder::start(&game_state, predicted_move, 6).unwrap();
+        assert_eq!(ponder.get_predicted_move(), predicted_move);
+
+        let result = ponder.hit(&SearchLimits::new(6, 0));
+        game_state.make_move(predicted_move);
+
+        assert!(result.depth >= 1);
+        assert!(game_state
+            .generate_moves()
+            .contains(&result.best_move.unwrap()));
+    }
+
+    #[test]
+    fn test_ponder_cancel() {
+        let game_state = GameState::new();
+        let predicted_move = game_state.generate_moves()[0];
+
+        // without the stop flag this search would take practically forever
+        let ponder = Ponder::start(&game_state, predicted_move, 100).unwrap();
+        let start_instant = Instant::now();
+        drop(ponder);
+
+        assert!(start_instant.elapsed().as_millis() < 1000);
+    }
+
+    #[test]
+    fn test_ponder_invalid_prediction() {
+        let game_state = GameState::new();
+
+        assert!(Ponder::start(&game_state, Move::new(0, 1), 6).is_none());
+    }
+}
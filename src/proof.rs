@@ -0,0 +1,906 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::move_gen::Move;
+use crate::state::GameResult;
+use crate::state::GameState;
+use crate::symmetry::Symmetry;
+use crate::symmetry::MIRRORS;
+use crate::tablebase::get_position_key;
+
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000_000;
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+pub const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(600);
+
+const INFINITY: u64 = u64::MAX / 4;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"uisge-pn";
+const CHECKPOINT_VERSION: u8 = 2;
+const CHECKPOINT_HEADER_BYTES: usize = 8 + 1 + 1 + 16 + 8 + 8;
+const CHECKPOINT_ENTRY_BYTES: usize = 16 + 8 + 8 + 8 + 2;
+const NO_MOVE: u16 = 0xffff;
+
+const PROOF_HEADER: &str = "# uisge proof";
+
+#[derive(Debug)]
+pub struct ProofLimits {
+    pub max_nodes: Option<u64>,
+    // the table forgets the least searched positions when it grows beyond this
+    pub max_entries: usize,
+    // the longest line the search follows, deeper positions are no win for
+    // the claimed winner so that the recursion cannot overflow the stack
+    pub max_depth: usize,
+    pub checkpoint_path: Option<String>,
+    pub checkpoint_interval: Duration,
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl Default for ProofLimits {
+    fn default() -> Self {
+        Self {
+            max_nodes: None,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_depth: DEFAULT_MAX_DEPTH,
+            checkpoint_path: None,
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            stop: None,
+        }
+    }
+}
+
+// the proof and disproof numbers of the root are those of the claimed win
+pub struct ProofProgress {
+    pub nodes: u64,
+    pub entries: usize,
+    pub proof_number: u64,
+    pub disproof_number: u64,
+    pub elapsed: Duration,
+}
+
+pub enum ProofOutcome {
+    Proven(Proof),
+    // The search found no forced win. Repetitions and the depth limit make
+    // this depend on the paths that were searched, so it is not verified.
+    UnverifiedDisproof,
+    // the search stopped before it could decide
+    Unknown,
+}
+
+// The numbers are stored for the side to move: phi is the proof number of a
+// win for that side and delta its disproof number. The winning move is the one
+// that proved the position first, it only leads to positions that were proven
+// earlier, so following these moves never goes around in circles.
+// Mirrored positions share one entry, the one of their canonical mirror image,
+// and the winning move is stored for that image.
+#[derive(Clone, Copy)]
+struct Entry {
+    phi: u64,
+    delta: u64,
+    work: u64,
+    winning_move: Option<Move>,
+}
+
+// Depth-first proof-number search (df-pn) that tries to prove that the given
+// side can force a win. Repeating a position never counts as a win, so the
+// claimed winner has to make progress. Because such values depend on the path
+// to a position, a disproof can be wrong in rare cases and is reported as
+// unverified, proofs are checked by Proof::verify before they are reported.
+struct ProofSearch<'a> {
+    winner_is_white: bool,
+    limits: &'a ProofLimits,
+    on_progress: &'a mut dyn FnMut(&ProofProgress),
+    table: HashMap<u128, Entry>,
+    path: HashSet<u128>,
+    root_key: u128,
+    root_children: Vec<Child>,
+    is_winner_to_move_at_root: bool,
+    nodes: u64,
+    start_instant: Instant,
+    last_progress: Instant,
+    last_checkpoint: Instant,
+    error: Option<String>,
+    is_aborted: bool,
+}
+
+impl ProofSearch<'_> {
+    fn is_winner_to_move(&self, game_state: &GameState) -> bool {
+        game_state.is_active_player_white == self.winner_is_white
+    }
+
+    // the values of a position as they are known without searching it
+    fn lookup(&self, child: &Child) -> (u64, u64) {
+        if let Some(values) = child.terminal_values {
+            return values;
+        }
+
+        let entry = self.table.get(&child.key);
+        let is_too_deep = self.path.len() >= self.limits.max_depth && !entry.is_some_and(is_solved);
+
+        // going around in circles is no win for the claimed winner
+        if self.path.contains(&child.key) || is_too_deep {
+            return match self.is_winner_to_move(&child.game_state) {
+                true => (INFINITY, 0),
+                false => (0, INFINITY),
+            };
+        }
+
+        entry.map_or((1, 1), |entry| (entry.phi, entry.delta))
+    }
+
+    // A position is won if one move leads to a lost position and lost if all
+    // moves lead to won ones. Returns phi and delta of the position, the most
+    // promising child and the second smallest delta of the children.
+    fn evaluate(&self, children: &[Child]) -> (u64, u64, usize, u64) {
+        let mut phi = INFINITY;
+        let mut delta: u64 = 0;
+        let mut best = 0;
+        let mut second_best_delta = INFINITY;
+
+        for (i, child) in children.iter().enumerate() {
+            let (child_phi, child_delta) = self.lookup(child);
+
+            delta = delta
+                .saturating_add(child_phi)
+                .min(INFINITY);
+
+            if child_delta < phi {
+                second_best_delta = phi;
+                phi = child_delta;
+                best = i;
+            } else if child_delta < second_best_delta {
+                second_best_delta = child_delta;
+            }
+        }
+
+        (phi, delta, best, second_best_delta)
+    }
+
+    fn store(&mut self, key: u128, phi: u64, delta: u64, work: u64, winning_move: Option<Move>) {
+        let entry = self.table.entry(key).or_insert(Entry {
+            phi,
+            delta,
+            work: 0,
+            winning_move: None,
+        });
+        entry.phi = phi;
+        entry.delta = delta;
+        entry.work += work;
+        entry.winning_move = entry.winning_move.or(winning_move);
+
+        if self.table.len() > self.limits.max_entries {
+            self.collect_garbage();
+        }
+    }
+
+    // forgets the unsolved positions with less than the median work, solved
+    // positions are kept because the proof is built from them
+    fn collect_garbage(&mut self) {
+        let mut works: Vec<u64> = self
+            .table
+            .values()
+            .filter(|entry| !is_solved(entry))
+            .map(|entry| entry.work)
+            .collect();
+
+        if works.is_empty() {
+            return;
+        }
+
+        let middle = works.len() / 2;
+        let median = *works.select_nth_unstable(middle).1;
+
+        self.table
+            .retain(|_, entry| is_solved(entry) || entry.work > median);
+    }
+
+    fn check_limits(&mut self) {
+        if let Some(stop) = &self.limits.stop {
+            if stop.load(Ordering::Relaxed) {
+                self.is_aborted = true;
+            }
+        }
+
+        if let Some(max_nodes) = self.limits.max_nodes {
+            if self.nodes >= max_nodes {
+                self.is_aborted = true;
+            }
+        }
+
+        // looking at the clock is comparatively expensive
+        if !self.nodes.is_multiple_of(1024) {
+            return;
+        }
+
+        if self.last_progress.elapsed() >= PROGRESS_INTERVAL {
+            self.last_progress = Instant::now();
+            self.report_progress();
+        }
+
+        if self.limits.checkpoint_path.is_some()
+            && self.last_checkpoint.elapsed() >= self.limits.checkpoint_interval
+        {
+            self.last_checkpoint = Instant::now();
+            if let Err(error) = self.write_checkpoint() {
+                self.error = Some(error);
+                self.is_aborted = true;
+            }
+        }
+    }
+
+    fn report_progress(&mut self) {
+        let (proof_number, disproof_number) = self.get_root_numbers();
+
+        (self.on_progress)(&ProofProgress {
+            nodes: self.nodes,
+            entries: self.table.len(),
+            proof_number,
+            disproof_number,
+            elapsed: self.start_instant.elapsed(),
+        });
+    }
+
+    // the root is only stored when the search returns, in between its
+    // numbers are those of its children
+    fn get_root_numbers(&self) -> (u64, u64) {
+        let (phi, delta) = match self.table.get(&self.root_key) {
+            Some(entry) if is_solved(entry) || self.root_children.is_empty() => {
+                (entry.phi, entry.delta)
+            }
+            _ if !self.root_children.is_empty() => {
+                let (phi, delta, _, _) = self.evaluate(&self.root_children);
+                (phi, delta)
+            }
+            _ => (1, 1),
+        };
+
+        match self.is_winner_to_move_at_root {
+            true => (phi, delta),
+            false => (delta, phi),
+        }
+    }
+
+    // Searches the position until its phi reaches th_phi or its delta reaches
+    // th_delta, the children get thresholds that keep the search in the most
+    // promising subtree for as long as possible.
+    fn mid(
+        &mut self,
+        game_state: &GameState,
+        key: u128,
+        symmetry: Symmetry,
+        th_phi: u64,
+        th_delta: u64,
+    ) {
+        self.nodes += 1;
+        let start_nodes = self.nodes;
+        self.check_limits();
+
+        if let Some((phi, delta)) = get_terminal_values(game_state) {
+            self.store(key, phi, delta, 1, None);
+            return;
+        }
+
+        let children = get_children(game_state);
+        self.path.insert(key);
+
+        loop {
+            let (phi, delta, best, second_best_delta) = self.evaluate(&children);
+
+            if phi >= th_phi || delta >= th_delta || self.is_aborted {
+                let winning_move = (phi == 0 && self.is_winner_to_move(game_state))
+                    .then(|| symmetry.map_move(children[best].mov));
+                self.store(key, phi, delta, self.nodes - start_nodes + 1, winning_move);
+                break;
+            }
+
+            let child = &children[best];
+            let (child_phi, _) = self.lookup(child);
+
+            let child_th_phi = (th_delta - delta)
+                .saturating_add(child_phi)
+                .min(INFINITY);
+            let child_th_delta = th_phi.min(second_best_delta.saturating_add(1));
+
+            self.mid(
+                &child.game_state,
+                child.key,
+                child.symmetry,
+                child_th_phi,
+                child_th_delta,
+            );
+        }
+
+        self.path.remove(&key);
+    }
+
+    // the winning moves for every position of the winner that can be reached
+    // when the loser tries all moves
+    fn extract_proof(&self, root: &GameState) -> Result<Proof, String> {
+        let mut proof = Proof::new(root, self.winner_is_white);
+        let mut visited = HashSet::new();
+        let mut positions = vec![root.clone()];
+
+        while let Some(game_state) = positions.pop() {
+            if !visited.insert(get_position_key(&game_state)) || game_state.get_result().is_some() {
+                continue;
+            }
+
+            if self.is_winner_to_move(&game_state) {
+                let (key, symmetry) = get_table_key(&game_state);
+                let mov = self
+                    .table
+                    .get(&key)
+                    .and_then(|entry| entry.winning_move)
+                    .map(|mov| symmetry.inverse().map_move(mov))
+                    .ok_or(format!(
+                        "the table has no winning move for {}",
+                        game_state.to_notation()
+                    ))?;
+
+                let mut child = game_state.clone();
+                child.make_move(mov);
+                proof.insert(&game_state, mov);
+                positions.push(child);
+            } else {
+                for mov in game_state.generate_moves() {
+                    let mut child = game_state.clone();
+                    child.make_move(mov);
+                    positions.push(child);
+                }
+            }
+        }
+
+        Ok(proof)
+    }
+
+    fn write_checkpoint(&self) -> Result<(), String> {
+        let Some(path) = &self.limits.checkpoint_path else {
+            return Ok(());
+        };
+
+        let mut bytes =
+            Vec::with_capacity(CHECKPOINT_HEADER_BYTES + self.table.len() * CHECKPOINT_ENTRY_BYTES);
+
+        bytes.extend_from_slice(CHECKPOINT_MAGIC);
+        bytes.push(CHECKPOINT_VERSION);
+        bytes.push(self.winner_is_white as u8);
+        bytes.extend_from_slice(&self.root_key.to_le_bytes());
+        bytes.extend_from_slice(&self.nodes.to_le_bytes());
+        bytes.extend_from_slice(&(self.table.len() as u64).to_le_bytes());
+
+        for (key, entry) in &self.table {
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.extend_from_slice(&entry.phi.to_le_bytes());
+            bytes.extend_from_slice(&entry.delta.to_le_bytes());
+            bytes.extend_from_slice(&entry.work.to_le_bytes());
+            bytes.extend_from_slice(&encode_move(entry.winning_move).to_le_bytes());
+        }
+
+        // an interrupted write must not destroy the previous checkpoint
+        let temporary_path = format!("{path}.tmp");
+        fs::write(&temporary_path, bytes)
+            .map_err(|error| format!("could not write '{temporary_path}': {error}"))?;
+        fs::rename(&temporary_path, path)
+            .map_err(|error| format!("could not write '{path}': {error}"))
+    }
+
+    fn read_checkpoint(&mut self, path: &str) -> Result<(), String> {
+        let bytes = fs::read(path).map_err(|error| format!("could not read '{path}': {error}"))?;
+        let error = |message: &str| format!("{path}: {message}");
+
+        if bytes.len() < CHECKPOINT_HEADER_BYTES || &bytes[..8] != CHECKPOINT_MAGIC {
+            return Err(error("not a proof search checkpoint"));
+        }
+
+        if bytes[8] != CHECKPOINT_VERSION {
+            return Err(error(&format!(
+                "unsupported checkpoint version {}",
+                bytes[8]
+            )));
+        }
+
+        let read_u64 = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let read_u128 = |at: usize| u128::from_le_bytes(bytes[at..at + 16].try_into().unwrap());
+
+        if (bytes[9] == 1) != self.winner_is_white || read_u128(10) != self.root_key {
+            return Err(error(
+                "the checkpoint belongs to a different position or winner",
+            ));
+        }
+
+        let count = read_u64(34) as usize;
+        if bytes.len() - CHECKPOINT_HEADER_BYTES != count.saturating_mul(CHECKPOINT_ENTRY_BYTES) {
+            return Err(error("the checkpoint is incomplete"));
+        }
+
+        self.nodes = read_u64(26);
+        self.table.clear();
+
+        for at in (CHECKPOINT_HEADER_BYTES..bytes.len()).step_by(CHECKPOINT_ENTRY_BYTES) {
+            self.table.insert(
+                read_u128(at),
+                Entry {
+                    phi: read_u64(at + 16),
+                    delta: read_u64(at + 24),
+                    work: read_u64(at + 32),
+                    winning_move: decode_move(u16::from_le_bytes([bytes[at + 40], bytes[at + 41]])),
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+struct Child {
+    mov: Move,
+    game_state: GameState,
+    key: u128,
+    symmetry: Symmetry,
+    terminal_values: Option<(u64, u64)>,
+}
+
+// the key of the canonical mirror image and the symmetry that leads there
+fn get_table_key(game_state: &GameState) -> (u128, Symmetry) {
+    game_state.get_canonical_key_among(&MIRRORS)
+}
+
+fn get_children(game_state: &GameState) -> Vec<Child> {
+    game_state
+        .generate_moves()
+        .into_iter()
+        .map(|mov| {
+            let mut child = game_state.clone();
+            child.make_move(mov);
+            let (key, symmetry) = get_table_key(&child);
+
+            Child {
+                mov,
+                key,
+                symmetry,
+                terminal_values: get_terminal_values(&child),
+                game_state: child,
+            }
+        })
+        .collect()
+}
+
+fn encode_move(mov: Option<Move>) -> u16 {
+    mov.map_or(NO_MOVE, |mov| {
+        (mov.get_from() as u16) << 8 | mov.get_to() as u16
+    })
+}
+
+fn decode_move(value: u16) -> Option<Move> {
+    (value != NO_MOVE).then(|| Move::new((value >> 8) as u8, value as u8))
+}
+
+fn is_solved(entry: &Entry) -> bool {
+    entry.phi == 0 || entry.delta == 0
+}
+
+fn get_terminal_values(game_state: &GameState) -> Option<(u64, u64)> {
+    let is_won = match game_state.get_result()? {
+        GameResult::WhiteWins => game_state.is_active_player_white,
+        GameResult::BlackWins => !game_state.is_active_player_white,
+    };
+
+    match is_won {
+        true => Some((0, INFINITY)),
+        false => Some((INFINITY, 0)),
+    }
+}
+
+// Tries to prove that the given side can force a win from the position. With
+// a checkpoint path an earlier run is resumed and the table is saved
+// regularly and at the end, so that long runs can be continued.
+pub fn prove_win(
+    game_state: &GameState,
+    winner_is_white: bool,
+    limits: &ProofLimits,
+    on_progress: &mut dyn FnMut(&ProofProgress),
+) -> Result<ProofOutcome, String> {
+    let mut search = ProofSearch {
+        winner_is_white,
+        limits,
+        on_progress,
+        table: HashMap::new(),
+        path: HashSet::new(),
+        root_key: get_table_key(game_state).0,
+        root_children: match game_state.get_result() {
+            Some(_) => Vec::new(),
+            None => get_children(game_state),
+        },
+        is_winner_to_move_at_root: game_state.is_active_player_white == winner_is_white,
+        nodes: 0,
+        start_instant: Instant::now(),
+        last_progress: Instant::now(),
+        last_checkpoint: Instant::now(),
+        error: None,
+        is_aborted: false,
+    };
+
+    if let Some(path) = &limits.checkpoint_path {
+        if fs::metadata(path).is_ok() {
+            search.read_checkpoint(path)?;
+        }
+    }
+
+    let (root_key, root_symmetry) = get_table_key(game_state);
+    search.mid(game_state, root_key, root_symmetry, INFINITY, INFINITY);
+    search.report_progress();
+
+    if let Some(error) = search.error {
+        return Err(error);
+    }
+
+    search.write_checkpoint()?;
+
+    match search.get_root_numbers() {
+        (0, _) => {
+            let proof = search.extract_proof(game_state)?;
+            proof.verify()?;
+            Ok(ProofOutcome::Proven(proof))
+        }
+        (_, 0) => Ok(ProofOutcome::UnverifiedDisproof),
+        _ => Ok(ProofOutcome::Unknown),
+    }
+}
+
+// A proof tree: one move for every position of the winner that the loser can
+// force, from which every game ends with a win. It is stored as text with one
+// position per line, e.g.
+//   # uisge proof
+//   root 7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0
+//   winner white
+//   4184b0c0a00e3c018 c2e2 ; 7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0
+pub struct Proof {
+    root: GameState,
+    winner_is_white: bool,
+    moves: HashMap<u128, Move>,
+    notations: HashMap<u128, String>,
+}
+
+impl Proof {
+    pub fn new(root: &GameState, winner_is_white: bool) -> Self {
+        Self {
+            root: root.clone(),
+            winner_is_white,
+            moves: HashMap::new(),
+            notations: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn get_winner(&self) -> GameResult {
+        match self.winner_is_white {
+            true => GameResult::WhiteWins,
+            false => GameResult::BlackWins,
+        }
+    }
+
+    pub fn get_move(&self, game_state: &GameState) -> Option<Move> {
+        self.moves
+            .get(&get_position_key(game_state))
+            .copied()
+    }
+
+    pub fn insert(&mut self, game_state: &GameState, mov: Move) {
+        let key = get_position_key(game_state);
+
+        self.moves.insert(key, mov);
+        self.notations
+            .insert(key, game_state.to_notation());
+    }
+
+    // Checks with the move generator alone that the winner wins every game
+    // that follows the proof, returns the number of positions it covers.
+    pub fn verify(&self) -> Result<usize, String> {
+        let mut verified = HashSet::new();
+        let mut path = HashSet::new();
+
+        self.verify_position(&self.root, &mut path, &mut verified)?;
+
+        Ok(verified.len())
+    }
+
+    fn verify_position(
+        &self,
+        game_state: &GameState,
+        path: &mut HashSet<u128>,
+        verified: &mut HashSet<u128>,
+    ) -> Result<(), String> {
+        let key = get_position_key(game_state);
+        let notation = game_state.to_notation();
+
+        if verified.contains(&key) {
+            return Ok(());
+        }
+
+        if !path.insert(key) {
+            return Err(format!("the proof goes around in circles at {notation}"));
+        }
+
+        let moves = game_state.generate_moves();
+
+        if moves.is_empty() {
+            if game_state.get_result() != Some(self.get_winner()) {
+                return Err(format!("the game at {notation} is not won"));
+            }
+        } else if game_state.is_active_player_white == self.winner_is_white {
+            let mov = self
+                .get_move(game_state)
+                .ok_or(format!("the proof has no move for {notation}"))?;
+
+            if !moves.contains(&mov) {
+                return Err(format!("the move {mov} is not legal in {notation}"));
+            }
+
+            let mut child = game_state.clone();
+            child.make_move(mov);
+            self.verify_position(&child, path, verified)?;
+        } else {
+            for mov in moves {
+                let mut child = game_state.clone();
+                child.make_move(mov);
+                self.verify_position(&child, path, verified)?;
+            }
+        }
+
+        path.remove(&key);
+        verified.insert(key);
+
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut keys: Vec<&u128> = self.moves.keys().collect();
+        keys.sort();
+
+        let mut text = format!(
+            "{PROOF_HEADER}\nroot {}\nwinner {}\n",
+            self.root.to_notation(),
+            if self.winner_is_white {
+                "white"
+            } else {
+                "black"
+            }
+        );
+
+        for key in keys {
+            text.push_str(&format!("{key:x} {}", self.moves[key]));
+            if let Some(notation) = self.notations.get(key) {
+                text.push_str(&format!(" ; {notation}"));
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut root = None;
+        let mut winner_is_white = None;
+        let mut moves = HashMap::new();
+        let mut notations = HashMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| format!("line {}: {message}", i + 1);
+
+            let (data, notation) = match line.split_once(';') {
+                Some((data, notation)) => (data.trim(), Some(notation.trim())),
+                None => (line.trim(), None),
+            };
+
+            if data.is_empty() || data.starts_with('#') {
+                continue;
+            }
+
+            let (name, value) = data
+                .split_once(' ')
+                .ok_or(error("expected <key> <move>"))?;
+
+            match name {
+                "root" => {
+                    root = Some(GameState::from_notation(value.trim()).map_err(|e| error(&e))?)
+                }
+                "winner" => {
+                    winner_is_white = Some(match value.trim() {
+                        "white" => true,
+                        "black" => false,
+                        _ => return Err(error("the winner must be white or black")),
+                    })
+                }
+                _ => {
+                    let key = u128::from_str_radix(name, 16)
+                        .map_err(|_| error(&format!("invalid position key '{name}'")))?;
+                    let mov: Move = value
+                        .trim()
+                        .parse()
+                        .map_err(|e: String| error(&e))?;
+
+                    moves.insert(key, mov);
+                    if let Some(notation) = notation {
+                        notations.insert(key, notation.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            root: root.ok_or("the proof has no root position")?,
+            winner_is_white: winner_is_white.ok_or("the proof has no winner")?,
+            moves,
+            notations,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("could not read '{path}': {error}"))?;
+
+        Self::from_text(&text).map_err(|error| format!("{path}: {error}"))
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text())
+            .map_err(|error| format!("could not write '{path}': {error}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // black wins in 5 plies
+    const BLACK_TO_MOVE_WINS: &str = "PPPp3/2Pp3/pPpp3/K1k4/7/7 b 11";
+    // white loses in 4 plies
+    const WHITE_TO_MOVE_LOSES: &str = "PPPp3/2P4/pPpp3/K1kk3/7/7 w 12";
+
+    fn prove(notation: &str, winner_is_white: bool, limits: &ProofLimits) -> ProofOutcome {
+        let game_state = GameState::from_notation(notation).unwrap();
+
+        prove_win(&game_state, winner_is_white, limits, &mut |_| {}).unwrap()
+    }
+
+    #[test]
+    fn test_prove_win() {
+        let ProofOutcome::Proven(proof) = prove(BLACK_TO_MOVE_WINS, false, &ProofLimits::default())
+        else {
+            panic!("the win was not proven");
+        };
+
+        assert_eq!(proof.get_winner(), GameResult::BlackWins);
+        assert!(proof.len() >= 3);
+        assert!(proof.verify().unwrap() > proof.len());
+
+        assert!(matches!(
+            prove(BLACK_TO_MOVE_WINS, true, &ProofLimits::default()),
+            ProofOutcome::UnverifiedDisproof
+        ));
+    }
+
+    #[test]
+    fn test_prove_loss() {
+        // a loss is a proven win for the other side
+        let ProofOutcome::Proven(proof) =
+            prove(WHITE_TO_MOVE_LOSES, false, &ProofLimits::default())
+        else {
+            panic!("the loss was not proven");
+        };
+
+        let root = GameState::from_notation(WHITE_TO_MOVE_LOSES).unwrap();
+        assert_eq!(proof.get_move(&root), None);
+        assert!(proof.verify().is_ok());
+
+        assert!(matches!(
+            prove(WHITE_TO_MOVE_LOSES, true, &ProofLimits::default()),
+            ProofOutcome::UnverifiedDisproof
+        ));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let limits = ProofLimits {
+            max_depth: 3,
+            ..ProofLimits::default()
+        };
+
+        // the win takes 5 plies
+        assert!(matches!(
+            prove(BLACK_TO_MOVE_WINS, false, &limits),
+            ProofOutcome::UnverifiedDisproof
+        ));
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let limits = ProofLimits {
+            max_nodes: Some(1000),
+            ..ProofLimits::default()
+        };
+        let mut reports = 0;
+
+        let outcome = prove_win(&GameState::new(), true, &limits, &mut |progress| {
+            reports += 1;
+            assert!(progress.proof_number > 0 && progress.disproof_number > 0);
+        })
+        .unwrap();
+
+        assert!(matches!(outcome, ProofOutcome::Unknown));
+        assert!(reports >= 1);
+    }
+
+    #[test]
+    fn test_proof_round_trip() {
+        let ProofOutcome::Proven(proof) = prove(BLACK_TO_MOVE_WINS, false, &ProofLimits::default())
+        else {
+            panic!("the win was not proven");
+        };
+
+        let text = proof.to_text();
+        assert!(text.starts_with(PROOF_HEADER));
+        assert!(text.contains(&format!("root {BLACK_TO_MOVE_WINS}\nwinner black\n")));
+
+        let parsed = Proof::from_text(&text).unwrap();
+        assert_eq!(parsed.to_text(), text);
+        assert_eq!(parsed.verify(), proof.verify());
+
+        // a proof with a missing move does not verify
+        let mut broken = Proof::from_text(&text).unwrap();
+        broken
+            .moves
+            .remove(&get_position_key(&broken.root));
+        assert!(broken.verify().is_err());
+
+        assert!(Proof::from_text("winner white").is_err());
+        assert!(Proof::from_text(&format!("root {BLACK_TO_MOVE_WINS}\nwinner red")).is_err());
+        assert!(Proof::from_text(&format!(
+            "root {BLACK_TO_MOVE_WINS}\nwinner black\nxyz a1a3"
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let path = std::env::temp_dir()
+            .join(format!("uisge-proof-test-{}.pn", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let _ = fs::remove_file(&path);
+
+        let mut limits = ProofLimits {
+            max_nodes: Some(3),
+            checkpoint_path: Some(path.clone()),
+            ..ProofLimits::default()
+        };
+
+        assert!(matches!(
+            prove(BLACK_TO_MOVE_WINS, false, &limits),
+            ProofOutcome::Unknown
+        ));
+        assert!(fs::metadata(&path).is_ok());
+
+        // the second run continues where the first one stopped
+        limits.max_nodes = None;
+        assert!(matches!(
+            prove(BLACK_TO_MOVE_WINS, false, &limits),
+            ProofOutcome::Proven(_)
+        ));
+
+        // a checkpoint only fits the position and winner it was made for
+        let game_state = GameState::from_notation(BLACK_TO_MOVE_WINS).unwrap();
+        assert!(prove_win(&game_state, true, &limits, &mut |_| {}).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
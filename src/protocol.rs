@@ -0,0 +1,253 @@
+use std::io::stdin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+
+use crate::move_gen::Move;
+use crate::notation::START_POSITION;
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::state::GameState;
+
+// The engine protocol is line based and similar in spirit to the UCI protocol
+// known from chess. Commands read from stdin:
+//   uei
+//   isready
+//   setoption name <MaxSearchDepth|MinSearchTime|MultiPV> value <n>
+//   newgame
+//   position startpos|notation <position notation> [moves <move> ...]
+//   go [depth <n>] [movetime <ms>] [infinite]
+//   stop
+//   d
+//   quit
+// Answers written to stdout:
+//   id name <name>, id author <author>, option ..., ueiok
+//   readyok
+//   info depth <n> score <n> time <ms> nodes <n> pv <move> ...
+//   info depth <n> multipv <rank> score <n> time <ms> nodes <n> pv <move> ... (MultiPV > 1)
+//   info string <message>
+//   bestmove <move>|none
+const MAX_DEPTH: u32 = 100;
+const MAX_MULTI_PV: u32 = 32;
+
+struct Engine {
+    game_state: GameState,
+    max_search_depth: u32,
+    min_search_time: u32,
+    multi_pv: u32,
+    search: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+pub fn start_protocol(max_search_depth: u32, min_search_time: u32) {
+    let mut engine = Engine {
+        game_state: GameState::new(),
+        max_search_depth,
+        min_search_time,
+        multi_pv: 1,
+        search: None,
+    };
+
+    let stdin = stdin();
+    let mut buffer = String::new();
+
+    loop {
+        buffer.clear();
+        if stdin.read_line(&mut buffer).unwrap() == 0 {
+            break;
+        }
+
+        let tokens: Vec<&str> = buffer.split_whitespace().collect();
+        let Some((command, args)) = tokens.split_first() else {
+            continue;
+        };
+
+        match *command {
+            "uei" => engine.identify(),
+            "isready" => println!("readyok"),
+            "setoption" => engine.set_option(args),
+            "newgame" => {
+                engine.stop_search();
+                engine.game_state = GameState::new();
+            }
+            "position" => engine.set_position(args),
+            "go" => engine.go(args),
+            "stop" => engine.stop_search(),
+            "d" => println!("{}\n{}", engine.game_state, engine.game_state.to_notation()),
+            "quit" => break,
+            _ => println!("info string unknown command '{command}'"),
+        }
+    }
+
+    engine.stop_search();
+}
+
+impl Engine {
+    fn identify(&self) {
+        println!("id name uisge {}", env!("CARGO_PKG_VERSION"));
+        println!("id author golmman");
+        println!(
+            "option name MaxSearchDepth type spin default {} min 1 max {MAX_DEPTH}",
+            self.max_search_depth
+        );
+        println!(
+            "option name MinSearchTime type spin default {} min 0 max {}",
+            self.min_search_time,
+            u32::MAX
+        );
+        println!(
+            "option name MultiPV type spin default {} min 1 max {MAX_MULTI_PV}",
+            self.multi_pv
+        );
+        println!("ueiok");
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        let (name, value) = match args {
+            ["name", name, "value", value] => (*name, *value),
+            _ => {
+                println!("info string usage: setoption name <name> value <value>");
+                return;
+            }
+        };
+
+        let Ok(value) = value.parse::<u32>() else {
+            println!("info string invalid value '{value}' for option {name}");
+            return;
+        };
+
+        match name {
+            "MaxSearchDepth" => self.max_search_depth = value.clamp(1, MAX_DEPTH),
+            "MinSearchTime" => self.min_search_time = value,
+            "MultiPV" => self.multi_pv = value.clamp(1, MAX_MULTI_PV),
+            _ => println!("info string unknown option '{name}'"),
+        }
+    }
+
+    fn set_position(&mut self, args: &[&str]) {
+        self.stop_search();
+
+        let moves_index = args.iter().position(|a| *a == "moves");
+        let (position_args, move_args) = match moves_index {
+            Some(i) => (&args[..i], &args[i + 1..]),
+            None => (args, &[][..]),
+        };
+
+        let game_state = match position_args {
+            ["startpos"] => GameState::from_notation(START_POSITION),
+            ["notation", notation @ ..] => GameState::from_notation(&notation.join(" ")),
+            _ => Err("usage: position startpos|notation <notation> [moves ...]".to_string()),
+        };
+
+        let mut game_state = match game_state {
+            Ok(game_state) => game_state,
+            Err(error) => {
+                println!("info string {error}");
+                return;
+            }
+        };
+
+        for move_arg in move_args {
+            let mov = match move_arg.parse::<Move>() {
+                Ok(mov) => mov,
+                Err(error) => {
+                    println!("info string {error}");
+                    return;
+                }
+            };
+
+            if !game_state
+                .generate_moves()
+                .contains(&mov)
+            {
+                println!("info string illegal move '{move_arg}'");
+                return;
+            }
+
+            game_state.make_move(mov);
+        }
+
+        self.game_state = game_state;
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        self.stop_search();
+
+        let mut limits = SearchLimits::new(self.max_search_depth, self.min_search_time);
+        let stop = Arc::new(AtomicBool::new(false));
+        limits.stop = Some(stop.clone());
+        limits.multi_pv = self.multi_pv as usize;
+
+        let mut i = 0;
+        while i < args.len() {
+            let value = args
+                .get(i + 1)
+                .and_then(|v| v.parse::<u32>().ok());
+
+            match (args[i], value) {
+                ("depth", Some(depth)) => {
+                    limits.max_depth = depth.clamp(1, MAX_DEPTH);
+                    limits.min_time = u32::MAX;
+                    i += 1;
+                }
+                ("movetime", Some(movetime)) => {
+                    limits.max_depth = MAX_DEPTH;
+                    limits.min_time = movetime;
+                    limits.max_time = Some(movetime);
+                    i += 1;
+                }
+                ("infinite", _) => {
+                    limits.max_depth = MAX_DEPTH;
+                    limits.min_time = u32::MAX;
+                }
+                (arg, _) => {
+                    println!("info string invalid go argument '{arg}'");
+                    return;
+                }
+            }
+
+            i += 1;
+        }
+
+        let game_state = self.game_state.clone();
+        let handle = thread::spawn(move || {
+            let is_multi_pv = limits.multi_pv > 1;
+            let result = think_with(&game_state, &limits, &mut |info| {
+                for (i, line) in info.lines.iter().enumerate() {
+                    let pv: Vec<String> = line
+                        .pv
+                        .iter()
+                        .map(|m| m.to_string())
+                        .collect();
+                    let rank = match is_multi_pv {
+                        true => format!(" multipv {}", i + 1),
+                        false => String::new(),
+                    };
+                    println!(
+                        "info depth {}{rank} score {} time {} nodes {} pv {}",
+                        info.depth,
+                        line.score,
+                        info.time,
+                        info.nodes,
+                        pv.join(" ")
+                    );
+                }
+            });
+
+            match result.best_move {
+                Some(mov) => println!("bestmove {mov}"),
+                None => println!("bestmove none"),
+            }
+        });
+
+        self.search = Some((stop, handle));
+    }
+
+    fn stop_search(&mut self) {
+        if let Some((stop, handle)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            handle.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use crate::bit_utils::get_bit_indices;
+use crate::constants::BitBoard;
+use crate::geometry::get_geometry;
+use crate::rng::Rng;
+use crate::state::Board;
+use crate::state::GameState;
+
+pub const DEFAULT_MIN_PLIES: u32 = 4;
+pub const DEFAULT_MAX_PLIES: u32 = 40;
+
+// tries per requested position before giving up on filters nothing passes
+const MAX_ATTEMPTS: usize = 1000;
+
+pub enum PositionSource {
+    // random moves from a position, their number in [min_plies, max_plies]
+    Playout {
+        start: GameState,
+        min_plies: u32,
+        max_plies: u32,
+    },
+    // connected shapes, e.g. of a generate-boards file, with random colours
+    // and piece types
+    Shapes(Vec<BitBoard>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionFilter {
+    // moves the side to move must have at least
+    pub min_moves: usize,
+    pub allow_won: bool,
+}
+
+impl Default for PositionFilter {
+    fn default() -> Self {
+        Self {
+            min_moves: 1,
+            allow_won: false,
+        }
+    }
+}
+
+impl PositionFilter {
+    pub fn accepts(&self, game_state: &GameState) -> bool {
+        if !self.allow_won && game_state.get_result().is_some() {
+            return false;
+        }
+
+        game_state.generate_moves().len() >= self.min_moves
+    }
+}
+
+// The same seed gives the same positions. Fails when too few positions pass
+// the filter.
+pub fn generate_random_positions(
+    source: &PositionSource,
+    filter: &PositionFilter,
+    count: usize,
+    seed: u64,
+) -> Result<Vec<GameState>, String> {
+    if let PositionSource::Playout {
+        min_plies,
+        max_plies,
+        ..
+    } = source
+    {
+        if min_plies > max_plies {
+            return Err(format!(
+                "the minimum of {min_plies} plies is above the maximum of {max_plies}"
+            ));
+        }
+    }
+
+    if let PositionSource::Shapes(shapes) = source {
+        let pieces = 2 * get_geometry().pieces_per_side as u32;
+
+        if shapes.is_empty() {
+            return Err("there are no shapes to sample from".to_string());
+        }
+
+        if let Some(shape) = shapes
+            .iter()
+            .find(|shape| shape.count_ones() != pieces)
+        {
+            return Err(format!(
+                "the shape {shape:#x} does not have {pieces} squares"
+            ));
+        }
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut positions = Vec::with_capacity(count);
+
+    for _ in 0..count.saturating_mul(MAX_ATTEMPTS) {
+        if positions.len() == count {
+            break;
+        }
+
+        let game_state = match source {
+            PositionSource::Playout {
+                start,
+                min_plies,
+                max_plies,
+            } => play_random_moves(&mut rng, start, *min_plies, *max_plies),
+            PositionSource::Shapes(shapes) => make_shape_position(&mut rng, shapes),
+        };
+
+        if filter.accepts(&game_state) {
+            positions.push(game_state);
+        }
+    }
+
+    if positions.len() < count {
+        return Err(format!(
+            "only {} of {count} random positions passed the filters",
+            positions.len()
+        ));
+    }
+
+    Ok(positions)
+}
+
+// stops early when the game ends
+fn play_random_moves(
+    rng: &mut Rng,
+    start: &GameState,
+    min_plies: u32,
+    max_plies: u32,
+) -> GameState {
+    let plies = min_plies + (rng.next_u64() % (max_plies - min_plies + 1) as u64) as u32;
+    let mut game_state = start.clone();
+
+    for _ in 0..plies {
+        if game_state.get_result().is_some() {
+            break;
+        }
+
+        let moves = game_state.generate_moves();
+        let mov = moves[(rng.next_u64() % moves.len() as u64) as usize];
+        game_state.make_move(mov);
+    }
+
+    game_state
+}
+
+// Every side gets its number of pieces on random squares of the shape, every
+// piece is a king or a pawn with the same chance.
+fn make_shape_position(rng: &mut Rng, shapes: &[BitBoard]) -> GameState {
+    let shape = shapes[(rng.next_u64() % shapes.len() as u64) as usize];
+    let mut squares = get_bit_indices(shape);
+
+    // Fisher-Yates shuffle
+    for i in (1..squares.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        squares.swap(i, j);
+    }
+
+    let pieces_per_side = get_geometry().pieces_per_side;
+    let mut board = Board::empty();
+    board.piece_bits = shape;
+
+    for (n, square) in squares.into_iter().enumerate() {
+        let is_king = rng.next_u64() & 1 == 1;
+
+        let list = match (n < pieces_per_side, is_king) {
+            (true, true) => &mut board.white_kings,
+            (true, false) => &mut board.white_pawns,
+            (false, true) => &mut board.black_kings,
+            (false, false) => &mut board.black_pawns,
+        };
+        list.push_front(square);
+    }
+
+    GameState {
+        board,
+        is_active_player_white: rng.next_u64() & 1 == 1,
+        move_count: 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tablebase::parse_region;
+    use crate::valid_board_gen::grow_polyominoes;
+
+    fn get_notations(positions: &[GameState]) -> Vec<String> {
+        positions
+            .iter()
+            .map(|game_state| game_state.to_notation())
+            .collect()
+    }
+
+    #[test]
+    fn test_random_playouts() {
+        let source = PositionSource::Playout {
+            start: GameState::new(),
+            min_plies: 2,
+            max_plies: 10,
+        };
+        let filter = PositionFilter {
+            min_moves: 3,
+            allow_won: false,
+        };
+
+        let positions = generate_random_positions(&source, &filter, 20, 7).unwrap();
+        assert_eq!(positions.len(), 20);
+
+        for game_state in &positions {
+            assert!((2..=10).contains(&game_state.move_count));
+            assert!(game_state.board.validate().is_empty());
+            assert_eq!(game_state.get_result(), None);
+            assert!(game_state.generate_moves().len() >= 3);
+
+            // the notation is a valid position again
+            let notation = game_state.to_notation();
+            assert_eq!(
+                GameState::from_notation(&notation)
+                    .unwrap()
+                    .to_notation(),
+                notation
+            );
+        }
+
+        // reproducible with the same seed
+        assert_eq!(
+            get_notations(&positions),
+            get_notations(&generate_random_positions(&source, &filter, 20, 7).unwrap())
+        );
+        assert_ne!(
+            get_notations(&positions),
+            get_notations(&generate_random_positions(&source, &filter, 20, 8).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_random_shapes() {
+        let shapes = grow_polyominoes(parse_region("a1:e4").unwrap(), false);
+        let source = PositionSource::Shapes(shapes.clone());
+
+        let positions =
+            generate_random_positions(&source, &PositionFilter::default(), 50, 1).unwrap();
+
+        for game_state in &positions {
+            assert!(shapes.contains(&game_state.board.piece_bits));
+            assert!(game_state.board.validate().is_empty());
+            assert_eq!(game_state.get_result(), None);
+        }
+
+        // both colours to move and both piece types show up
+        assert!(positions
+            .iter()
+            .any(|game_state| game_state.is_active_player_white));
+        assert!(positions
+            .iter()
+            .any(|game_state| !game_state.is_active_player_white));
+        assert!(positions
+            .iter()
+            .any(|game_state| game_state.board.white_kings.len() > 0));
+    }
+
+    #[test]
+    fn test_impossible_filters() {
+        let source = PositionSource::Playout {
+            start: GameState::new(),
+            min_plies: 0,
+            max_plies: 0,
+        };
+        let filter = PositionFilter {
+            min_moves: 100,
+            allow_won: false,
+        };
+
+        assert!(generate_random_positions(&source, &filter, 1, 0)
+            .err()
+            .unwrap()
+            .contains("only 0 of 1"));
+
+        let source = PositionSource::Playout {
+            start: GameState::new(),
+            min_plies: 5,
+            max_plies: 4,
+        };
+        assert!(generate_random_positions(&source, &PositionFilter::default(), 1, 0).is_err());
+
+        assert!(generate_random_positions(
+            &PositionSource::Shapes(vec![0b111]),
+            &PositionFilter::default(),
+            1,
+            0
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use crate::constants::BitBoard;
+use crate::constants::BoardIndex;
+use crate::geometry::get_geometry;
+use crate::move_gen::Move;
+use crate::state::Board;
+use crate::state::GameState;
+use crate::theme::Highlight;
+use crate::theme::Theme;
+use crate::valid_board_gen::get_cut_vertices;
+
+// How the board is laid out on the screen. By default rank 1 and the white
+// pieces are at the bottom, flipping turns the board around so that black is
+// at the bottom. Square indices are the numbering of the bit boards, 0..41 on
+// the standard board.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct View {
+    pub is_flipped: bool,
+    pub show_indices: bool,
+}
+
+impl View {
+    // the files from left to right
+    pub fn get_files(&self) -> Vec<BoardIndex> {
+        let width = get_geometry().width;
+
+        match self.is_flipped {
+            false => (0..width).collect(),
+            true => (0..width).rev().collect(),
+        }
+    }
+
+    // the ranks from top to bottom
+    pub fn get_ranks(&self) -> Vec<BoardIndex> {
+        let height = get_geometry().height;
+
+        match self.is_flipped {
+            false => (0..height).rev().collect(),
+            true => (0..height).collect(),
+        }
+    }
+
+    // turns a direction on the screen, with y pointing down, into a direction on the board
+    pub fn get_board_direction(&self, dx: i8, dy: i8) -> (i8, i8) {
+        match self.is_flipped {
+            false => (dx, -dy),
+            true => (-dx, dy),
+        }
+    }
+
+    // every printed board line has this many characters
+    pub fn get_width(&self) -> usize {
+        3 + get_geometry().width as usize * self.get_square_width() + 2
+    }
+
+    fn get_square_width(&self) -> usize {
+        match self.show_indices {
+            false => 2,
+            true => 5,
+        }
+    }
+}
+
+// Information drawn on top of the board. When several highlights apply to
+// the same square the cursor wins over the selection, the selection over the
+// destinations, the destinations over the last move and the last move over
+// frozen pieces.
+#[derive(Clone, Default)]
+pub struct Overlay {
+    pub last_move: Option<Move>,
+    pub cursor: Option<BoardIndex>,
+    pub selected: Option<BoardIndex>,
+    pub destinations: Vec<BoardIndex>,
+    pub frozen: BitBoard,
+}
+
+impl Overlay {
+    // highlights the last move and all pieces that cannot leave their square
+    pub fn new(game_state: &GameState, last_move: Option<Move>) -> Self {
+        Self {
+            last_move,
+            frozen: get_cut_vertices(game_state.board.piece_bits),
+            ..Default::default()
+        }
+    }
+
+    // highlights the piece on a square together with all of its destinations
+    pub fn select(&mut self, game_state: &GameState, from: BoardIndex) {
+        self.selected = Some(from);
+        self.destinations = game_state
+            .generate_moves()
+            .iter()
+            .filter(|m| m.get_from() == from)
+            .map(|m| m.get_to())
+            .collect();
+    }
+
+    pub fn get_highlight(&self, i: BoardIndex) -> Highlight {
+        let is_last_move = self
+            .last_move
+            .is_some_and(|m| m.get_from() == i || m.get_to() == i);
+
+        if self.cursor == Some(i) {
+            Highlight::Cursor
+        } else if self.selected == Some(i) {
+            Highlight::Selected
+        } else if self.destinations.contains(&i) {
+            Highlight::Destination
+        } else if is_last_move {
+            Highlight::LastMove
+        } else if self.frozen & (1 << i) != 0 {
+            Highlight::Frozen
+        } else {
+            Highlight::None
+        }
+    }
+}
+
+pub fn render_board(board: &Board, overlay: &Overlay, theme: &Theme, view: &View) -> Vec<String> {
+    let square_width = view.get_square_width();
+    let files: String = view
+        .get_files()
+        .iter()
+        .map(|x| format!("{:<square_width$}", (b'a' + x) as char))
+        .collect();
+    let files = theme.frame(&format!("   {files}  "));
+
+    let mut lines = vec![files.clone()];
+
+    for y in view.get_ranks() {
+        let rank = y + 1;
+        let mut line = theme.frame(&format!(" {rank} "));
+
+        for x in view.get_files() {
+            let i = get_geometry().get_index(x, y);
+            line.push_str(&theme.square(board.get_piece(i), overlay.get_highlight(i)));
+
+            if view.show_indices {
+                line.push_str(&theme.frame(&format!("{i:02} ")));
+            }
+        }
+
+        line.push_str(&theme.frame(&format!("{rank} ")));
+        lines.push(line);
+    }
+
+    lines.push(files);
+    lines
+}
+
+pub fn render_status(game_state: &GameState, theme: &Theme) -> String {
+    let move_count = game_state.move_count;
+
+    if game_state.is_active_player_white {
+        theme.side(true, &format!("    WHITE {move_count:05}    "))
+    } else {
+        theme.side(false, &format!("    BLACK {move_count:05}    "))
+    }
+}
+
+pub fn render_game_state(
+    game_state: &GameState,
+    overlay: &Overlay,
+    theme: &Theme,
+    view: &View,
+) -> String {
+    let mut lines = render_board(&game_state.board, overlay, theme, view);
+    lines.push(render_status(game_state, theme));
+
+    lines.join("\n")
+}
+
+// explains the highlights of an overlay
+pub fn render_legend(theme: &Theme) -> String {
+    [
+        theme.swatch(Highlight::LastMove, "last move"),
+        theme.swatch(Highlight::Destination, "destination"),
+        theme.swatch(Highlight::Frozen, "frozen"),
+    ]
+    .join("  ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::theme::ASCII;
+    use crate::theme::CLASSIC;
+
+    fn strip_colors(line: &str) -> String {
+        let mut text = String::new();
+        let mut is_escape = false;
+
+        for c in line.chars() {
+            match c {
+                '\x1b' => is_escape = true,
+                'm' if is_escape => is_escape = false,
+                _ if !is_escape => text.push(c),
+                _ => {}
+            }
+        }
+
+        text
+    }
+
+    #[test]
+    fn test_render_board() {
+        let view = View::default();
+        let lines = render_board(&Board::new(), &Overlay::default(), &CLASSIC, &view);
+
+        assert_eq!(lines.len(), get_geometry().height as usize + 2);
+        assert!(lines
+            .iter()
+            .all(|line| strip_colors(line).chars().count() == view.get_width()));
+        assert_eq!(strip_colors(&lines[0]), "   a b c d e f g   ");
+        assert_eq!(strip_colors(&lines[5]), " 2 - - o o - - - 2 ");
+        assert_eq!(strip_colors(&lines[3]), " 4 - - o o o o - 4 ");
+
+        let overlay = Overlay::new(&GameState::new(), Some(Move::new(17, 3)));
+        let lines = render_board(&Board::new(), &overlay, &ASCII, &view);
+        assert_eq!(lines[6], " 1 . . . .*. . . 1 ");
+        assert_eq!(lines[4], " 3 . o o#o*o . . 3 ");
+        assert_eq!(lines[3], " 4 . . x x x#x . 4 ");
+    }
+
+    #[test]
+    fn test_render_board_views() {
+        let board = Board::new();
+        let overlay = Overlay::default();
+
+        let view = View {
+            is_flipped: true,
+            show_indices: false,
+        };
+        let lines = render_board(&board, &overlay, &ASCII, &view);
+        assert_eq!(lines[0], "   g f e d c b a   ");
+        assert_eq!(lines[1], " 1 . . . . . . . 1 ");
+        assert_eq!(lines[3], " 3 . . o o o o . 3 ");
+        assert_eq!(lines[4], " 4 . x x x x . . 4 ");
+
+        let view = View {
+            is_flipped: false,
+            show_indices: true,
+        };
+        let lines = render_board(&board, &overlay, &ASCII, &view);
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() == view.get_width()));
+        assert_eq!(lines[0], "   a    b    c    d    e    f    g      ");
+        assert_eq!(lines[5], " 2 . 07 . 08 o 09 o 10 . 11 . 12 . 13 2 ");
+    }
+
+    #[test]
+    fn test_view_directions() {
+        let view = View::default();
+        assert_eq!(view.get_board_direction(0, -1), (0, 1));
+        assert_eq!(view.get_board_direction(1, 0), (1, 0));
+
+        let view = View {
+            is_flipped: true,
+            show_indices: false,
+        };
+        assert_eq!(view.get_board_direction(0, -1), (0, -1));
+        assert_eq!(view.get_board_direction(1, 0), (-1, 0));
+    }
+
+    #[test]
+    fn test_overlay_backgrounds() {
+        let game_state = GameState::new();
+
+        let mut overlay = Overlay::new(&game_state, Some(Move::new(24, 10)));
+        assert_eq!(overlay.get_highlight(24), Highlight::LastMove);
+        assert_eq!(overlay.get_highlight(10), Highlight::LastMove);
+        assert_eq!(overlay.get_highlight(0), Highlight::None);
+
+        overlay.select(&game_state, 17);
+        overlay.cursor = Some(17);
+        assert_eq!(overlay.destinations, vec![3, 19]);
+        assert_eq!(overlay.get_highlight(17), Highlight::Cursor);
+        assert_eq!(overlay.get_highlight(3), Highlight::Destination);
+
+        overlay.cursor = None;
+        assert_eq!(overlay.get_highlight(17), Highlight::Selected);
+    }
+
+    #[test]
+    fn test_overlay_frozen_pieces() {
+        let game_state = GameState::from_notation("7/7/PPPPPPp/6p/6p/4ppp w 0").unwrap();
+        let overlay = Overlay::new(&game_state, None);
+
+        // only the two ends of the chain are free to move
+        assert_eq!(
+            overlay.frozen,
+            game_state.board.piece_bits & !(1 << 14 | 1 << 39)
+        );
+        assert_eq!(overlay.get_highlight(16), Highlight::Frozen);
+        assert_eq!(overlay.get_highlight(14), Highlight::None);
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+// A small seeded pseudo random number generator (SplitMix64), the same seed
+// always gives the same numbers on every platform.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // a number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // picks an index with a probability proportional to its weight
+    pub fn pick_weighted(&mut self, weights: &[f64]) -> Option<usize> {
+        let total: f64 = weights.iter().sum();
+        if weights.is_empty() || total <= 0.0 {
+            return None;
+        }
+
+        let mut target = self.next_f64() * total;
+        for (i, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                return Some(i);
+            }
+            target -= weight;
+        }
+
+        // rounding errors can leave a tiny rest
+        weights.iter().rposition(|w| *w > 0.0)
+    }
+}
+
+// a seed for games that do not ask for a specific one
+pub fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rng_is_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+
+        let numbers: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(
+            numbers,
+            (0..5)
+                .map(|_| b.next_u64())
+                .collect::<Vec<u64>>()
+        );
+        assert_ne!(
+            numbers,
+            (0..5)
+                .map(|_| c.next_u64())
+                .collect::<Vec<u64>>()
+        );
+
+        assert!((0..1000)
+            .map(|_| a.next_f64())
+            .all(|x| (0.0..1.0).contains(&x)));
+    }
+
+    #[test]
+    fn test_pick_weighted() {
+        let mut rng = Rng::new(7);
+
+        assert_eq!(rng.pick_weighted(&[]), None);
+        assert_eq!(rng.pick_weighted(&[0.0, 0.0]), None);
+        assert_eq!(rng.pick_weighted(&[0.0, 1.0, 0.0]), Some(1));
+
+        let mut counts = [0; 2];
+        for _ in 0..10_000 {
+            counts[rng.pick_weighted(&[3.0, 1.0]).unwrap()] += 1;
+        }
+        assert!((7000..8000).contains(&counts[0]));
+    }
+}
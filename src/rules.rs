@@ -0,0 +1,162 @@
+use std::fmt::Display;
+
+use crate::constants::BitBoard;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Connectivity {
+    // pieces touch along an edge
+    Four,
+    // pieces also touch across a corner
+    Eight,
+}
+
+// How the squares of the start block are shared between the sides.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartLayout {
+    // white below black
+    Block,
+    // white left of black
+    Sides,
+    // white and black take turns square by square
+    Alternating,
+}
+
+// The rules the engine plays by. The standard rules are 4-connectivity, a
+// side wins when all of its pieces are kings and kings may step to any
+// neighbouring square that keeps the group connected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ruleset {
+    pub connectivity: Connectivity,
+    // None is all pieces of a side
+    pub kings_to_win: Option<usize>,
+    pub king_steps: bool,
+    pub start_layout: StartLayout,
+}
+
+pub const STANDARD_RULES: Ruleset = Ruleset {
+    connectivity: Connectivity::Four,
+    kings_to_win: None,
+    king_steps: true,
+    start_layout: StartLayout::Block,
+};
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        STANDARD_RULES
+    }
+}
+
+impl Display for Connectivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Connectivity::Four => write!(f, "4-connected"),
+            Connectivity::Eight => write!(f, "8-connected"),
+        }
+    }
+}
+
+impl Display for StartLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StartLayout::Block => write!(f, "block"),
+            StartLayout::Sides => write!(f, "sides"),
+            StartLayout::Alternating => write!(f, "alternating"),
+        }
+    }
+}
+
+impl StartLayout {
+    // shares the squares of the start block, white gets pieces_per_side of them
+    pub fn assign(
+        &self,
+        squares: BitBoard,
+        width: u8,
+        pieces_per_side: usize,
+    ) -> (BitBoard, BitBoard) {
+        let mut indices: Vec<u32> = (0..64)
+            .filter(|i| squares & 1 << i != 0)
+            .collect();
+
+        if *self == StartLayout::Sides {
+            indices.sort_by_key(|i| (i % width as u32, i / width as u32));
+        }
+
+        let mut white = 0;
+        let mut black = 0;
+
+        for (n, i) in indices.into_iter().enumerate() {
+            let is_white = match self {
+                StartLayout::Alternating => n % 2 == 0,
+                _ => n < pieces_per_side,
+            };
+
+            if is_white {
+                white |= 1 << i;
+            } else {
+                black |= 1 << i;
+            }
+        }
+
+        (white, black)
+    }
+}
+
+pub fn parse_connectivity(value: &str) -> Result<Connectivity, String> {
+    match value {
+        "4" => Ok(Connectivity::Four),
+        "8" => Ok(Connectivity::Eight),
+        _ => Err(format!("invalid connectivity '{value}', expected 4 or 8")),
+    }
+}
+
+pub fn parse_start_layout(value: &str) -> Result<StartLayout, String> {
+    match value {
+        "block" => Ok(StartLayout::Block),
+        "sides" => Ok(StartLayout::Sides),
+        "alternating" => Ok(StartLayout::Alternating),
+        _ => Err(format!(
+            "invalid start layout '{value}', expected block, sides or alternating"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_start_layouts() {
+        // the start block of the standard board
+        let squares = 0b000000000110000111100001111000011000000000;
+
+        assert_eq!(
+            StartLayout::Block.assign(squares, 7, 6),
+            (
+                0b000000000000000000000001111000011000000000,
+                0b000000000110000111100000000000000000000000
+            )
+        );
+        assert_eq!(
+            StartLayout::Sides.assign(squares, 7, 6),
+            (
+                0b000000000000000000100000111000011000000000,
+                0b000000000110000111000001000000000000000000
+            )
+        );
+
+        let (white, black) = StartLayout::Alternating.assign(squares, 7, 6);
+        assert_eq!(white | black, squares);
+        assert_eq!(white.count_ones(), 6);
+        assert_eq!(white & 1 << 9, 1 << 9);
+        assert_eq!(black & 1 << 10, 1 << 10);
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(parse_connectivity("8"), Ok(Connectivity::Eight));
+        assert!(parse_connectivity("6").is_err());
+        assert_eq!(parse_start_layout("sides"), Ok(StartLayout::Sides));
+        assert!(parse_start_layout("random").is_err());
+        assert_eq!(Ruleset::default(), STANDARD_RULES);
+    }
+}
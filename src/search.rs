@@ -1,12 +1,19 @@
 use std::fmt::Display;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::Instant;
 
-use crate::constants::SCORE_KING_COUNT;
+use crate::book::probe_book;
 use crate::constants::SCORE_MAX;
 use crate::constants::SCORE_MIN;
+use crate::constants::SCORE_STEP;
+use crate::geometry::get_geometry;
 use crate::move_gen::Move;
 use crate::state::GameState;
+use crate::tablebase::probe_tablebase;
 
+#[derive(Clone)]
 pub struct PVLine {
     moves: Vec<Move>,
 }
@@ -39,34 +46,309 @@ impl Display for PVLine {
     }
 }
 
+pub struct SearchLimits {
+    pub max_depth: u32,
+    // no new iteration is started after this many milliseconds
+    pub min_time: u32,
+    // the search is aborted after this many milliseconds, even in the middle of an iteration
+    pub max_time: Option<u32>,
+    // the search is aborted after visiting this many nodes
+    pub max_nodes: Option<u64>,
+    // the search is aborted as soon as this flag is set
+    pub stop: Option<Arc<AtomicBool>>,
+    // the number of best root moves that get their own score and principal variation
+    pub multi_pv: usize,
+}
+
+impl SearchLimits {
+    pub fn new(max_depth: u32, min_time: u32) -> Self {
+        Self {
+            max_depth,
+            min_time,
+            max_time: None,
+            max_nodes: None,
+            stop: None,
+            multi_pv: 1,
+        }
+    }
+}
+
+// one of the best root moves with its own score and principal variation
+#[derive(Clone)]
+pub struct SearchLine {
+    pub score: i32,
+    pub pv: Vec<Move>,
+}
+
+// Reported after every completed iteration of the iterative deepening, score
+// and pv belong to the best line. There are up to multi_pv lines, best first.
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: i32,
+    pub time: u128,
+    pub nodes: u64,
+    pub pv: Vec<Move>,
+    pub lines: Vec<SearchLine>,
+}
+
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub time: u128,
+    pub nodes: u64,
+    pub pv: Vec<Move>,
+    pub lines: Vec<SearchLine>,
+}
+
+struct SearchContext<'a> {
+    limits: &'a SearchLimits,
+    start_instant: Instant,
+    nodes: u64,
+    root_move_count: u32,
+    can_abort: bool,
+    is_aborted: bool,
+}
+
+impl SearchContext<'_> {
+    fn should_abort(&mut self) -> bool {
+        if self.is_aborted {
+            return true;
+        }
+
+        // checking the clock is comparatively expensive, so only do it every 1024 nodes
+        if !self.can_abort || !self.nodes.is_multiple_of(1024) {
+            return false;
+        }
+
+        if let Some(stop) = &self.limits.stop {
+            if stop.load(Ordering::Relaxed) {
+                self.is_aborted = true;
+            }
+        }
+
+        if let Some(max_nodes) = self.limits.max_nodes {
+            if self.nodes >= max_nodes {
+                self.is_aborted = true;
+            }
+        }
+
+        if let Some(max_time) = self.limits.max_time {
+            if self.start_instant.elapsed().as_millis() > max_time.into() {
+                self.is_aborted = true;
+            }
+        }
+
+        self.is_aborted
+    }
+}
+
+// plays a move from the opening book when there is one
 pub fn think(game_state: &GameState, max_depth: u32, min_time: u32) -> Move {
-    let start_instant = Instant::now();
+    if let Some(mov) = probe_book(game_state) {
+        println!("book move {mov:?}");
+        return mov;
+    }
+
+    think_with_limits(game_state, &SearchLimits::new(max_depth, min_time))
+        .best_move
+        .unwrap()
+}
 
-    let mut pv_line = PVLine::new();
+// like think but with full control over when the search stops
+pub fn think_with_limits(game_state: &GameState, limits: &SearchLimits) -> SearchResult {
+    if limits.multi_pv > 1 {
+        return think_multi_pv(game_state, limits);
+    }
 
     println!("      time | depth |      score | principal variation");
     println!(" ----------|-------|------------|---------------------------");
-    for depth in 1..max_depth + 1 {
-        let score_int = pvs(game_state, SCORE_MIN, SCORE_MAX, depth, &mut pv_line);
-        let score = score_int as f32 / 1000f32;
-        let elapsed_millis = start_instant.elapsed().as_millis();
-        let elapsed = elapsed_millis as f32 / 1000f32;
+
+    think_with(game_state, limits, &mut |info| {
+        let elapsed = info.time as f32 / 1000f32;
+        let depth = info.depth;
+        let score = info.score as f32 / 1000f32;
+        let pv_line = PVLine {
+            moves: info.pv.clone(),
+        };
 
         println!("{elapsed:>10.3} | {depth:>5} | {score:>10.3} | {pv_line}");
+    })
+}
+
+// prints a table of the ranked lines after every iteration
+fn think_multi_pv(game_state: &GameState, limits: &SearchLimits) -> SearchResult {
+    println!("      time | depth | rank |      score | principal variation");
+    println!(" ----------|-------|------|------------|---------------------------");
+
+    think_with(game_state, limits, &mut |info| {
+        let elapsed = info.time as f32 / 1000f32;
+        let depth = info.depth;
+
+        for (i, line) in info.lines.iter().enumerate() {
+            let rank = i + 1;
+            let score = line.score as f32 / 1000f32;
+            let pv_line = PVLine {
+                moves: line.pv.clone(),
+            };
 
-        if elapsed_millis > min_time.into() {
+            println!("{elapsed:>10.3} | {depth:>5} | {rank:>4} | {score:>10.3} | {pv_line}");
+        }
+        println!(" ----------|-------|------|------------|---------------------------");
+    })
+}
+
+pub fn think_with(
+    game_state: &GameState,
+    limits: &SearchLimits,
+    on_info: &mut dyn FnMut(&SearchInfo),
+) -> SearchResult {
+    let mut ctx = SearchContext {
+        limits,
+        start_instant: Instant::now(),
+        nodes: 0,
+        root_move_count: game_state.move_count,
+        can_abort: false,
+        is_aborted: false,
+    };
+
+    let root_moves = game_state.generate_moves();
+    // the lines of the previous iteration, they are searched first
+    let mut pv_lines = vec![
+        PVLine::new();
+        limits
+            .multi_pv
+            .clamp(1, root_moves.len().max(1))
+    ];
+    let mut result = SearchResult {
+        best_move: root_moves.first().copied(),
+        score: 0,
+        depth: 0,
+        time: 0,
+        nodes: 0,
+        pv: Vec::new(),
+        lines: Vec::new(),
+    };
+
+    for depth in 1..limits.max_depth + 1 {
+        let mut iteration_pv_lines = pv_lines.clone();
+        let mut scores = Vec::new();
+
+        for k in 0..iteration_pv_lines.len() {
+            let score = if k == 0 {
+                pvs(
+                    game_state,
+                    SCORE_MIN,
+                    SCORE_MAX,
+                    depth,
+                    &mut iteration_pv_lines[0],
+                    &mut ctx,
+                )
+            } else {
+                // every further line is the best move among those not yet taken by a better line
+                let mut moves: Vec<Move> = root_moves
+                    .iter()
+                    .filter(|mov| {
+                        !iteration_pv_lines[..k]
+                            .iter()
+                            .any(|line| line.moves.first() == Some(mov))
+                    })
+                    .copied()
+                    .collect();
+
+                if let Some(mov) = iteration_pv_lines[k].moves.first() {
+                    swap_move_to_front(&mut moves, *mov);
+                }
+
+                search_moves(
+                    game_state,
+                    moves,
+                    SCORE_MIN,
+                    SCORE_MAX,
+                    depth,
+                    &mut iteration_pv_lines[k],
+                    &mut ctx,
+                )
+            };
+
+            if ctx.is_aborted {
+                break;
+            }
+
+            scores.push(score);
+        }
+
+        if ctx.is_aborted {
+            break;
+        }
+
+        // the first iteration always has to finish, so that there is a move to play
+        ctx.can_abort = true;
+        pv_lines = iteration_pv_lines;
+
+        let elapsed_millis = ctx.start_instant.elapsed().as_millis();
+
+        let mut lines: Vec<SearchLine> = pv_lines
+            .iter()
+            .zip(scores)
+            .map(|(pv_line, score)| SearchLine {
+                score,
+                pv: pv_line.moves.clone(),
+            })
+            .collect();
+        // reductions can make a later line score a little better than an earlier one
+        lines.sort_by_key(|line| -line.score);
+
+        result.best_move = lines[0]
+            .pv
+            .first()
+            .copied()
+            .or(result.best_move);
+        result.score = lines[0].score;
+        result.depth = depth;
+        result.pv = lines[0].pv.clone();
+        result.lines = lines;
+
+        on_info(&SearchInfo {
+            depth,
+            score: result.score,
+            time: elapsed_millis,
+            nodes: ctx.nodes,
+            pv: result.pv.clone(),
+            lines: result.lines.clone(),
+        });
+
+        if elapsed_millis > limits.min_time.into() {
             break;
         }
     }
 
-    pv_line.moves[0]
+    result.time = ctx.start_instant.elapsed().as_millis();
+    result.nodes = ctx.nodes;
+
+    result
 }
 
-pub fn pvs(game_state: &GameState, alpha: i32, beta: i32, depth: u32, pv_line: &mut PVLine) -> i32 {
-    let mut new_pv_line = PVLine::from_pv_line_tail(pv_line); //PVLine::new();
-    let mut a = alpha;
-    let b = beta;
-    let mut score: i32;
+fn pvs(
+    game_state: &GameState,
+    alpha: i32,
+    beta: i32,
+    depth: u32,
+    pv_line: &mut PVLine,
+    ctx: &mut SearchContext,
+) -> i32 {
+    ctx.nodes += 1;
+
+    if ctx.should_abort() {
+        return 0;
+    }
+
+    // the root still needs a move, below it a solved position needs no search
+    if game_state.move_count > ctx.root_move_count {
+        if let Some(outcome) = probe_tablebase(game_state) {
+            return outcome.get_score(game_state.move_count);
+        }
+    }
 
     if depth == 0 {
         return evaluate(game_state);
@@ -82,25 +364,68 @@ pub fn pvs(game_state: &GameState, alpha: i32, beta: i32, depth: u32, pv_line: &
         swap_move_to_front(&mut moves, pv_line.moves[0]);
     }
 
+    search_moves(game_state, moves, alpha, beta, depth, pv_line, ctx)
+}
+
+// the principal variation search over the given moves of a position
+fn search_moves(
+    game_state: &GameState,
+    moves: Vec<Move>,
+    alpha: i32,
+    beta: i32,
+    depth: u32,
+    pv_line: &mut PVLine,
+    ctx: &mut SearchContext,
+) -> i32 {
+    let mut new_pv_line = PVLine::from_pv_line_tail(pv_line); //PVLine::new();
+    let mut a = alpha;
+    let b = beta;
+    let mut score: i32;
+
     for (i, mov) in moves.into_iter().enumerate() {
         let mut game_state_move = game_state.clone();
         game_state_move.make_move(mov);
 
         if i == 0 {
-            score = -pvs(&game_state_move, -b, -a, depth - 1, &mut new_pv_line);
+            score = -pvs(&game_state_move, -b, -a, depth - 1, &mut new_pv_line, ctx);
         } else {
             if depth > 2 && !mov.is_jump() {
                 // late move reduction
-                score = -pvs(&game_state_move, -a - 1, -a, depth - 2, &mut new_pv_line);
+                score = -pvs(
+                    &game_state_move,
+                    -a - 1,
+                    -a,
+                    depth - 2,
+                    &mut new_pv_line,
+                    ctx,
+                );
             } else {
-                score = -pvs(&game_state_move, -a - 1, -a, depth - 1, &mut new_pv_line);
+                score = -pvs(
+                    &game_state_move,
+                    -a - 1,
+                    -a,
+                    depth - 1,
+                    &mut new_pv_line,
+                    ctx,
+                );
             }
 
             if a < score && score < b {
-                score = -pvs(&game_state_move, -b, -score, depth - 1, &mut new_pv_line);
+                score = -pvs(
+                    &game_state_move,
+                    -b,
+                    -score,
+                    depth - 1,
+                    &mut new_pv_line,
+                    ctx,
+                );
             }
         }
 
+        if ctx.is_aborted {
+            return 0;
+        }
+
         if score > a {
             a = score;
             pv_line.update(mov, &mut new_pv_line);
@@ -114,9 +439,18 @@ pub fn pvs(game_state: &GameState, alpha: i32, beta: i32, depth: u32, pv_line: &
     a
 }
 
+// every king is worth a step, enough kings win
+fn get_king_score(kings: usize) -> i32 {
+    if kings >= get_geometry().get_kings_to_win() {
+        SCORE_MAX
+    } else {
+        kings as i32 * SCORE_STEP
+    }
+}
+
 pub fn evaluate(game_state: &GameState) -> i32 {
-    let white_king_score = SCORE_KING_COUNT[game_state.board.white_kings.len()];
-    let black_king_score = SCORE_KING_COUNT[game_state.board.black_kings.len()];
+    let white_king_score = get_king_score(game_state.board.white_kings.len());
+    let black_king_score = get_king_score(game_state.board.black_kings.len());
     let move_count_score = game_state.move_count as i32;
 
     let mut score = white_king_score - black_king_score;
@@ -148,6 +482,7 @@ fn swap_move_to_front(moves: &mut [Move], mov: Move) {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::notation::START_POSITION;
 
     #[test]
     fn test_update_pv_line() {
@@ -202,4 +537,91 @@ mod test {
             ],
         );
     }
+
+    #[test]
+    fn test_think_with_depth_limit() {
+        let game_state = GameState::from_notation(START_POSITION).unwrap();
+
+        let mut depths = Vec::new();
+        let limits = SearchLimits::new(4, u32::MAX);
+        let result = think_with(&game_state, &limits, &mut |info| depths.push(info.depth));
+
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert_eq!(result.depth, 4);
+        assert_eq!(result.best_move, result.pv.first().copied());
+        assert!(game_state
+            .generate_moves()
+            .contains(&result.best_move.unwrap()));
+        assert!(result.nodes > 0);
+    }
+
+    #[test]
+    fn test_think_with_node_limit() {
+        let game_state = GameState::new();
+
+        let mut limits = SearchLimits::new(100, u32::MAX);
+        limits.max_nodes = Some(5000);
+        let result = think_with(&game_state, &limits, &mut |_| {});
+
+        // the limit is only checked every 1024 nodes
+        assert!(result.nodes < 5000 + 1024);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn test_think_with_multi_pv() {
+        let game_state = GameState::from_notation(START_POSITION).unwrap();
+
+        let mut limits = SearchLimits::new(4, u32::MAX);
+        limits.multi_pv = 3;
+        let mut line_counts = Vec::new();
+        let result = think_with(&game_state, &limits, &mut |info| {
+            line_counts.push(info.lines.len())
+        });
+
+        assert_eq!(line_counts, vec![3, 3, 3, 3]);
+        assert_eq!(result.pv, result.lines[0].pv);
+        assert_eq!(result.score, result.lines[0].score);
+        assert!(result
+            .lines
+            .windows(2)
+            .all(|w| w[0].score >= w[1].score));
+
+        // every line starts with a different move
+        let first_moves: Vec<Move> = result
+            .lines
+            .iter()
+            .map(|line| line.pv[0])
+            .collect();
+        assert!(!first_moves[1..].contains(&first_moves[0]));
+        assert_ne!(first_moves[1], first_moves[2]);
+
+        // there are not more lines than moves
+        let game_state = GameState::from_notation("7/KKKK3/1KPpp2/1pppp2/7/7 w 0").unwrap();
+        limits.multi_pv = 100;
+        let result = think_with(&game_state, &limits, &mut |_| {});
+        assert_eq!(result.lines.len(), game_state.generate_moves().len());
+        assert!(result.lines[0].score > SCORE_MAX - 10);
+    }
+
+    #[test]
+    fn test_think_with_stop_flag() {
+        let game_state = GameState::new();
+
+        let stop = Arc::new(AtomicBool::new(true));
+        let limits = SearchLimits {
+            max_depth: 100,
+            min_time: u32::MAX,
+            max_time: None,
+            max_nodes: None,
+            stop: Some(stop),
+            multi_pv: 1,
+        };
+        let result = think_with(&game_state, &limits, &mut |_| {});
+
+        // the first iteration always completes, afterwards the stop flag is honoured
+        assert!(result.depth >= 1);
+        assert!(result.depth < 100);
+        assert!(result.best_move.is_some());
+    }
 }
@@ -0,0 +1,217 @@
+use std::io::stdin;
+
+use crate::constants::BoardIndex;
+use crate::geometry::get_geometry;
+use crate::gui::GameMode;
+use crate::move_gen::parse_square;
+use crate::state::Board;
+use crate::state::GameState;
+use crate::theme::get_theme;
+
+pub enum SetupAction {
+    Play(GameMode),
+    Analyze,
+}
+
+const SETUP_HELP: &str = "\
+Setup commands:
+    wp <square>, wk <square>    place a white pawn/king, e.g. 'wk c3'
+    bp <square>, bk <square>    place a black pawn/king
+    x <square>                  remove the piece on a square
+    side w|b                    choose the side to move
+    clear                       remove all pieces
+    reset                       restore the initial position
+    load <notation>             load a position notation
+    play b|w|h                  start a game as black, as white or playing both sides
+    analyze                     analyze the position
+    q                           leave the setup without starting";
+
+pub fn run_setup(game_state: &mut GameState) -> Option<SetupAction> {
+    println!("{SETUP_HELP}");
+
+    loop {
+        println!("*******************************************************");
+        println!("{game_state}");
+        print_validation(&game_state.board);
+        println!("Enter a setup command or 'help':");
+
+        let mut buffer = String::new();
+        let stdin = stdin();
+        if stdin.read_line(&mut buffer).unwrap() == 0 {
+            return None;
+        }
+
+        let tokens: Vec<&str> = buffer.split_whitespace().collect();
+
+        let result = match tokens.as_slice() {
+            ["help"] => {
+                println!("{SETUP_HELP}");
+                Ok(None)
+            }
+            [piece @ ("wp" | "wk" | "bp" | "bk"), square] => parse_setup_square(square)
+                .and_then(|i| place_piece(&mut game_state.board, piece, i))
+                .map(|_| None),
+            ["x", square] => parse_setup_square(square)
+                .map(|i| remove_piece(&mut game_state.board, i))
+                .map(|_| None),
+            ["side", "w"] => {
+                game_state.is_active_player_white = true;
+                Ok(None)
+            }
+            ["side", "b"] => {
+                game_state.is_active_player_white = false;
+                Ok(None)
+            }
+            ["clear"] => {
+                game_state.board = Board::empty();
+                Ok(None)
+            }
+            ["reset"] => {
+                *game_state = GameState::new();
+                Ok(None)
+            }
+            ["load", notation @ ..] => GameState::from_notation(&notation.join(" "))
+                .map(|loaded| *game_state = loaded)
+                .map(|_| None),
+            ["play", "b"] => finish_setup(game_state, SetupAction::Play(GameMode::ComputerWhite)),
+            ["play", "w"] => finish_setup(game_state, SetupAction::Play(GameMode::ComputerBlack)),
+            ["play", "h"] => finish_setup(game_state, SetupAction::Play(GameMode::NoComputer)),
+            ["play", ..] => Err("please enter one of the game modes: b/w/h".to_string()),
+            ["analyze"] => finish_setup(game_state, SetupAction::Analyze),
+            ["q"] => return None,
+            _ => Err("invalid setup command, type 'help' for a list of commands".to_string()),
+        };
+
+        match result {
+            Ok(Some(action)) => return Some(action),
+            Ok(None) => {}
+            Err(error) => println!("{}", get_theme().error(&error)),
+        }
+    }
+}
+
+// starting from the position is only possible once it is valid
+fn finish_setup(
+    game_state: &mut GameState,
+    action: SetupAction,
+) -> Result<Option<SetupAction>, String> {
+    if let Some(problem) = game_state.board.validate().first() {
+        return Err(format!("the position is not valid: {problem}"));
+    }
+
+    game_state.move_count = 0;
+
+    Ok(Some(action))
+}
+
+fn parse_setup_square(square: &str) -> Result<BoardIndex, String> {
+    parse_square(square).ok_or(format!("invalid square '{square}', expected e.g. 'c3'"))
+}
+
+fn place_piece(board: &mut Board, piece: &str, i: BoardIndex) -> Result<(), String> {
+    remove_piece(board, i);
+
+    let is_white = piece.starts_with('w');
+    let pieces = if is_white {
+        board.count_white_pieces()
+    } else {
+        board.count_black_pieces()
+    };
+
+    let pieces_per_side = get_geometry().pieces_per_side;
+    if pieces >= pieces_per_side {
+        return Err(format!(
+            "{} already has {pieces_per_side} pieces, remove one first",
+            if is_white { "white" } else { "black" }
+        ));
+    }
+
+    let list = match piece {
+        "wp" => &mut board.white_pawns,
+        "wk" => &mut board.white_kings,
+        "bp" => &mut board.black_pawns,
+        _ => &mut board.black_kings,
+    };
+
+    list.push_front(i);
+    board.piece_bits |= 1 << i;
+
+    Ok(())
+}
+
+fn remove_piece(board: &mut Board, i: BoardIndex) {
+    board.white_pawns.find_and_remove(i);
+    board.white_kings.find_and_remove(i);
+    board.black_pawns.find_and_remove(i);
+    board.black_kings.find_and_remove(i);
+    board.piece_bits &= !(1 << i);
+}
+
+fn print_validation(board: &Board) {
+    let pieces_per_side = get_geometry().pieces_per_side;
+    println!(
+        "white pieces: {}/{pieces_per_side}, black pieces: {}/{pieces_per_side}",
+        board.count_white_pieces(),
+        board.count_black_pieces()
+    );
+
+    let problems = board.validate();
+
+    if problems.is_empty() {
+        println!("{}", get_theme().success("the position is valid"));
+    }
+
+    for problem in problems {
+        println!("{}", get_theme().error(&problem));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_place_and_remove_pieces() {
+        let mut board = Board::empty();
+
+        place_piece(&mut board, "wk", 16).unwrap();
+        place_piece(&mut board, "bp", 17).unwrap();
+        assert_eq!(board.piece_bits, 0b11 << 16);
+        assert_eq!(board.count_white_pieces(), 1);
+        assert_eq!(board.count_black_pieces(), 1);
+
+        // placing a piece on an occupied square replaces it
+        place_piece(&mut board, "wp", 17).unwrap();
+        assert_eq!(board.piece_bits, 0b11 << 16);
+        assert_eq!(board.count_white_pieces(), 2);
+        assert_eq!(board.count_black_pieces(), 0);
+        assert!(board.white_pawns.contains(17));
+
+        remove_piece(&mut board, 16);
+        assert_eq!(board.piece_bits, 1 << 17);
+        assert_eq!(board.count_white_pieces(), 1);
+
+        for i in 0..5 {
+            place_piece(&mut board, "wp", i).unwrap();
+        }
+        assert!(place_piece(&mut board, "wk", 20).is_err());
+        assert_eq!(board.count_white_pieces(), 6);
+    }
+
+    #[test]
+    fn test_validate_setup() {
+        let mut board = GameState::new().board;
+        assert!(board.validate().is_empty());
+
+        remove_piece(&mut board, 9);
+        assert_eq!(board.validate().len(), 1);
+
+        place_piece(&mut board, "wp", 0).unwrap();
+        assert_eq!(
+            board.validate(),
+            vec!["the pieces are not 4-connected".to_string()]
+        );
+
+        assert_eq!(Board::empty().validate().len(), 2);
+    }
+}
@@ -0,0 +1,308 @@
+use std::fs;
+use std::fs::File;
+use std::ops::Deref;
+
+use memmap2::Mmap;
+
+use crate::constants::BitBoard;
+use crate::constants::BOARD_HEIGHT;
+use crate::constants::BOARD_TOTAL_PIECES;
+use crate::constants::BOARD_WIDTH;
+use crate::tablebase::ALL_SQUARES;
+
+const INDEX_MAGIC: &[u8; 8] = b"uisge-si";
+const INDEX_VERSION: u8 = 1;
+const INDEX_HEADER_BYTES: usize = 8 + 1 + 1 + 1 + 8;
+
+// the shapes are split into buckets by their highest squares, so a lookup
+// only searches the shapes of one bucket
+const BUCKET_BITS: u8 = 16;
+const BUCKET_SHIFT: u8 = BOARD_TOTAL_PIECES - BUCKET_BITS;
+const BUCKET_COUNT: usize = 1 << BUCKET_BITS;
+const BUCKETS_BYTES: usize = (BUCKET_COUNT + 1) * 8;
+
+enum IndexBytes {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for IndexBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            IndexBytes::Owned(bytes) => bytes,
+            IndexBytes::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+// A bijection between the connected shapes and the numbers 0..len(): the rank
+// of a shape is its position in the sorted list of all shapes. The file is
+// the header, the index of the first shape of every bucket and the sorted
+// shapes as little endian u64s, so it is used in place after mapping it into
+// memory and only the parts that are looked up are read from disk.
+pub struct ShapeIndex {
+    bytes: IndexBytes,
+    count: u64,
+}
+
+impl ShapeIndex {
+    pub fn new(shapes: &[BitBoard]) -> Result<Self, String> {
+        let mut sorted = shapes.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        if let Some(shape) = sorted
+            .iter()
+            .find(|shape| *shape & !ALL_SQUARES != 0)
+        {
+            return Err(format!(
+                "the shape {shape:#x} has squares outside of the board"
+            ));
+        }
+
+        let mut bytes = Vec::with_capacity(INDEX_HEADER_BYTES + BUCKETS_BYTES + 8 * sorted.len());
+        bytes.extend_from_slice(INDEX_MAGIC);
+        bytes.push(INDEX_VERSION);
+        bytes.push(BOARD_WIDTH);
+        bytes.push(BOARD_HEIGHT);
+        bytes.extend_from_slice(&(sorted.len() as u64).to_le_bytes());
+
+        let mut first = 0;
+        for bucket in 0..=BUCKET_COUNT as u64 {
+            while first < sorted.len() && sorted[first] >> BUCKET_SHIFT < bucket {
+                first += 1;
+            }
+            bytes.extend_from_slice(&(first as u64).to_le_bytes());
+        }
+
+        for shape in &sorted {
+            bytes.extend_from_slice(&shape.to_le_bytes());
+        }
+
+        Ok(Self {
+            bytes: IndexBytes::Owned(bytes),
+            count: sorted.len() as u64,
+        })
+    }
+
+    // The shapes themselves are not checked, that would read the whole file.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|error| format!("could not read '{path}': {error}"))?;
+
+        // The file must not be changed while it is mapped. write() never
+        // changes an existing file but renames a new one over it, so a mapping
+        // keeps the old contents; other programs that change the file in
+        // place are not guarded against.
+        let mmap = unsafe { Mmap::map(&file) }
+            .map_err(|error| format!("could not map '{path}': {error}"))?;
+
+        Self::from_bytes(IndexBytes::Mapped(mmap)).map_err(|error| format!("{path}: {error}"))
+    }
+
+    fn from_bytes(bytes: IndexBytes) -> Result<Self, String> {
+        if bytes.len() < INDEX_HEADER_BYTES + BUCKETS_BYTES || &bytes[..8] != INDEX_MAGIC {
+            return Err("not a shape index file".to_string());
+        }
+
+        if bytes[8] != INDEX_VERSION {
+            return Err(format!("unsupported shape index version {}", bytes[8]));
+        }
+
+        let (width, height) = (bytes[9], bytes[10]);
+        if (width, height) != (BOARD_WIDTH, BOARD_HEIGHT) {
+            return Err(format!(
+                "the shapes are for a {width}x{height} board, expected {BOARD_WIDTH}x{BOARD_HEIGHT}"
+            ));
+        }
+
+        let count = read_u64(&bytes, 11);
+        let expected_len = (count as usize)
+            .checked_mul(8)
+            .and_then(|len| len.checked_add(INDEX_HEADER_BYTES + BUCKETS_BYTES));
+        if expected_len != Some(bytes.len()) {
+            return Err(format!(
+                "expected {count} shapes, the file has {} bytes",
+                bytes.len()
+            ));
+        }
+
+        let index = Self { bytes, count };
+
+        let mut previous = 0;
+        for bucket in 0..=BUCKET_COUNT {
+            let first = index.get_bucket_start(bucket);
+            if first < previous || first > count {
+                return Err("the buckets are damaged".to_string());
+            }
+            previous = first;
+        }
+
+        if previous != count {
+            return Err("the buckets do not cover all shapes".to_string());
+        }
+
+        Ok(index)
+    }
+
+    // an interrupted write must not leave a damaged index, and an index that
+    // is mapped somewhere must not change underneath it
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let temporary_path = format!("{path}.tmp");
+        fs::write(&temporary_path, &*self.bytes)
+            .map_err(|error| format!("could not write '{temporary_path}': {error}"))?;
+        fs::rename(&temporary_path, path)
+            .map_err(|error| format!("could not write '{path}': {error}"))
+    }
+
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn get_rank(&self, shape: BitBoard) -> Option<u64> {
+        if shape & !ALL_SQUARES != 0 {
+            return None;
+        }
+
+        let bucket = (shape >> BUCKET_SHIFT) as usize;
+        let mut low = self.get_bucket_start(bucket);
+        let mut high = self.get_bucket_start(bucket + 1);
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let other = self.get_shape_unchecked(middle);
+
+            if other == shape {
+                return Some(middle);
+            } else if other < shape {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        None
+    }
+
+    pub fn get_shape(&self, rank: u64) -> Option<BitBoard> {
+        (rank < self.count).then(|| self.get_shape_unchecked(rank))
+    }
+
+    fn get_bucket_start(&self, bucket: usize) -> u64 {
+        read_u64(&self.bytes, INDEX_HEADER_BYTES + 8 * bucket)
+    }
+
+    fn get_shape_unchecked(&self, rank: u64) -> BitBoard {
+        read_u64(
+            &self.bytes,
+            INDEX_HEADER_BYTES + BUCKETS_BYTES + 8 * rank as usize,
+        )
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(
+        bytes[offset..offset + 8]
+            .try_into()
+            .unwrap(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tablebase::parse_region;
+    use crate::valid_board_gen::grow_polyominoes;
+
+    // every connected shape of 12 squares within a1:e4
+    fn get_small_shapes() -> Vec<BitBoard> {
+        grow_polyominoes(parse_region("a1:e4").unwrap(), false)
+    }
+
+    fn assert_round_trip(index: &ShapeIndex, shapes: &[BitBoard]) {
+        assert_eq!(index.len(), shapes.len() as u64);
+
+        for rank in 0..index.len() {
+            let shape = index.get_shape(rank).unwrap();
+            assert_eq!(index.get_rank(shape), Some(rank));
+        }
+
+        for shape in shapes {
+            let rank = index.get_rank(*shape).unwrap();
+            assert_eq!(index.get_shape(rank), Some(*shape));
+        }
+
+        assert_eq!(index.get_shape(index.len()), None);
+    }
+
+    #[test]
+    fn test_rank_round_trip() {
+        let mut shapes = get_small_shapes();
+        assert!(!shapes.is_empty());
+
+        // the order of the given shapes does not matter
+        shapes.reverse();
+        let index = ShapeIndex::new(&shapes).unwrap();
+        assert_round_trip(&index, &shapes);
+
+        shapes.sort_unstable();
+        assert_eq!(index.get_rank(shapes[0]), Some(0));
+
+        // shapes that are not in the index
+        assert_eq!(index.get_rank(0b111111111111), None);
+        assert_eq!(index.get_rank(shapes[0] << 7 | 1 << 41), None);
+        assert_eq!(index.get_rank(1 << 42), None);
+
+        assert!(ShapeIndex::new(&[1 << 50]).is_err());
+    }
+
+    #[test]
+    fn test_mapped_index() {
+        let shapes = get_small_shapes();
+        let path =
+            std::env::temp_dir().join(format!("uisge-shape-test-{}.idx", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        ShapeIndex::new(&shapes)
+            .unwrap()
+            .write(path)
+            .unwrap();
+        let index = ShapeIndex::open(path).unwrap();
+        assert_round_trip(&index, &shapes);
+        assert!(fs::metadata(format!("{path}.tmp")).is_err());
+
+        // writing over a mapped index leaves the mapping as it was
+        ShapeIndex::new(&shapes[..10])
+            .unwrap()
+            .write(path)
+            .unwrap();
+        assert_round_trip(&index, &shapes);
+        assert_eq!(ShapeIndex::open(path).unwrap().len(), 10);
+        ShapeIndex::new(&shapes)
+            .unwrap()
+            .write(path)
+            .unwrap();
+
+        let mut bytes = fs::read(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        bytes.pop();
+        assert!(ShapeIndex::from_bytes(IndexBytes::Owned(bytes.clone())).is_err());
+
+        bytes[INDEX_HEADER_BYTES + 7] = 1;
+        bytes.push(0);
+        assert!(ShapeIndex::from_bytes(IndexBytes::Owned(bytes.clone()))
+            .err()
+            .unwrap()
+            .contains("buckets"));
+
+        bytes[9] = 8;
+        assert!(ShapeIndex::from_bytes(IndexBytes::Owned(bytes))
+            .err()
+            .unwrap()
+            .contains("8x6"));
+
+        assert!(ShapeIndex::open(path).is_err());
+    }
+}
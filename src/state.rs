@@ -1,14 +1,45 @@
 use std::fmt::Display;
 
+use crate::bit_utils::get_bit_indices;
 use crate::constants::BitBoard;
 use crate::constants::BoardIndex;
-use crate::constants::BOARD_TOTAL_PIECES;
-use crate::constants::BOARD_WIDTH;
-use crate::constants::COLOR_BLACK_ON_MAGENTA;
-use crate::constants::COLOR_RESET;
-use crate::constants::COLOR_WHITE_ON_MAGENTA;
+use crate::geometry::get_geometry;
 use crate::piece_list::PieceList;
-use crate::valid_board_gen::make_board;
+use crate::render::render_board;
+use crate::render::render_game_state;
+use crate::render::Overlay;
+use crate::render::View;
+use crate::theme::get_theme;
+use crate::valid_board_gen::is_connected;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameResult::WhiteWins => write!(f, "white wins"),
+            GameResult::BlackWins => write!(f, "black wins"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Piece {
+    BlackKing,
+    BlackPawn,
+    WhiteKing,
+    WhitePawn,
+}
+
+impl Piece {
+    pub fn is_white(&self) -> bool {
+        matches!(self, Piece::WhiteKing | Piece::WhitePawn)
+    }
+}
 
 #[derive(Clone)]
 pub struct GameState {
@@ -37,6 +68,29 @@ impl GameState {
         }
     }
 
+    // A player wins when enough of their pieces are kings, all of them under the standard
+    // rules, or when the opponent has no moves left.
+    pub fn get_result(&self) -> Option<GameResult> {
+        let kings_to_win = get_geometry().get_kings_to_win();
+
+        if self.board.white_kings.len() >= kings_to_win {
+            return Some(GameResult::WhiteWins);
+        }
+
+        if self.board.black_kings.len() >= kings_to_win {
+            return Some(GameResult::BlackWins);
+        }
+
+        if !self.generate_moves().is_empty() {
+            return None;
+        }
+
+        match self.is_active_player_white {
+            true => Some(GameResult::BlackWins),
+            false => Some(GameResult::WhiteWins),
+        }
+    }
+
     pub fn set_active_pieces(&mut self, kings: PieceList, pawns: PieceList) {
         match self.is_active_player_white {
             true => {
@@ -53,22 +107,11 @@ impl GameState {
 
 impl Display for GameState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut text = String::new();
-
-        text.push_str(&format!("{}\n", self.board));
-
-        let move_count = self.move_count;
-        if self.is_active_player_white {
-            text.push_str(&format!(
-                "{COLOR_WHITE_ON_MAGENTA}    WHITE {move_count:05}    {COLOR_RESET}"
-            ));
-        } else {
-            text.push_str(&format!(
-                "{COLOR_BLACK_ON_MAGENTA}    BLACK {move_count:05}    {COLOR_RESET}"
-            ));
-        }
-
-        write!(f, "{text}")
+        write!(
+            f,
+            "{}",
+            render_game_state(self, &Overlay::default(), get_theme(), &View::default())
+        )
     }
 }
 
@@ -91,80 +134,25 @@ pub struct Board {
 
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut text = String::new();
-
-        text.push_str(&format!(
-            "{}{}{}\n",
-            COLOR_BLACK_ON_MAGENTA, "   a b c d e f g   ", COLOR_RESET
-        ));
-
-        let mut piece = vec![
-            format!("{}{}{}", COLOR_BLACK_ON_MAGENTA, "- ", COLOR_RESET);
-            BOARD_TOTAL_PIECES as usize
-        ];
-
-        for i in self.black_kings {
-            piece[i as usize] = format!("{}{}{}", COLOR_BLACK_ON_MAGENTA, "W ", COLOR_RESET);
-        }
-        for i in self.black_pawns {
-            piece[i as usize] = format!("{}{}{}", COLOR_BLACK_ON_MAGENTA, "o ", COLOR_RESET);
-        }
-        for i in self.white_kings {
-            piece[i as usize] = format!("{}{}{}", COLOR_WHITE_ON_MAGENTA, "W ", COLOR_RESET);
-        }
-        for i in self.white_pawns {
-            piece[i as usize] = format!("{}{}{}", COLOR_WHITE_ON_MAGENTA, "o ", COLOR_RESET);
-        }
-
-        for i in 0..BOARD_TOTAL_PIECES {
-            if i % BOARD_WIDTH == 0 {
-                text.push_str(&format!(
-                    "{} {} {}",
-                    COLOR_BLACK_ON_MAGENTA,
-                    i / BOARD_WIDTH + 1,
-                    COLOR_RESET
-                ));
-            }
-
-            text.push_str(&piece[i as usize]);
-
-            if (i + 1) % BOARD_WIDTH == 0 {
-                text.push_str(&format!(
-                    "{}{} {}\n",
-                    COLOR_BLACK_ON_MAGENTA,
-                    i / BOARD_WIDTH + 1,
-                    COLOR_RESET
-                ));
-            }
-        }
-
-        text.push_str(&format!(
-            "{}{}{}",
-            COLOR_BLACK_ON_MAGENTA, "   a b c d e f g   ", COLOR_RESET
-        ));
-
-        write!(f, "{}", text)
+        write!(
+            f,
+            "{}",
+            render_board(self, &Overlay::default(), get_theme(), &View::default()).join("\n")
+        )
     }
 }
 
 impl Board {
+    // the start position of the board geometry, all pieces are pawns
     pub fn new() -> Self {
-        let piece_bits = make_board(
-            "\
-            0000000\
-            0011000\
-            0111100\
-            0011110\
-            0001100\
-            0000000\
-            ",
-        );
+        let geometry = get_geometry();
+        let piece_bits = geometry.white_start | geometry.black_start;
 
         let black_kings = PieceList::new();
-        let black_pawns = PieceList::from(vec![23, 24, 25, 26, 31, 32]);
+        let black_pawns = PieceList::from(get_bit_indices(geometry.black_start));
 
         let white_kings = PieceList::new();
-        let white_pawns = PieceList::from(vec![9, 10, 15, 16, 17, 18]);
+        let white_pawns = PieceList::from(get_bit_indices(geometry.white_start));
 
         Self {
             piece_bits,
@@ -175,6 +163,73 @@ impl Board {
         }
     }
 
+    pub fn empty() -> Self {
+        Self {
+            piece_bits: 0,
+            black_kings: PieceList::new(),
+            black_pawns: PieceList::new(),
+            white_kings: PieceList::new(),
+            white_pawns: PieceList::new(),
+        }
+    }
+
+    pub fn get_piece(&self, i: BoardIndex) -> Option<Piece> {
+        if self.black_kings.contains(i) {
+            Some(Piece::BlackKing)
+        } else if self.black_pawns.contains(i) {
+            Some(Piece::BlackPawn)
+        } else if self.white_kings.contains(i) {
+            Some(Piece::WhiteKing)
+        } else if self.white_pawns.contains(i) {
+            Some(Piece::WhitePawn)
+        } else {
+            None
+        }
+    }
+
+    pub fn count_white_pieces(&self) -> usize {
+        self.white_kings.len() + self.white_pawns.len()
+    }
+
+    pub fn count_black_pieces(&self) -> usize {
+        self.black_kings.len() + self.black_pawns.len()
+    }
+
+    // lists every rule the position violates, an empty list means the position is valid
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let geometry = get_geometry();
+        let pieces_per_side = geometry.pieces_per_side;
+
+        let white_pieces = self.count_white_pieces();
+        if white_pieces != pieces_per_side {
+            problems.push(format!(
+                "white must have exactly {pieces_per_side} pieces, found {white_pieces}"
+            ));
+        }
+
+        let black_pieces = self.count_black_pieces();
+        if black_pieces != pieces_per_side {
+            problems.push(format!(
+                "black must have exactly {pieces_per_side} pieces, found {black_pieces}"
+            ));
+        }
+
+        if self.piece_bits != 0
+            && !is_connected(
+                self.piece_bits,
+                self.piece_bits.trailing_zeros() as BoardIndex,
+            )
+        {
+            problems.push(format!(
+                "the pieces are not {}",
+                geometry.rules.connectivity
+            ));
+        }
+
+        problems
+    }
+
     #[allow(unused)]
     pub fn is_king(&self, i: BoardIndex) -> bool {
         self.black_kings.contains(i) || self.white_kings.contains(i)
@@ -0,0 +1,372 @@
+use crate::constants::BitBoard;
+use crate::constants::BoardIndex;
+use crate::geometry::get_geometry;
+use crate::move_gen::Move;
+use crate::piece_list::PieceList;
+use crate::state::Board;
+use crate::state::GameState;
+use crate::tablebase::make_position_key;
+use crate::tablebase::to_bits;
+
+// a <-> g, b <-> f, c <-> e on the standard board, the bits of every rank are
+// reversed at once
+pub fn mirror_files(bit_board: BitBoard) -> BitBoard {
+    let geometry = get_geometry();
+    let rank_mask = (1 << geometry.width) - 1;
+    let mut mirrored = 0;
+
+    for rank in 0..geometry.height {
+        let shift = rank * geometry.width;
+        let bits: BitBoard = (bit_board >> shift) & rank_mask;
+        mirrored |= (bits.reverse_bits() >> (BitBoard::BITS - geometry.width as u32)) << shift;
+    }
+
+    mirrored
+}
+
+// 1 <-> 6, 2 <-> 5, 3 <-> 4 on the standard board
+pub fn mirror_ranks(bit_board: BitBoard) -> BitBoard {
+    let geometry = get_geometry();
+    let rank_mask = (1 << geometry.width) - 1;
+    let mut mirrored = 0;
+
+    for rank in 0..geometry.height {
+        let bits = (bit_board >> (rank * geometry.width)) & rank_mask;
+        mirrored |= bits << ((geometry.height - 1 - rank) * geometry.width);
+    }
+
+    mirrored
+}
+
+// The rules do not depend on the direction or the colour, so a position can be
+// mirrored along both axes and the colours can be swapped together with the
+// side to move without changing its value. Every element of this group of 8
+// symmetries is its own inverse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    pub mirror_files: bool,
+    pub mirror_ranks: bool,
+    pub swap_colours: bool,
+}
+
+pub const IDENTITY: Symmetry = Symmetry {
+    mirror_files: false,
+    mirror_ranks: false,
+    swap_colours: false,
+};
+
+pub const SYMMETRIES: [Symmetry; 8] = make_symmetries();
+
+// the symmetries that keep the colours, for tables whose values depend on them
+pub const MIRRORS: [Symmetry; 4] = [SYMMETRIES[0], SYMMETRIES[1], SYMMETRIES[2], SYMMETRIES[3]];
+
+const fn make_symmetries() -> [Symmetry; 8] {
+    let mut symmetries = [IDENTITY; 8];
+
+    let mut i = 0;
+    while i < 8 {
+        symmetries[i] = Symmetry {
+            mirror_files: i & 1 != 0,
+            mirror_ranks: i & 2 != 0,
+            swap_colours: i & 4 != 0,
+        };
+        i += 1;
+    }
+
+    symmetries
+}
+
+impl Symmetry {
+    pub fn inverse(self) -> Self {
+        self
+    }
+
+    pub fn map_index(self, i: BoardIndex) -> BoardIndex {
+        let geometry = get_geometry();
+        let mut file = i % geometry.width;
+        let mut rank = i / geometry.width;
+
+        if self.mirror_files {
+            file = geometry.width - 1 - file;
+        }
+
+        if self.mirror_ranks {
+            rank = geometry.height - 1 - rank;
+        }
+
+        geometry.get_index(file, rank)
+    }
+
+    // the colours are not part of a bit board, only the squares move
+    pub fn map_bit_board(self, bit_board: BitBoard) -> BitBoard {
+        let mut mapped = bit_board;
+
+        if self.mirror_files {
+            mapped = mirror_files(mapped);
+        }
+
+        if self.mirror_ranks {
+            mapped = mirror_ranks(mapped);
+        }
+
+        mapped
+    }
+
+    pub fn map_move(self, mov: Move) -> Move {
+        Move::new(self.map_index(mov.get_from()), self.map_index(mov.get_to()))
+    }
+
+    pub fn map_board(self, board: &Board) -> Board {
+        let map_pieces = |pieces: PieceList| {
+            pieces
+                .into_iter()
+                .fold(PieceList::new(), |mut mapped, i| {
+                    mapped.push_front(self.map_index(i));
+                    mapped
+                })
+        };
+
+        let (white_kings, white_pawns, black_kings, black_pawns) = match self.swap_colours {
+            false => (
+                board.white_kings,
+                board.white_pawns,
+                board.black_kings,
+                board.black_pawns,
+            ),
+            true => (
+                board.black_kings,
+                board.black_pawns,
+                board.white_kings,
+                board.white_pawns,
+            ),
+        };
+
+        Board {
+            piece_bits: self.map_bit_board(board.piece_bits),
+            black_kings: map_pieces(black_kings),
+            black_pawns: map_pieces(black_pawns),
+            white_kings: map_pieces(white_kings),
+            white_pawns: map_pieces(white_pawns),
+        }
+    }
+
+    // the position key of the mapped position, computed from the bit boards alone
+    fn map_position_key(
+        self,
+        piece_bits: BitBoard,
+        white_bits: BitBoard,
+        king_bits: BitBoard,
+        is_active_player_white: bool,
+    ) -> u128 {
+        let piece_bits = self.map_bit_board(piece_bits);
+        let mut white_bits = self.map_bit_board(white_bits);
+
+        if self.swap_colours {
+            white_bits = piece_bits & !white_bits;
+        }
+
+        make_position_key(
+            piece_bits,
+            white_bits,
+            self.map_bit_board(king_bits),
+            is_active_player_white != self.swap_colours,
+        )
+    }
+
+    pub fn map_game_state(self, game_state: &GameState) -> GameState {
+        GameState {
+            board: self.map_board(&game_state.board),
+            is_active_player_white: game_state.is_active_player_white != self.swap_colours,
+            move_count: game_state.move_count,
+        }
+    }
+}
+
+impl GameState {
+    // The representative of all symmetric positions is the one with the
+    // smallest position key. A move found for it is turned into a move for
+    // this position with symmetry.inverse().map_move(mov).
+    pub fn get_canonical(&self) -> (GameState, Symmetry) {
+        let (_, symmetry) = self.get_canonical_key_among(&SYMMETRIES);
+
+        (symmetry.map_game_state(self), symmetry)
+    }
+
+    pub fn get_canonical_key(&self) -> u128 {
+        self.get_canonical_key_among(&SYMMETRIES)
+            .0
+    }
+
+    // the smallest key of the positions the given symmetries lead to, and the
+    // symmetry that leads there
+    pub fn get_canonical_key_among(&self, symmetries: &[Symmetry]) -> (u128, Symmetry) {
+        let board = &self.board;
+        let white_bits = to_bits(board.white_kings) | to_bits(board.white_pawns);
+        let king_bits = to_bits(board.white_kings) | to_bits(board.black_kings);
+
+        symmetries
+            .iter()
+            .map(|symmetry| {
+                let key = symmetry.map_position_key(
+                    board.piece_bits,
+                    white_bits,
+                    king_bits,
+                    self.is_active_player_white,
+                );
+                (key, *symmetry)
+            })
+            .min_by_key(|(key, _)| *key)
+            .unwrap()
+    }
+}
+
+// the smallest of the mirrored bit boards, for boards without colours like
+// the ones of generate_valid_boards
+pub fn get_canonical_bit_board(bit_board: BitBoard) -> (BitBoard, Symmetry) {
+    MIRRORS
+        .iter()
+        .map(|symmetry| (symmetry.map_bit_board(bit_board), *symmetry))
+        .min_by_key(|(bit_board, _)| *bit_board)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::move_gen::parse_square;
+    use crate::search::evaluate;
+    use crate::tablebase::get_position_key;
+
+    const POSITION: &str = "7/2PP3/1PKPP2/2pppk1/3pp2/7 b 5";
+
+    #[test]
+    fn test_mirror_bit_boards() {
+        let a1 = 1 << parse_square("a1").unwrap();
+        let g1 = 1 << parse_square("g1").unwrap();
+        let a6 = 1 << parse_square("a6").unwrap();
+        let d3 = 1 << parse_square("d3").unwrap();
+        let d4 = 1 << parse_square("d4").unwrap();
+
+        assert_eq!(mirror_files(a1 | d3), g1 | d3);
+        assert_eq!(mirror_ranks(a1 | d3), a6 | d4);
+
+        let bit_board = GameState::new().board.piece_bits;
+        for symmetry in SYMMETRIES {
+            assert_eq!(
+                symmetry.map_bit_board(symmetry.map_bit_board(bit_board)),
+                bit_board
+            );
+
+            let mapped_indices: BitBoard = (0..42)
+                .filter(|i| bit_board & 1 << i != 0)
+                .fold(0, |bits, i| bits | 1 << symmetry.map_index(i));
+            assert_eq!(symmetry.map_bit_board(bit_board), mapped_indices);
+        }
+    }
+
+    #[test]
+    fn test_symmetric_positions() {
+        let game_state = GameState::from_notation(POSITION).unwrap();
+        let moves = game_state.generate_moves();
+
+        for symmetry in SYMMETRIES {
+            let mapped = symmetry.map_game_state(&game_state);
+            assert_eq!(
+                game_state
+                    .get_canonical_key_among(&[symmetry])
+                    .0,
+                get_position_key(&mapped)
+            );
+            assert_eq!(
+                symmetry
+                    .inverse()
+                    .map_game_state(&mapped)
+                    .to_notation(),
+                POSITION
+            );
+
+            // the legal moves are the mapped legal moves
+            let mut mapped_moves: Vec<String> = moves
+                .iter()
+                .map(|mov| symmetry.map_move(*mov).to_string())
+                .collect();
+            let mut expected_moves: Vec<String> = mapped
+                .generate_moves()
+                .iter()
+                .map(Move::to_string)
+                .collect();
+            mapped_moves.sort();
+            expected_moves.sort();
+            assert_eq!(mapped_moves, expected_moves);
+
+            // the evaluation of balanced positions favours white, so only
+            // the mirrored positions get exactly the same score
+            if !symmetry.swap_colours {
+                assert_eq!(evaluate(&mapped), evaluate(&game_state));
+            }
+            assert_eq!(mapped.perft(3), game_state.perft(3));
+        }
+    }
+
+    #[test]
+    fn test_canonical_position() {
+        let game_state = GameState::from_notation(POSITION).unwrap();
+        let (canonical, symmetry) = game_state.get_canonical();
+
+        assert_eq!(get_position_key(&canonical), game_state.get_canonical_key());
+
+        for other in SYMMETRIES {
+            let mapped = other.map_game_state(&game_state);
+            assert_eq!(mapped.get_canonical_key(), game_state.get_canonical_key());
+
+            // a move of the canonical position maps back to a legal move
+            let (canonical, symmetry) = mapped.get_canonical();
+            for mov in canonical.generate_moves() {
+                let actual = symmetry.inverse().map_move(mov);
+                assert!(mapped
+                    .generate_moves()
+                    .contains(&actual));
+
+                let mut after_canonical = canonical.clone();
+                after_canonical.make_move(mov);
+                let mut after_actual = mapped.clone();
+                after_actual.make_move(actual);
+                assert_eq!(
+                    after_canonical.get_canonical_key(),
+                    after_actual.get_canonical_key()
+                );
+            }
+        }
+
+        // the canonical position maps back to the original one
+        assert_eq!(
+            symmetry
+                .inverse()
+                .map_game_state(&canonical)
+                .to_notation(),
+            POSITION
+        );
+    }
+
+    #[test]
+    fn test_start_position_symmetry() {
+        // turning the board around and swapping the colours gives the start
+        // position with the other side to move
+        let game_state = GameState::new();
+        let symmetry = Symmetry {
+            mirror_files: true,
+            mirror_ranks: true,
+            swap_colours: true,
+        };
+
+        let mut mapped = symmetry.map_game_state(&game_state);
+        assert!(!mapped.is_active_player_white);
+        mapped.is_active_player_white = true;
+        assert_eq!(get_position_key(&mapped), get_position_key(&game_state));
+
+        let (bit_board, _) = get_canonical_bit_board(game_state.board.piece_bits);
+        assert!(MIRRORS
+            .iter()
+            .all(|symmetry| symmetry.map_bit_board(game_state.board.piece_bits) >= bit_board));
+    }
+}
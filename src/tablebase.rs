@@ -0,0 +1,856 @@
+use std::fmt::Display;
+use std::fs;
+use std::sync::OnceLock;
+
+use crate::bit_utils::get_bit_indices;
+use crate::constants::BitBoard;
+use crate::constants::BOARD_TOTAL_PIECES;
+use crate::constants::BOARD_WIDTH;
+use crate::constants::PIECES_PER_SIDE;
+use crate::constants::SCORE_MAX;
+use crate::constants::SCORE_MIN;
+use crate::geometry::get_geometry;
+use crate::move_gen::parse_square;
+use crate::piece_list::PieceList;
+use crate::shape_index::ShapeIndex;
+use crate::state::Board;
+use crate::state::GameResult;
+use crate::state::GameState;
+use crate::valid_board_gen::grow_polyominoes;
+
+pub const ALL_SQUARES: BitBoard = (1 << BOARD_TOTAL_PIECES) - 1;
+pub const DEFAULT_MAX_POSITIONS: usize = 5_000_000;
+
+const TABLEBASE_MAGIC: &[u8; 8] = b"uisge-tb";
+const TABLEBASE_VERSION: u8 = 1;
+const HEADER_BYTES: usize = 8 + 1 + 8 + 1 + 8;
+const KEY_BYTES: usize = 9;
+const ENTRY_BYTES: usize = KEY_BYTES + 2;
+
+// The value of a position for the side to move, with the number of plies
+// until the game ends when both sides play perfectly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Win(u16),
+    Loss(u16),
+    Draw,
+}
+
+impl Outcome {
+    // the same scores the search gives to a game that ends after that many plies
+    pub fn get_score(&self, move_count: u32) -> i32 {
+        match *self {
+            Outcome::Win(plies) => SCORE_MAX - (move_count + plies as u32) as i32,
+            Outcome::Loss(plies) => SCORE_MIN + (move_count + plies as u32) as i32,
+            Outcome::Draw => 0,
+        }
+    }
+
+    fn encode(&self) -> u16 {
+        match *self {
+            Outcome::Draw => 0,
+            Outcome::Win(plies) => 1 + 2 * plies,
+            Outcome::Loss(plies) => 2 + 2 * plies,
+        }
+    }
+
+    fn decode(value: u16) -> Self {
+        match value {
+            0 => Outcome::Draw,
+            _ if value % 2 == 1 => Outcome::Win(value / 2),
+            _ => Outcome::Loss(value / 2 - 1),
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Win(plies) => write!(f, "win in {plies}"),
+            Outcome::Loss(plies) => write!(f, "loss in {plies}"),
+            Outcome::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+// The positions a tablebase covers: all pieces stand inside the region and
+// both sides have at least min_kings kings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Subset {
+    pub region: BitBoard,
+    pub min_kings: usize,
+}
+
+impl Default for Subset {
+    fn default() -> Self {
+        Self {
+            region: ALL_SQUARES,
+            min_kings: 0,
+        }
+    }
+}
+
+impl Subset {
+    pub fn contains(&self, game_state: &GameState) -> bool {
+        game_state.board.piece_bits & !self.region == 0
+            && game_state.board.white_kings.len() >= self.min_kings
+            && game_state.board.black_kings.len() >= self.min_kings
+    }
+}
+
+// a rectangle given by two corners, e.g. "a1:d4"
+pub fn parse_region(text: &str) -> Result<BitBoard, String> {
+    let error = || format!("invalid region '{text}', expected two corners like 'a1:d4'");
+
+    let (from, to) = text.split_once(':').ok_or_else(error)?;
+    let from = parse_square(from).ok_or_else(error)?;
+    let to = parse_square(to).ok_or_else(error)?;
+
+    let (file_from, file_to) = sorted(from % BOARD_WIDTH, to % BOARD_WIDTH);
+    let (rank_from, rank_to) = sorted(from / BOARD_WIDTH, to / BOARD_WIDTH);
+
+    let mut region = 0;
+    for rank in rank_from..=rank_to {
+        for file in file_from..=file_to {
+            region |= 1 << (rank * BOARD_WIDTH + file);
+        }
+    }
+
+    Ok(region)
+}
+
+fn sorted(a: u8, b: u8) -> (u8, u8) {
+    (a.min(b), a.max(b))
+}
+
+// An exact key of a position: the occupied squares, then one bit per occupied
+// square (in index order) for the colour and one for the piece type, and the
+// side to move. The move count is not part of it. On the standard board the
+// colours start at bit 42, the piece types at 54 and the side to move is 66.
+pub fn get_position_key(game_state: &GameState) -> u128 {
+    let board = &game_state.board;
+
+    make_position_key(
+        board.piece_bits,
+        to_bits(board.white_kings) | to_bits(board.white_pawns),
+        to_bits(board.white_kings) | to_bits(board.black_kings),
+        game_state.is_active_player_white,
+    )
+}
+
+pub fn make_position_key(
+    piece_bits: BitBoard,
+    white_bits: BitBoard,
+    king_bits: BitBoard,
+    is_active_player_white: bool,
+) -> u128 {
+    let geometry = get_geometry();
+    let colour_shift = geometry.get_square_count() as u32;
+    let type_shift = colour_shift + 2 * geometry.pieces_per_side as u32;
+
+    let mut key = piece_bits as u128;
+    let mut bits = piece_bits;
+    let mut n = 0;
+
+    while bits != 0 {
+        let square = bits & bits.wrapping_neg();
+        if white_bits & square != 0 {
+            key |= 1 << (colour_shift + n);
+        }
+        if king_bits & square != 0 {
+            key |= 1 << (type_shift + n);
+        }
+        bits &= bits - 1;
+        n += 1;
+    }
+
+    if !is_active_player_white {
+        key |= 1 << (type_shift + 2 * geometry.pieces_per_side as u32);
+    }
+
+    key
+}
+
+pub fn to_bits(pieces: PieceList) -> BitBoard {
+    pieces
+        .into_iter()
+        .fold(0, |bits, i| bits | 1 << i)
+}
+
+// Tablebase files start with a header (magic, version, region, minimum number
+// of kings and entry count) followed by the entries sorted by key, 9 bytes of
+// key and 2 bytes of outcome each.
+pub struct Tablebase {
+    subset: Subset,
+    entries: Vec<(u128, Outcome)>,
+}
+
+impl Tablebase {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get_outcomes(&self) -> impl Iterator<Item = Outcome> + '_ {
+        self.entries
+            .iter()
+            .map(|(_, outcome)| *outcome)
+    }
+
+    pub fn probe(&self, game_state: &GameState) -> Option<Outcome> {
+        if !self.subset.contains(game_state) {
+            return None;
+        }
+
+        let key = get_position_key(game_state);
+
+        self.entries
+            .binary_search_by_key(&key, |(key, _)| *key)
+            .ok()
+            .map(|i| self.entries[i].1)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + self.entries.len() * ENTRY_BYTES);
+
+        bytes.extend_from_slice(TABLEBASE_MAGIC);
+        bytes.push(TABLEBASE_VERSION);
+        bytes.extend_from_slice(&self.subset.region.to_le_bytes());
+        bytes.push(self.subset.min_kings as u8);
+        bytes.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+
+        for (key, outcome) in &self.entries {
+            bytes.extend_from_slice(&key.to_le_bytes()[..KEY_BYTES]);
+            bytes.extend_from_slice(&outcome.encode().to_le_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_BYTES || &bytes[..8] != TABLEBASE_MAGIC {
+            return Err("not a tablebase file".to_string());
+        }
+
+        if bytes[8] != TABLEBASE_VERSION {
+            return Err(format!("unsupported tablebase version {}", bytes[8]));
+        }
+
+        let subset = Subset {
+            region: u64::from_le_bytes(bytes[9..17].try_into().unwrap()),
+            min_kings: bytes[17] as usize,
+        };
+        let count = u64::from_le_bytes(bytes[18..26].try_into().unwrap()) as usize;
+
+        let data = &bytes[HEADER_BYTES..];
+        if data.len() != count.saturating_mul(ENTRY_BYTES) {
+            return Err(format!(
+                "expected {count} entries, the file has {} bytes of data",
+                data.len()
+            ));
+        }
+
+        let entries: Vec<(u128, Outcome)> = data
+            .chunks_exact(ENTRY_BYTES)
+            .map(|entry| {
+                let mut key = [0; 16];
+                key[..KEY_BYTES].copy_from_slice(&entry[..KEY_BYTES]);
+                let outcome = u16::from_le_bytes([entry[KEY_BYTES], entry[KEY_BYTES + 1]]);
+
+                (u128::from_le_bytes(key), Outcome::decode(outcome))
+            })
+            .collect();
+
+        if !entries.is_sorted_by(|a, b| a.0 < b.0) {
+            return Err("the tablebase entries are not sorted".to_string());
+        }
+
+        Ok(Self { subset, entries })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|error| format!("could not read '{path}': {error}"))?;
+
+        Self::from_bytes(&bytes).map_err(|error| format!("{path}: {error}"))
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes())
+            .map_err(|error| format!("could not write '{path}': {error}"))
+    }
+}
+
+// the rank of the masks that do not belong to the subset
+const NO_RANK: u32 = u32::MAX;
+
+// The positions of a subset numbered from 0 to len(): the rank of the occupied
+// squares among the connected shapes of the region, then which of them are
+// white, which white and which black pieces are kings and the side to move.
+struct SubsetIndex {
+    shapes: ShapeIndex,
+    colourings: Vec<u16>,
+    colouring_ranks: Vec<u32>,
+    kingings: Vec<u8>,
+    kinging_ranks: Vec<u32>,
+}
+
+impl SubsetIndex {
+    fn new(subset: &Subset) -> Result<Self, String> {
+        let shapes = ShapeIndex::new(&grow_polyominoes(subset.region, false))?;
+
+        let (colourings, colouring_ranks) =
+            get_masks(2 * PIECES_PER_SIDE, |ones| ones == PIECES_PER_SIDE);
+        let (kingings, kinging_ranks) = get_masks(PIECES_PER_SIDE, |ones| ones >= subset.min_kings);
+
+        Ok(Self {
+            shapes,
+            colourings: colourings
+                .into_iter()
+                .map(|mask| mask as u16)
+                .collect(),
+            colouring_ranks,
+            kingings: kingings
+                .into_iter()
+                .map(|mask| mask as u8)
+                .collect(),
+            kinging_ranks,
+        })
+    }
+
+    fn len(&self) -> Option<u64> {
+        let kingings = self.kingings.len() as u64;
+
+        self.shapes
+            .len()
+            .checked_mul(self.colourings.len() as u64)?
+            .checked_mul(kingings * kingings * 2)
+    }
+
+    // None for positions outside of the subset
+    fn get_index(&self, game_state: &GameState) -> Option<usize> {
+        let board = &game_state.board;
+        let shape = self.shapes.get_rank(board.piece_bits)?;
+        let white_bits = to_bits(board.white_kings) | to_bits(board.white_pawns);
+        let king_bits = to_bits(board.white_kings) | to_bits(board.black_kings);
+
+        let (mut colouring, mut white_kinging, mut black_kinging) = (0, 0, 0);
+        let (mut whites, mut blacks) = (0, 0);
+        let mut bits = board.piece_bits;
+        let mut n = 0;
+
+        while bits != 0 {
+            let square = bits & bits.wrapping_neg();
+            let is_king = king_bits & square != 0;
+
+            if white_bits & square != 0 {
+                colouring |= 1 << n;
+                white_kinging |= (is_king as usize) << whites;
+                whites += 1;
+            } else {
+                black_kinging |= (is_king as usize) << blacks;
+                blacks += 1;
+            }
+
+            bits &= bits - 1;
+            n += 1;
+        }
+
+        let ranks = [
+            self.colouring_ranks[colouring],
+            self.kinging_ranks[white_kinging],
+            self.kinging_ranks[black_kinging],
+        ];
+        if ranks.contains(&NO_RANK) {
+            return None;
+        }
+
+        let kingings = self.kingings.len();
+        let index = ((shape as usize * self.colourings.len() + ranks[0] as usize) * kingings
+            + ranks[1] as usize)
+            * kingings
+            + ranks[2] as usize;
+
+        Some(2 * index + !game_state.is_active_player_white as usize)
+    }
+
+    fn get_position(&self, mut index: usize) -> GameState {
+        let is_active_player_white = index.is_multiple_of(2);
+        index /= 2;
+        let black_kinging = self.kingings[index % self.kingings.len()];
+        index /= self.kingings.len();
+        let white_kinging = self.kingings[index % self.kingings.len()];
+        index /= self.kingings.len();
+        let colouring = self.colourings[index % self.colourings.len()];
+        index /= self.colourings.len();
+
+        let mut board = Board::empty();
+        board.piece_bits = self
+            .shapes
+            .get_shape(index as u64)
+            .unwrap();
+
+        let (mut whites, mut blacks) = (0, 0);
+        for (n, square) in get_bit_indices(board.piece_bits)
+            .into_iter()
+            .enumerate()
+        {
+            let list = if colouring & 1 << n != 0 {
+                whites += 1;
+                match white_kinging & 1 << (whites - 1) != 0 {
+                    true => &mut board.white_kings,
+                    false => &mut board.white_pawns,
+                }
+            } else {
+                blacks += 1;
+                match black_kinging & 1 << (blacks - 1) != 0 {
+                    true => &mut board.black_kings,
+                    false => &mut board.black_pawns,
+                }
+            };
+            list.push_front(square);
+        }
+
+        GameState {
+            board,
+            is_active_player_white,
+            move_count: 0,
+        }
+    }
+}
+
+// the masks of the given number of bits whose number of ones is accepted,
+// ascending, and the rank of every mask among them
+fn get_masks(bits: usize, accepts: impl Fn(usize) -> bool) -> (Vec<usize>, Vec<u32>) {
+    let masks: Vec<usize> = (0..1 << bits)
+        .filter(|mask: &usize| accepts(mask.count_ones() as usize))
+        .collect();
+
+    let mut ranks = vec![NO_RANK; 1 << bits];
+    for (rank, mask) in masks.iter().enumerate() {
+        ranks[*mask] = rank as u32;
+    }
+
+    (masks, ranks)
+}
+
+fn get_final_outcome(game_state: &GameState, result: GameResult) -> Outcome {
+    let is_won = match result {
+        GameResult::WhiteWins => game_state.is_active_player_white,
+        GameResult::BlackWins => !game_state.is_active_player_white,
+    };
+
+    match is_won {
+        true => Outcome::Win(0),
+        false => Outcome::Loss(0),
+    }
+}
+
+fn get_children(game_state: &GameState) -> impl Iterator<Item = GameState> + '_ {
+    game_state
+        .generate_moves()
+        .into_iter()
+        .map(|mov| {
+            let mut child = game_state.clone();
+            child.make_move(mov);
+            child
+        })
+}
+
+// A move that leaves the subset either ends the game or leads to a position
+// of a tablebase solved before, otherwise its value is unknown.
+fn get_exit_outcome(child: &GameState, previous: &[Tablebase]) -> Option<Outcome> {
+    match child.get_result() {
+        Some(result) => Some(get_final_outcome(child, result)),
+        None => previous
+            .iter()
+            .find_map(|tablebase| tablebase.probe(child)),
+    }
+}
+
+// The moves of a position that leave the subset, summed up once before the
+// analysis because their values do not change.
+#[derive(Clone, Copy, Default)]
+struct Exits {
+    // the fewest plies to win with one of them
+    win_plies: Option<u16>,
+    // the most plies to lose with them, None when one of them does not lose
+    loss_plies: Option<u16>,
+    is_unknown: bool,
+}
+
+fn get_exits(game_state: &GameState, index: &SubsetIndex, previous: &[Tablebase]) -> Exits {
+    let mut exits = Exits {
+        loss_plies: Some(0),
+        ..Exits::default()
+    };
+
+    for child in get_children(game_state).filter(|child| index.get_index(child).is_none()) {
+        match get_exit_outcome(&child, previous) {
+            Some(Outcome::Loss(plies)) => {
+                exits.win_plies = Some(
+                    exits
+                        .win_plies
+                        .map_or(plies + 1, |p| p.min(plies + 1)),
+                );
+            }
+            Some(Outcome::Win(plies)) => {
+                exits.loss_plies = exits
+                    .loss_plies
+                    .map(|p| p.max(plies + 1));
+            }
+            outcome => {
+                exits.loss_plies = None;
+                exits.is_unknown |= outcome.is_none();
+            }
+        }
+    }
+
+    exits
+}
+
+// Solves every position of the subset. Round n of the retrograde analysis
+// finds the positions that end in n plies: a position is won if one move
+// leads to a position lost in n - 1 plies and lost if all moves lead to
+// positions won in fewer plies, so the winner takes the shortest and the
+// loser the longest way. Positions that stay unresolved are drawn, unless
+// they can reach a move out of the subset whose value is unknown. Those are
+// left out.
+pub fn solve(
+    subset: &Subset,
+    previous: &[Tablebase],
+    max_positions: usize,
+) -> Result<Tablebase, String> {
+    let index = SubsetIndex::new(subset)?;
+    let len = index
+        .len()
+        .filter(|len| *len <= max_positions as u64)
+        .ok_or(format!(
+            "the subset has more than {max_positions} positions"
+        ))? as usize;
+
+    let mut outcomes = vec![None; len];
+    let mut exits = vec![Exits::default(); len];
+
+    for i in 0..len {
+        let game_state = index.get_position(i);
+
+        match game_state.get_result() {
+            Some(result) => outcomes[i] = Some(get_final_outcome(&game_state, result)),
+            None => exits[i] = get_exits(&game_state, &index, previous),
+        }
+    }
+
+    for plies in 1.. {
+        let mut is_changed = false;
+        // moves out of the subset that end the game in more plies
+        let mut is_waiting = false;
+
+        for i in 0..len {
+            if outcomes[i].is_some() {
+                continue;
+            }
+
+            let mut is_won = exits[i].win_plies == Some(plies);
+            let mut is_lost = exits[i]
+                .loss_plies
+                .is_some_and(|p| p <= plies);
+            is_waiting |= exits[i].win_plies > Some(plies) || exits[i].loss_plies > Some(plies);
+
+            for child in get_children(&index.get_position(i)) {
+                let Some(j) = index.get_index(&child) else {
+                    continue;
+                };
+
+                match outcomes[j] {
+                    Some(Outcome::Loss(child_plies)) if child_plies + 1 == plies => is_won = true,
+                    Some(Outcome::Win(child_plies)) if child_plies < plies => {}
+                    _ => is_lost = false,
+                }
+            }
+
+            if is_won {
+                outcomes[i] = Some(Outcome::Win(plies));
+            } else if is_lost {
+                outcomes[i] = Some(Outcome::Loss(plies));
+            }
+
+            is_changed |= outcomes[i].is_some();
+        }
+
+        if !is_changed && !is_waiting {
+            break;
+        }
+    }
+
+    // whoever can reach an unknown value might be able to win there
+    let mut is_unknown: Vec<bool> = (0..len)
+        .map(|i| outcomes[i].is_none() && exits[i].is_unknown)
+        .collect();
+    let mut is_changed = true;
+
+    while is_changed {
+        is_changed = false;
+
+        for i in 0..len {
+            if outcomes[i].is_some() || is_unknown[i] {
+                continue;
+            }
+
+            is_unknown[i] = get_children(&index.get_position(i))
+                .filter_map(|child| index.get_index(&child))
+                .any(|j| is_unknown[j]);
+            is_changed |= is_unknown[i];
+        }
+    }
+
+    let mut entries: Vec<(u128, Outcome)> = (0..len)
+        .filter(|&i| !is_unknown[i])
+        .map(|i| {
+            let key = get_position_key(&index.get_position(i));
+            (key, outcomes[i].unwrap_or(Outcome::Draw))
+        })
+        .collect();
+    entries.sort_by_key(|(key, _)| *key);
+
+    Ok(Tablebase {
+        subset: *subset,
+        entries,
+    })
+}
+
+static TABLEBASES: OnceLock<Vec<Tablebase>> = OnceLock::new();
+
+// the tablebases can only be loaded once, at startup
+pub fn set_tablebases(tablebases: Vec<Tablebase>) {
+    let _ = TABLEBASES.set(tablebases);
+}
+
+pub fn get_tablebases() -> &'static [Tablebase] {
+    TABLEBASES
+        .get()
+        .map_or(&[], Vec::as_slice)
+}
+
+pub fn probe_tablebase(game_state: &GameState) -> Option<Outcome> {
+    get_tablebases()
+        .iter()
+        .find_map(|tablebase| tablebase.probe(game_state))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::search::think_with;
+    use crate::search::SearchLimits;
+
+    // the 4x3 block with at least 5 kings on each side, one shape
+    fn get_small_subset() -> Subset {
+        Subset {
+            region: parse_region("a1:d3").unwrap(),
+            min_kings: 5,
+        }
+    }
+
+    // solved once for all tests
+    fn get_small_tablebase() -> &'static Tablebase {
+        static TABLEBASE: OnceLock<Tablebase> = OnceLock::new();
+
+        TABLEBASE
+            .get_or_init(|| solve(&get_small_subset(), &[get_exit_tablebase()], 100_000).unwrap())
+    }
+
+    // White's pawn on a1 cannot jump, so all moves leave the block
+    const EXIT_ROOT: &str = "PKKK3/KKkk3/kkkp3/7/7/7 w 0";
+
+    // the value of one of those moves, as if it was solved before
+    fn get_exit_tablebase() -> Tablebase {
+        let root = GameState::from_notation(EXIT_ROOT).unwrap();
+        let exit = get_children(&root)
+            .find(|child| child.get_result().is_none())
+            .unwrap();
+
+        Tablebase {
+            subset: Subset::default(),
+            entries: vec![(get_position_key(&exit), Outcome::Loss(4))],
+        }
+    }
+
+    fn get_child_outcome(tablebase: &Tablebase, child: &GameState) -> Option<Outcome> {
+        if tablebase.subset.contains(child) {
+            return tablebase.probe(child);
+        }
+
+        // finished games outside of the subset are not stored
+        get_exit_outcome(child, &[get_exit_tablebase()])
+    }
+
+    #[test]
+    fn test_position_key() {
+        let game_state = GameState::new();
+        let key = get_position_key(&game_state);
+
+        let mut other_side = game_state.clone();
+        other_side.is_active_player_white = false;
+        assert_ne!(get_position_key(&other_side), key);
+
+        let mut later = game_state.clone();
+        later.move_count += 2;
+        assert_eq!(get_position_key(&later), key);
+
+        let mut keys: Vec<u128> = game_state
+            .generate_moves()
+            .into_iter()
+            .map(|mov| {
+                let mut next_game_state = game_state.clone();
+                next_game_state.make_move(mov);
+                get_position_key(&next_game_state)
+            })
+            .collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), game_state.generate_moves().len());
+        assert!(keys
+            .iter()
+            .all(|key| *key < 1 << (KEY_BYTES * 8)));
+    }
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(parse_region("a1:g6"), Ok(ALL_SQUARES));
+        assert_eq!(parse_region("b2:a1"), Ok(0b110000011));
+        assert!(parse_region("a1").is_err());
+        assert!(parse_region("a1:h9").is_err());
+    }
+
+    #[test]
+    fn test_subset_index() {
+        let subset = get_small_subset();
+        let index = SubsetIndex::new(&subset).unwrap();
+        let len = index.len().unwrap() as usize;
+        assert_eq!(len, 924 * 7 * 7 * 2);
+
+        for i in (0..len).step_by(97) {
+            let game_state = index.get_position(i);
+            assert!(subset.contains(&game_state));
+            assert!(game_state.board.validate().is_empty());
+            assert_eq!(index.get_index(&game_state), Some(i));
+        }
+
+        assert_eq!(index.get_index(&GameState::new()), None);
+        let few_kings = GameState::from_notation("KKKK3/Pkkk3/Ppkp3/7/7/7 w 0").unwrap();
+        assert_eq!(index.get_index(&few_kings), None);
+    }
+
+    #[test]
+    fn test_solve_small_subset() {
+        let tablebase = get_small_tablebase();
+        let index = SubsetIndex::new(&tablebase.subset).unwrap();
+        assert!(tablebase.len() > 10_000);
+
+        // every solved position agrees with the values of its moves
+        for i in (0..index.len().unwrap() as usize).step_by(11) {
+            let game_state = index.get_position(i);
+            let Some(outcome) = tablebase.probe(&game_state) else {
+                continue;
+            };
+
+            let child_outcomes: Vec<Option<Outcome>> = get_children(&game_state)
+                .map(|child| get_child_outcome(tablebase, &child))
+                .collect();
+
+            match outcome {
+                Outcome::Win(0) => assert!(child_outcomes.is_empty()),
+                Outcome::Win(plies) => {
+                    assert!(child_outcomes.contains(&Some(Outcome::Loss(plies - 1))));
+                    assert!(!child_outcomes
+                        .iter()
+                        .any(|o| matches!(o, Some(Outcome::Loss(p)) if *p + 1 < plies)));
+                }
+                Outcome::Loss(plies) => {
+                    assert_eq!(plies == 0, child_outcomes.is_empty());
+                    assert!(child_outcomes
+                        .iter()
+                        .all(|o| matches!(o, Some(Outcome::Win(p)) if *p < plies)));
+                }
+                Outcome::Draw => {
+                    assert!(child_outcomes
+                        .iter()
+                        .all(|o| matches!(o, Some(Outcome::Win(_)) | Some(Outcome::Draw))));
+                }
+            }
+        }
+
+        assert!(solve(&get_small_subset(), &[], 1000).is_err());
+    }
+
+    #[test]
+    fn test_exits_use_previous_tablebases() {
+        let root = GameState::from_notation(EXIT_ROOT).unwrap();
+        assert!(get_small_subset().contains(&root));
+        assert!(get_children(&root).all(|child| !get_small_subset().contains(&child)));
+
+        assert_eq!(get_small_tablebase().probe(&root), Some(Outcome::Win(5)));
+    }
+
+    #[test]
+    fn test_tablebase_matches_search() {
+        let tablebase = get_small_tablebase();
+        let index = SubsetIndex::new(&tablebase.subset).unwrap();
+
+        // short wins and losses have to be found by a plain search as well
+        let solved: Vec<(GameState, Outcome)> = (0..index.len().unwrap() as usize)
+            .map(|i| index.get_position(i))
+            .filter_map(|game_state| match tablebase.probe(&game_state) {
+                Some(outcome @ (Outcome::Win(1..=4) | Outcome::Loss(1..=4))) => {
+                    Some((game_state, outcome))
+                }
+                _ => None,
+            })
+            .take(20)
+            .collect();
+        assert!(!solved.is_empty());
+
+        for (game_state, outcome) in solved {
+            let result = think_with(&game_state, &SearchLimits::new(5, u32::MAX), &mut |_| {});
+            assert_eq!(result.score, outcome.get_score(game_state.move_count));
+        }
+    }
+
+    #[test]
+    fn test_tablebase_round_trip() {
+        let tablebase = get_small_tablebase();
+        let game_state = SubsetIndex::new(&tablebase.subset)
+            .unwrap()
+            .get_position(0);
+
+        let bytes = tablebase.to_bytes();
+        assert_eq!(bytes.len(), HEADER_BYTES + tablebase.len() * ENTRY_BYTES);
+
+        let parsed = Tablebase::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.subset, get_small_subset());
+        assert_eq!(parsed.len(), tablebase.len());
+        assert_eq!(parsed.probe(&game_state), tablebase.probe(&game_state));
+        assert!(parsed
+            .get_outcomes()
+            .eq(tablebase.get_outcomes()));
+
+        assert!(Tablebase::from_bytes(b"uisge-tb").is_err());
+        assert!(Tablebase::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert_eq!(parsed.probe(&GameState::new()), None);
+    }
+
+    #[test]
+    fn test_outcome_encoding() {
+        for outcome in [
+            Outcome::Draw,
+            Outcome::Win(0),
+            Outcome::Loss(0),
+            Outcome::Win(7),
+            Outcome::Loss(12),
+        ] {
+            assert_eq!(Outcome::decode(outcome.encode()), outcome);
+        }
+
+        assert!(Outcome::Win(3).get_score(10) > Outcome::Win(5).get_score(10));
+        assert!(Outcome::Loss(3).get_score(10) < Outcome::Loss(5).get_score(10));
+    }
+}
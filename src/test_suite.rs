@@ -0,0 +1,239 @@
+use std::fs;
+
+use crate::move_gen::Move;
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::state::GameState;
+
+// A test suite contains one puzzle per line, similar to the EPD format known
+// from chess: a position notation followed by ';'-separated operations
+//   id "<name>"     name of the puzzle
+//   bm <move> ...   the best move(s), one of them has to be found
+//   am <move> ...   moves to avoid, none of them may be played
+// Empty lines and lines starting with '#' are ignored, e.g.
+//   7/KKKK3/1KPpp2/1pppp2/7/7 w 0; id "sixth-king-01"; bm c3c1 c3a3 c3c5
+pub struct TestPosition {
+    pub id: String,
+    pub game_state: GameState,
+    pub best_moves: Vec<Move>,
+    pub avoid_moves: Vec<Move>,
+}
+
+pub struct TestOutcome {
+    pub id: String,
+    pub best_move: Option<Move>,
+    pub is_solved: bool,
+    // time of the iteration from which on the engine stuck to a solving move
+    pub solution_time: Option<u128>,
+    pub depth: u32,
+}
+
+impl TestPosition {
+    pub fn is_solved_by(&self, mov: Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&mov))
+            && !self.avoid_moves.contains(&mov)
+    }
+}
+
+pub fn read_test_suite(path: &str) -> Result<Vec<TestPosition>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|error| format!("could not read '{path}': {error}"))?;
+
+    parse_test_suite(&contents).map_err(|error| format!("{path}:{error}"))
+}
+
+pub fn parse_test_suite(contents: &str) -> Result<Vec<TestPosition>, String> {
+    let mut positions = Vec::new();
+
+    for (line_index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let position = parse_test_position(line, line_index + 1)
+            .map_err(|error| format!("{}: {error}", line_index + 1))?;
+
+        positions.push(position);
+    }
+
+    Ok(positions)
+}
+
+fn parse_test_position(line: &str, line_number: usize) -> Result<TestPosition, String> {
+    let mut fields = line.split(';');
+
+    let game_state = GameState::from_notation(fields.next().unwrap_or_default())?;
+    let legal_moves = game_state.generate_moves();
+
+    let mut position = TestPosition {
+        id: format!("line {line_number}"),
+        game_state,
+        best_moves: Vec::new(),
+        avoid_moves: Vec::new(),
+    };
+
+    for field in fields {
+        let field = field.trim();
+        let (operation, operands) = field
+            .split_once(' ')
+            .unwrap_or((field, ""));
+
+        match operation {
+            "" => {}
+            "id" => {
+                position.id = operands
+                    .trim()
+                    .trim_matches('"')
+                    .to_string()
+            }
+            "bm" | "am" => {
+                let mut moves = Vec::new();
+
+                for operand in operands.split_whitespace() {
+                    let mov = operand.parse::<Move>()?;
+                    if !legal_moves.contains(&mov) {
+                        return Err(format!("illegal move '{operand}'"));
+                    }
+                    moves.push(mov);
+                }
+
+                if operation == "bm" {
+                    position.best_moves = moves;
+                } else {
+                    position.avoid_moves = moves;
+                }
+            }
+            _ => return Err(format!("unknown operation '{operation}'")),
+        }
+    }
+
+    if position.best_moves.is_empty() && position.avoid_moves.is_empty() {
+        return Err("either 'bm' or 'am' is required".to_string());
+    }
+
+    Ok(position)
+}
+
+pub fn run_test_suite(positions: &[TestPosition], limits: &SearchLimits) -> Vec<TestOutcome> {
+    let mut outcomes = Vec::new();
+
+    for position in positions {
+        let mut solution_time = None;
+
+        let result = think_with(
+            &position.game_state,
+            limits,
+            &mut |info| match info.pv.first() {
+                Some(mov) if position.is_solved_by(*mov) => {
+                    solution_time = solution_time.or(Some(info.time));
+                }
+                _ => solution_time = None,
+            },
+        );
+
+        let is_solved = result
+            .best_move
+            .is_some_and(|mov| position.is_solved_by(mov));
+
+        outcomes.push(TestOutcome {
+            id: position.id.clone(),
+            best_move: result.best_move,
+            is_solved,
+            solution_time: if is_solved { solution_time } else { None },
+            depth: result.depth,
+        });
+    }
+
+    outcomes
+}
+
+pub fn format_test_report(outcomes: &[TestOutcome]) -> String {
+    let mut text = String::new();
+
+    text.push_str("id                   |   result |  best | depth | solved after\n");
+    text.push_str("---------------------|----------|-------|-------|-------------\n");
+
+    for outcome in outcomes {
+        let result = if outcome.is_solved {
+            "solved"
+        } else {
+            "unsolved"
+        };
+        let best_move = match outcome.best_move {
+            Some(mov) => mov.to_string(),
+            None => "none".to_string(),
+        };
+        let solution_time = match outcome.solution_time {
+            Some(time) => format!("{:.3} s", time as f32 / 1000f32),
+            None => "-".to_string(),
+        };
+
+        text.push_str(&format!(
+            "{:<20} | {result:>8} | {best_move:>5} | {:>5} | {solution_time:>12}\n",
+            outcome.id, outcome.depth,
+        ));
+    }
+
+    let solved = outcomes
+        .iter()
+        .filter(|o| o.is_solved)
+        .count();
+    text.push_str(&format!("\nscore: {solved}/{}\n", outcomes.len()));
+
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TACTICS_SUITE: &str = include_str!("../suites/tactics.suite");
+
+    #[test]
+    fn test_parse_test_suite() {
+        let positions = parse_test_suite(
+            "\
+            # comment\n\
+            7/KKKK3/1KPpp2/1pppp2/7/7 w 0; id \"sixth king\"; bm c3c1 c3a3\n\
+            7/1K1P3/1PPKP2/2pppp1/4pk1/7 w 10; am b2b4\n",
+        )
+        .unwrap();
+
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].id, "sixth king");
+        assert_eq!(
+            positions[0].best_moves,
+            vec![Move::new(16, 2), Move::new(16, 14)]
+        );
+        assert_eq!(positions[1].id, "line 3");
+        assert_eq!(positions[1].avoid_moves, vec![Move::new(8, 22)]);
+
+        assert!(positions[0].is_solved_by(Move::new(16, 2)));
+        assert!(!positions[0].is_solved_by(Move::new(16, 30)));
+        assert!(!positions[1].is_solved_by(Move::new(8, 22)));
+        assert!(positions[1].is_solved_by(Move::new(8, 9)));
+
+        assert!(parse_test_suite("7/KKKK3/1KPpp2/1pppp2/7/7 w 0; id \"x\"").is_err());
+        assert!(parse_test_suite("7/KKKK3/1KPpp2/1pppp2/7/7 w 0; bm a1a3").is_err());
+        assert!(parse_test_suite("7/KKKK3/1KPpp2/1pppp2/7/7 w 0; xx c3c1").is_err());
+    }
+
+    #[test]
+    fn test_tactics_suite() {
+        let positions = parse_test_suite(TACTICS_SUITE).unwrap();
+        let outcomes = run_test_suite(&positions, &SearchLimits::new(5, u32::MAX));
+
+        let unsolved: Vec<&str> = outcomes
+            .iter()
+            .filter(|o| !o.is_solved)
+            .map(|o| o.id.as_str())
+            .collect();
+
+        assert_eq!(outcomes.len(), 12);
+        assert_eq!(unsolved, Vec::<&str>::new());
+        assert!(outcomes
+            .iter()
+            .all(|o| o.solution_time.is_some()));
+    }
+}
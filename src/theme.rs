@@ -0,0 +1,308 @@
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+use crate::state::Piece;
+
+const RESET: &str = "\x1b[0m";
+
+// ANSI SGR codes, foregrounds are 30-37/90-97 and backgrounds 40-47/100-107
+#[derive(Debug)]
+pub struct Palette {
+    pub frame: u8,
+    pub white: u8,
+    pub black: u8,
+    pub board: u8,
+    pub frozen: u8,
+    pub last_move: u8,
+    pub destination: u8,
+    pub selected: u8,
+    pub cursor: u8,
+    pub success: u8,
+    pub error: u8,
+    pub highlight: u8,
+}
+
+#[derive(Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    // themes without a palette print no escape sequences at all
+    pub palette: Option<Palette>,
+    pub white_pawn: char,
+    pub white_king: char,
+    pub black_pawn: char,
+    pub black_king: char,
+    pub empty: char,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Highlight {
+    None,
+    Frozen,
+    LastMove,
+    Destination,
+    Selected,
+    Cursor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    // colours only on terminals and only if NO_COLOR is not set
+    Auto,
+    Always,
+    Never,
+}
+
+pub const CLASSIC: Theme = Theme {
+    name: "classic",
+    palette: Some(Palette {
+        frame: 30,
+        white: 37,
+        black: 30,
+        board: 45,
+        frozen: 41,
+        last_move: 44,
+        destination: 43,
+        selected: 42,
+        cursor: 46,
+        success: 32,
+        error: 31,
+        highlight: 33,
+    }),
+    white_pawn: 'o',
+    white_king: 'W',
+    black_pawn: 'o',
+    black_king: 'W',
+    empty: '-',
+};
+
+pub const OCEAN: Theme = Theme {
+    name: "ocean",
+    palette: Some(Palette {
+        frame: 30,
+        white: 97,
+        black: 30,
+        board: 44,
+        frozen: 101,
+        last_move: 106,
+        destination: 103,
+        selected: 102,
+        cursor: 105,
+        success: 92,
+        error: 91,
+        highlight: 96,
+    }),
+    white_pawn: 'o',
+    white_king: 'W',
+    black_pawn: 'o',
+    black_king: 'W',
+    empty: '-',
+};
+
+// readable on terminals with a light background
+pub const LIGHT: Theme = Theme {
+    name: "light",
+    palette: Some(Palette {
+        frame: 30,
+        white: 34,
+        black: 31,
+        board: 47,
+        frozen: 101,
+        last_move: 106,
+        destination: 103,
+        selected: 102,
+        cursor: 105,
+        success: 32,
+        error: 31,
+        highlight: 35,
+    }),
+    white_pawn: 'o',
+    white_king: 'O',
+    black_pawn: 'x',
+    black_king: 'X',
+    empty: '.',
+};
+
+pub const ASCII: Theme = Theme {
+    name: "ascii",
+    palette: None,
+    white_pawn: 'o',
+    white_king: 'O',
+    black_pawn: 'x',
+    black_king: 'X',
+    empty: '.',
+};
+
+pub const THEMES: [&Theme; 4] = [&CLASSIC, &OCEAN, &LIGHT, &ASCII];
+
+static THEME: OnceLock<&'static Theme> = OnceLock::new();
+
+// the theme can only be chosen once, at startup
+pub fn set_theme(theme: &'static Theme) {
+    let _ = THEME.set(theme);
+}
+
+pub fn get_theme() -> &'static Theme {
+    THEME.get().copied().unwrap_or(&CLASSIC)
+}
+
+pub fn find_theme(name: &str) -> Option<&'static Theme> {
+    THEMES
+        .iter()
+        .find(|theme| theme.name == name)
+        .copied()
+}
+
+// falls back to plain ASCII when colours are not wanted, see https://no-color.org
+pub fn resolve_theme(theme: &'static Theme, color_mode: ColorMode) -> &'static Theme {
+    let is_no_color = std::env::var("NO_COLOR").is_ok_and(|value| !value.is_empty());
+
+    match color_mode {
+        ColorMode::Always => theme,
+        ColorMode::Never => &ASCII,
+        ColorMode::Auto if is_no_color || !std::io::stdout().is_terminal() => &ASCII,
+        ColorMode::Auto => theme,
+    }
+}
+
+impl Palette {
+    fn get_background(&self, highlight: Highlight) -> u8 {
+        match highlight {
+            Highlight::None => self.board,
+            Highlight::Frozen => self.frozen,
+            Highlight::LastMove => self.last_move,
+            Highlight::Destination => self.destination,
+            Highlight::Selected => self.selected,
+            Highlight::Cursor => self.cursor,
+        }
+    }
+}
+
+impl Theme {
+    pub fn success(&self, text: &str) -> String {
+        self.paint(text, |p| p.success)
+    }
+
+    pub fn error(&self, text: &str) -> String {
+        self.paint(text, |p| p.error)
+    }
+
+    pub fn highlight(&self, text: &str) -> String {
+        self.paint(text, |p| p.highlight)
+    }
+
+    // text on the board background, e.g. coordinates
+    pub fn frame(&self, text: &str) -> String {
+        match &self.palette {
+            Some(p) => format!("\x1b[{};{}m{text}{RESET}", p.frame, p.board),
+            None => text.to_string(),
+        }
+    }
+
+    // text on the board background in the colour of a side
+    pub fn side(&self, is_white: bool, text: &str) -> String {
+        match &self.palette {
+            Some(p) => format!(
+                "\x1b[{};{}m{text}{RESET}",
+                if is_white { p.white } else { p.black },
+                p.board
+            ),
+            None => text.to_string(),
+        }
+    }
+
+    pub fn piece_symbol(&self, piece: Option<Piece>) -> char {
+        match piece {
+            Some(Piece::WhitePawn) => self.white_pawn,
+            Some(Piece::WhiteKing) => self.white_king,
+            Some(Piece::BlackPawn) => self.black_pawn,
+            Some(Piece::BlackKing) => self.black_king,
+            None => self.empty,
+        }
+    }
+
+    // Every square is two characters wide. Without colours the highlight is
+    // shown by a marker in place of the space following the symbol.
+    pub fn square(&self, piece: Option<Piece>, highlight: Highlight) -> String {
+        let symbol = self.piece_symbol(piece);
+
+        let Some(p) = &self.palette else {
+            return format!("{symbol}{}", highlight_marker(highlight));
+        };
+
+        let foreground = match piece {
+            Some(piece) if piece.is_white() => p.white,
+            Some(_) => p.black,
+            None => p.frame,
+        };
+        format!(
+            "\x1b[{foreground};{}m{symbol}\x1b[{};{}m {RESET}",
+            p.get_background(highlight),
+            p.frame,
+            p.board
+        )
+    }
+
+    // a sample of a highlight to explain it in a legend
+    pub fn swatch(&self, highlight: Highlight, text: &str) -> String {
+        match &self.palette {
+            Some(p) => format!(
+                "\x1b[{};{}m {text} {RESET}",
+                p.frame,
+                p.get_background(highlight)
+            ),
+            None => format!("{} {text}", highlight_marker(highlight)),
+        }
+    }
+
+    fn paint(&self, text: &str, code: fn(&Palette) -> u8) -> String {
+        match &self.palette {
+            Some(p) => format!("\x1b[{}m{text}{RESET}", code(p)),
+            None => text.to_string(),
+        }
+    }
+}
+
+fn highlight_marker(highlight: Highlight) -> char {
+    match highlight {
+        Highlight::None => ' ',
+        Highlight::Frozen => '#',
+        Highlight::LastMove => '*',
+        Highlight::Destination => '+',
+        Highlight::Selected => '!',
+        Highlight::Cursor => '<',
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_theme() {
+        for theme in THEMES {
+            assert_eq!(find_theme(theme.name).unwrap().name, theme.name);
+        }
+        assert!(find_theme("neon").is_none());
+    }
+
+    #[test]
+    fn test_resolve_theme() {
+        assert_eq!(resolve_theme(&OCEAN, ColorMode::Always).name, "ocean");
+        assert_eq!(resolve_theme(&OCEAN, ColorMode::Never).name, "ascii");
+        assert_eq!(resolve_theme(&ASCII, ColorMode::Always).name, "ascii");
+    }
+
+    #[test]
+    fn test_ascii_theme() {
+        assert!(ASCII.palette.is_none());
+        assert_eq!(ASCII.success("ok"), "ok");
+        assert_eq!(ASCII.square(Some(Piece::WhiteKing), Highlight::None), "O ");
+        assert_eq!(
+            ASCII.square(Some(Piece::BlackPawn), Highlight::LastMove),
+            "x*"
+        );
+        assert_eq!(ASCII.square(None, Highlight::Destination), ".+");
+
+        assert_eq!(CLASSIC.error("no"), "\x1b[31mno\x1b[0m");
+    }
+}
@@ -0,0 +1,668 @@
+use std::io::stdin;
+use std::io::stdout;
+use std::io::Read;
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+use crate::book::probe_book;
+use crate::clock::Clock;
+use crate::coach::format_review_warning;
+use crate::coach::get_hint;
+use crate::coach::review_move;
+use crate::coach::DEFAULT_COACH_THRESHOLD;
+use crate::constants::BoardIndex;
+use crate::difficulty::choose_weakened_move;
+use crate::game_record::GameRecord;
+use crate::geometry::get_geometry;
+use crate::gui::get_search_limits;
+use crate::gui::is_computers_turn;
+use crate::gui::Configuration;
+use crate::move_gen::Move;
+use crate::ponder::Ponder;
+use crate::render::render_board;
+use crate::render::render_legend;
+use crate::render::Overlay;
+use crate::render::View;
+use crate::rng::Rng;
+use crate::search::think_with;
+use crate::search::SearchLimits;
+use crate::search::SearchLine;
+use crate::search::SearchResult;
+use crate::state::GameState;
+use crate::theme::get_theme;
+
+const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+const PANEL_LINES: usize = 14;
+const HISTORY_WIDTH: usize = 18;
+const ENGINE_WIDTH: usize = 48;
+
+// Puts the terminal into raw mode on the alternate screen and restores
+// the previous state when dropped, also when unwinding from a panic.
+struct RawTerminal {
+    saved_settings: String,
+}
+
+impl RawTerminal {
+    // with ticks reading a key gives up after half a second so that clocks can be redrawn
+    fn enter(has_ticks: bool) -> Result<Self, String> {
+        let saved_settings = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        if has_ticks {
+            stty(&["min", "0", "time", "5"])?;
+        }
+
+        print!("{ENTER_ALTERNATE_SCREEN}");
+        stdout().flush().unwrap();
+
+        Ok(Self {
+            saved_settings: saved_settings.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("{LEAVE_ALTERNATE_SCREEN}");
+        stdout().flush().unwrap();
+
+        let _ = stty(&[&self.saved_settings]);
+    }
+}
+
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|error| format!("could not run stty: {error}"))?;
+
+    if !output.status.success() {
+        return Err("stty failed, is stdin a terminal?".to_string());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[derive(Debug, PartialEq)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+    Char(char),
+    // no key was pressed for a while
+    Tick,
+}
+
+fn read_key() -> Option<Key> {
+    let mut stdin = stdin().lock();
+    let mut byte = [0u8];
+
+    if stdin.read(&mut byte).ok()? == 0 {
+        return Some(Key::Tick);
+    }
+
+    let key = match byte[0] {
+        b'\r' | b'\n' | b' ' => Key::Enter,
+        0x7f | 0x08 => Key::Escape,
+        0x03 => Key::Char('q'),
+        0x1b => {
+            let mut sequence = [0u8; 2];
+            if stdin.read(&mut sequence[..1]).ok()? == 0 || sequence[0] != b'[' {
+                return Some(Key::Escape);
+            }
+            if stdin.read(&mut sequence[1..]).ok()? == 0 {
+                return Some(Key::Escape);
+            }
+
+            match sequence[1] {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                b'C' => Key::Right,
+                b'D' => Key::Left,
+                _ => Key::Escape,
+            }
+        }
+        b => Key::Char(b as char),
+    };
+
+    Some(key)
+}
+
+struct Screen {
+    cursor: BoardIndex,
+    selected: Option<BoardIndex>,
+    history: Vec<Move>,
+    first_move_count: u32,
+    is_first_player_white: bool,
+    engine_lines: Vec<String>,
+    message: String,
+    view: View,
+    clock: Option<Clock>,
+    coach_threshold: Option<i32>,
+}
+
+// the moves and the result are added to the record
+pub fn run_tui_game(
+    game_state: &mut GameState,
+    config: &Configuration,
+    record: &mut GameRecord,
+    rng: &mut Rng,
+) {
+    let terminal = match RawTerminal::enter(config.time_control.is_some()) {
+        Ok(terminal) => terminal,
+        Err(error) => {
+            eprintln!("{error}, use '--ui line' instead");
+            return;
+        }
+    };
+
+    let mut screen = Screen {
+        cursor: game_state
+            .get_active_pieces()
+            .1
+            .into_iter()
+            .next()
+            .unwrap_or(get_geometry().get_square_count() / 2),
+        selected: None,
+        history: Vec::new(),
+        first_move_count: game_state.move_count,
+        is_first_player_white: game_state.is_active_player_white,
+        engine_lines: Vec::new(),
+        message: String::new(),
+        view: config.view,
+        clock: config.time_control.map(Clock::new),
+        coach_threshold: config.coach_threshold,
+    };
+    let mut ponder: Option<Ponder> = None;
+
+    loop {
+        let result = game_state
+            .get_result()
+            .map(|r| r.to_string())
+            .or_else(|| {
+                screen
+                    .clock
+                    .as_ref()
+                    .and_then(Clock::get_result)
+                    .map(|r| format!("{r} on time"))
+            });
+
+        if let Some(result) = result {
+            screen.message = format!("{result}, press q to quit");
+            record.result = Some(result);
+            draw(game_state, &screen);
+
+            while !matches!(read_key(), Some(Key::Char('q')) | None) {}
+            break;
+        }
+
+        if let Some(clock) = &mut screen.clock {
+            clock.start_turn(game_state.is_active_player_white);
+        }
+
+        if is_computers_turn(game_state, &config.game_mode) {
+            screen.message = "computer is thinking...".to_string();
+            let limits = get_search_limits(game_state, config, screen.clock.as_ref());
+            let mut predicted_move = None;
+            let mov = if let Some(mov) = probe_book(game_state) {
+                screen.engine_lines = vec![format!("book move {mov}")];
+                mov
+            } else if config.level.is_weakened() {
+                draw(game_state, &screen);
+                choose_weakened_move(game_state, config.level, &limits, rng).unwrap()
+            } else {
+                let result = match ponder.take() {
+                    Some(ponder)
+                        if Some(ponder.get_predicted_move()) == screen.history.last().copied() =>
+                    {
+                        screen.message = "computer is thinking... (ponder hit)".to_string();
+                        draw(game_state, &screen);
+
+                        let result = ponder.hit(&limits);
+                        screen.engine_lines = result
+                            .lines
+                            .iter()
+                            .map(|line| format_engine_line(result.depth, 1, line))
+                            .collect();
+                        result
+                    }
+                    _ => search(game_state, &limits, &mut screen),
+                };
+                predicted_move = result.pv.get(1).copied();
+                result.best_move.unwrap()
+            };
+            screen.message = format!("computer moves {mov}");
+
+            make_move(game_state, &mut screen, mov);
+
+            if let Some(predicted_move) = predicted_move.filter(|_| config.ponder) {
+                ponder = Ponder::start(game_state, predicted_move, limits.max_depth);
+            }
+            continue;
+        }
+
+        draw(game_state, &screen);
+
+        let Some(key) = read_key() else {
+            break;
+        };
+
+        if key == Key::Tick {
+            continue;
+        }
+
+        screen.message.clear();
+
+        match key {
+            Key::Up => move_cursor(&mut screen, 0, -1),
+            Key::Down => move_cursor(&mut screen, 0, 1),
+            Key::Left => move_cursor(&mut screen, -1, 0),
+            Key::Right => move_cursor(&mut screen, 1, 0),
+            Key::Char('k') => move_cursor(&mut screen, 0, -1),
+            Key::Char('j') => move_cursor(&mut screen, 0, 1),
+            Key::Char('h') => move_cursor(&mut screen, -1, 0),
+            Key::Char('l') => move_cursor(&mut screen, 1, 0),
+            Key::Escape => screen.selected = None,
+            Key::Enter => {
+                if let Some(mov) = select_square(game_state, &mut screen) {
+                    // a wrong guess is stopped at once so that it does not slow down the next search
+                    if ponder
+                        .as_ref()
+                        .is_some_and(|p| p.get_predicted_move() != mov)
+                    {
+                        ponder = None;
+                    }
+
+                    let previous_game_state = game_state.clone();
+                    make_move(game_state, &mut screen, mov);
+                    screen.selected = None;
+                    screen.message = format!("you move {mov}");
+
+                    if let Some(threshold) = screen.coach_threshold {
+                        // the review is a search of its own, it must not run next to the ponder
+                        ponder = None;
+                        coach(&previous_game_state, mov, threshold, config, &mut screen);
+                    }
+                }
+            }
+            Key::Char('a') => {
+                screen.message = "analyzing...".to_string();
+                let mut limits = SearchLimits::new(config.max_search_depth, config.min_search_time);
+                limits.multi_pv = config.multi_pv;
+                ponder = None;
+                let mov = search(game_state, &limits, &mut screen)
+                    .best_move
+                    .unwrap();
+                screen.message = format!("best move {mov}");
+            }
+            Key::Char('?') => {
+                screen.message = "looking for a hint...".to_string();
+                draw(game_state, &screen);
+
+                ponder = None;
+                let hint = get_hint(game_state, config.max_search_depth);
+                if let Some(mov) = hint.best_move {
+                    screen.cursor = mov.get_from();
+                    screen.message = format!("hint: {mov}");
+                }
+            }
+            Key::Char('c') => {
+                screen.coach_threshold = match screen.coach_threshold {
+                    Some(_) => None,
+                    None => Some(DEFAULT_COACH_THRESHOLD),
+                };
+                screen.message = match screen.coach_threshold {
+                    Some(_) => "coach on".to_string(),
+                    None => "coach off".to_string(),
+                };
+            }
+            Key::Char('f') => screen.view.is_flipped = !screen.view.is_flipped,
+            Key::Char('i') => screen.view.show_indices = !screen.view.show_indices,
+            Key::Char('q') => break,
+            _ => screen.message = "unknown key".to_string(),
+        }
+    }
+
+    drop(terminal);
+
+    record.moves = screen.history;
+}
+
+fn make_move(game_state: &mut GameState, screen: &mut Screen, mov: Move) {
+    game_state.make_move(mov);
+    screen.history.push(mov);
+
+    if let Some(clock) = &mut screen.clock {
+        clock.finish_turn();
+    }
+}
+
+// warns in the message line when the move was much worse than the engine's choice
+fn coach(
+    previous_game_state: &GameState,
+    mov: Move,
+    threshold: i32,
+    config: &Configuration,
+    screen: &mut Screen,
+) {
+    let review = review_move(previous_game_state, mov, config.max_search_depth);
+
+    if review.is_blunder(threshold) {
+        screen.message = format!("coach: {}", format_review_warning(&review));
+    }
+}
+
+fn search(game_state: &GameState, limits: &SearchLimits, screen: &mut Screen) -> SearchResult {
+    screen.engine_lines.clear();
+    draw(game_state, screen);
+
+    think_with(game_state, limits, &mut |info| {
+        if info.lines.len() > 1 {
+            // the ranked lines of the latest iteration replace the previous ones
+            screen.engine_lines = info
+                .lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| format_engine_line(info.depth, i + 1, line))
+                .collect();
+        } else {
+            screen
+                .engine_lines
+                .push(format_engine_line(info.depth, 1, &info.lines[0]));
+        }
+        draw(game_state, screen);
+    })
+}
+
+// the direction is given as seen on the screen
+fn move_cursor(screen: &mut Screen, dx: i8, dy: i8) {
+    let geometry = get_geometry();
+    let (dx, dy) = screen.view.get_board_direction(dx, dy);
+    let x = (screen.cursor % geometry.width) as i8 + dx;
+    let y = (screen.cursor / geometry.width) as i8 + dy;
+
+    if (0..geometry.width as i8).contains(&x) && (0..geometry.height as i8).contains(&y) {
+        screen.cursor = geometry.get_index(x as BoardIndex, y as BoardIndex);
+    }
+}
+
+// Selects the piece under the cursor or, if a piece is already selected and
+// the cursor is on one of its destinations, returns the move to make.
+fn select_square(game_state: &GameState, screen: &mut Screen) -> Option<Move> {
+    let moves = game_state.generate_moves();
+
+    if let Some(from) = screen.selected {
+        let mov = Move::new(from, screen.cursor);
+        if moves.contains(&mov) {
+            return Some(mov);
+        }
+    }
+
+    if moves
+        .iter()
+        .any(|m| m.get_from() == screen.cursor)
+    {
+        screen.selected = Some(screen.cursor);
+    } else {
+        screen.selected = None;
+        screen.message = "no legal move for this square".to_string();
+    }
+
+    None
+}
+
+fn draw(game_state: &GameState, screen: &Screen) {
+    print!("{CLEAR_SCREEN}{}", render(game_state, screen));
+    stdout().flush().unwrap();
+}
+
+fn render(game_state: &GameState, screen: &Screen) -> String {
+    let board_lines = render_board(
+        &game_state.board,
+        &get_overlay(game_state, screen),
+        get_theme(),
+        &screen.view,
+    );
+    let board_width = screen.view.get_width();
+    let history_lines = render_history(screen);
+    let engine_lines = render_engine(screen);
+
+    let mut lines = vec![format!(
+        "   {:<board_width$}  {:<HISTORY_WIDTH$}  {}",
+        "Uisge", "Moves", "Engine"
+    )];
+
+    for i in 0..PANEL_LINES {
+        let board = board_lines
+            .get(i)
+            .cloned()
+            .unwrap_or(" ".repeat(board_width));
+        let history = history_lines
+            .get(i)
+            .map(String::as_str)
+            .unwrap_or_default();
+        let engine = engine_lines
+            .get(i)
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        lines.push(format!("   {board}  {history:<HISTORY_WIDTH$}  {engine}"));
+    }
+
+    let active_player = if game_state.is_active_player_white {
+        "WHITE"
+    } else {
+        "BLACK"
+    };
+    let clock_status = match &screen.clock {
+        Some(clock) => format!(
+            "{}   ",
+            clock.format_status(game_state.is_active_player_white)
+        ),
+        None => String::new(),
+    };
+    lines.push(format!(
+        "   {active_player} to move, move {:05}   {clock_status}{}",
+        game_state.move_count, screen.message
+    ));
+    lines.push(
+        "   arrows/hjkl: move cursor   enter/space: select/move   esc: cancel   ?: hint   c: coach   a: analyze   f: flip   i: indices   q: quit"
+            .to_string(),
+    );
+    lines.push(format!("   {}", render_legend(get_theme())));
+
+    lines.join("\r\n")
+}
+
+fn get_overlay(game_state: &GameState, screen: &Screen) -> Overlay {
+    let mut overlay = Overlay::new(game_state, screen.history.last().copied());
+
+    overlay.cursor = Some(screen.cursor);
+    if let Some(from) = screen.selected {
+        overlay.select(game_state, from);
+    }
+
+    overlay
+}
+
+fn render_history(screen: &Screen) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    // set up positions start at move count 0 with either player to move, so the
+    // player of the first position decides who moves on even move counts
+    let first_move_count = if screen
+        .first_move_count
+        .is_multiple_of(2)
+        == screen.is_first_player_white
+    {
+        screen.first_move_count
+    } else {
+        screen.first_move_count + 1
+    };
+
+    for (i, mov) in screen.history.iter().enumerate() {
+        let move_count = first_move_count + i as u32;
+
+        // white moves on even move counts
+        if move_count.is_multiple_of(2) || i == 0 {
+            if !line.is_empty() {
+                lines.push(line);
+            }
+            line = format!("{:>3}. ", move_count / 2 + 1);
+            if !move_count.is_multiple_of(2) {
+                line.push_str("....  ");
+            }
+        }
+
+        line.push_str(&format!("{mov}  "));
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    let skip = lines.len().saturating_sub(PANEL_LINES);
+    lines.into_iter().skip(skip).collect()
+}
+
+fn render_engine(screen: &Screen) -> Vec<String> {
+    let skip = screen
+        .engine_lines
+        .len()
+        .saturating_sub(PANEL_LINES);
+
+    screen
+        .engine_lines
+        .iter()
+        .skip(skip)
+        .cloned()
+        .collect()
+}
+
+// lines after the best one are marked with their rank
+fn format_engine_line(depth: u32, rank: usize, search_line: &SearchLine) -> String {
+    let pv: Vec<String> = search_line
+        .pv
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+
+    let mut line = match rank {
+        1 => format!("{depth:>2}"),
+        _ => format!("{:>2}", format!("{rank}.")),
+    };
+    line.push_str(&format!(
+        " {:>8.3} {}",
+        search_line.score as f32 / 1000f32,
+        pv.join(" ")
+    ));
+    line.truncate(ENGINE_WIDTH);
+
+    line
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_screen() -> Screen {
+        Screen {
+            cursor: 0,
+            selected: None,
+            history: Vec::new(),
+            first_move_count: 0,
+            is_first_player_white: true,
+            engine_lines: Vec::new(),
+            message: String::new(),
+            view: View::default(),
+            clock: None,
+            coach_threshold: None,
+        }
+    }
+
+    #[test]
+    fn test_move_cursor() {
+        let mut screen = new_screen();
+
+        // a1 is in the bottom left corner
+        move_cursor(&mut screen, -1, 0);
+        assert_eq!(screen.cursor, 0);
+        move_cursor(&mut screen, 0, 1);
+        assert_eq!(screen.cursor, 0);
+
+        move_cursor(&mut screen, 1, 0);
+        move_cursor(&mut screen, 0, -1);
+        assert_eq!(screen.cursor, 8);
+
+        screen.cursor = 41;
+        move_cursor(&mut screen, 1, 0);
+        move_cursor(&mut screen, 0, -1);
+        assert_eq!(screen.cursor, 41);
+
+        // on a flipped board a1 is in the top right corner
+        screen.view.is_flipped = true;
+        screen.cursor = 0;
+        move_cursor(&mut screen, 1, 0);
+        move_cursor(&mut screen, 0, -1);
+        assert_eq!(screen.cursor, 0);
+
+        move_cursor(&mut screen, -1, 0);
+        move_cursor(&mut screen, 0, 1);
+        assert_eq!(screen.cursor, 8);
+    }
+
+    #[test]
+    fn test_select_square() {
+        let game_state = GameState::new();
+        let mut screen = new_screen();
+
+        // empty square
+        assert_eq!(select_square(&game_state, &mut screen), None);
+        assert_eq!(screen.selected, None);
+
+        // d3 can jump to b3 and f3
+        screen.cursor = 17;
+        assert_eq!(select_square(&game_state, &mut screen), None);
+        assert_eq!(screen.selected, Some(17));
+
+        screen.cursor = 19;
+        assert_eq!(
+            select_square(&game_state, &mut screen),
+            Some(Move::new(17, 19))
+        );
+    }
+
+    #[test]
+    fn test_render_history() {
+        let mut screen = new_screen();
+        screen.first_move_count = 1;
+        screen.is_first_player_white = false;
+        screen.history = vec![Move::new(25, 11), Move::new(17, 19), Move::new(23, 21)];
+
+        assert_eq!(
+            render_history(&screen),
+            vec!["  1. ....  e4e2  ", "  2. d3f3  c4a4  "]
+        );
+    }
+
+    #[test]
+    fn test_render_history_of_setup() {
+        // a set up position with black to move starts at move count 0
+        let mut screen = new_screen();
+        screen.is_first_player_white = false;
+        screen.history = vec![Move::new(25, 11), Move::new(17, 19)];
+
+        assert_eq!(
+            render_history(&screen),
+            vec!["  1. ....  e4e2  ", "  2. d3f3  "]
+        );
+    }
+}
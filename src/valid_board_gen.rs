@@ -1,135 +1,556 @@
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::prelude::*;
+use std::fs;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::thread;
 use std::time::Instant;
 
-use crate::bit_utils::is_board_coord_set;
 use crate::constants::BitBoard;
 use crate::constants::BoardIndex;
+use crate::constants::BOARD_HEIGHT;
+use crate::constants::BOARD_TOTAL_PIECES;
 use crate::constants::BOARD_WIDTH;
+use crate::constants::PIECES_PER_SIDE;
+use crate::geometry::get_geometry;
+use crate::rules::Connectivity;
+use crate::symmetry::get_canonical_bit_board;
+use crate::tablebase::ALL_SQUARES;
+
+pub const VALID_BOARDS_PATH: &str = "connected_boards.dat";
+
+const BOARDS_MAGIC: &[u8; 8] = b"uisge-bb";
+const BOARDS_VERSION: u8 = 1;
+const BOARDS_HEADER_BYTES: usize = 8 + 1 + 1 + 1 + 1 + 8 + 8;
+const PIECES_PER_BOARD: u8 = 2 * PIECES_PER_SIDE as u8;
+
+// The two generators give exactly the same boards: Combinations tests every
+// choice of 12 squares for connectivity, Polyominoes grows the connected
+// shapes square by square and never sees the others.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoardGenerator {
+    Combinations,
+    Polyominoes,
+}
+
+// With canonical_only, a board is kept only if it is the smallest of its
+// mirrored boards, which leaves about a quarter of them.
+pub fn generate_valid_boards(
+    path: &str,
+    generator: BoardGenerator,
+    canonical_only: bool,
+) -> Result<(), String> {
+    let now = Instant::now();
+    let checkpoint_dir = format!("{path}.parts");
+
+    let connected_boards = match generator {
+        BoardGenerator::Combinations => enumerate_combinations(
+            ALL_SQUARES,
+            COMBINATIONS_PER_RANGE,
+            canonical_only,
+            &checkpoint_dir,
+        )?,
+        BoardGenerator::Polyominoes => grow_polyominoes(ALL_SQUARES, canonical_only),
+    };
+
+    write_valid_boards(path, &connected_boards)?;
+
+    // the saved ranges are only needed until the whole file is written
+    if generator == BoardGenerator::Combinations {
+        fs::remove_dir_all(&checkpoint_dir)
+            .map_err(|error| format!("could not remove '{checkpoint_dir}': {error}"))?;
+    }
 
-// base algorithm from https://stackoverflow.com/a/2075867/5460583
-#[allow(unused)]
-pub fn generate_valid_boards() {
-    let mut file = File::create("connected_boards_test.dat").unwrap();
-    let mut connected_boards = Vec::<BitBoard>::new();
+    println!(
+        "{} boards written to {path} in {:.1}s",
+        connected_boards.len(),
+        now.elapsed().as_secs_f64()
+    );
 
-    // equals 12 ones in binary
-    const START: BitBoard = 4096 - 1;
+    Ok(())
+}
 
-    // equals 42 choose 12, i.e. the number of ways to choose 12 elements (uisge pieces) from a set of 42 elements (board tiles)
-    const MAX: BitBoard = 11058116888;
+fn is_kept(bit_board: BitBoard, canonical_only: bool) -> bool {
+    !canonical_only || get_canonical_bit_board(bit_board).0 == bit_board
+}
 
-    let mut v = START;
+const COMBINATIONS_PER_RANGE: u64 = 100_000_000;
 
-    let mut j = 0;
-    let mut k = 0;
-    for i in 0..MAX {
-        if is_connected(v, v.trailing_zeros() as BoardIndex) {
-            connected_boards.push(v);
+// C(n, k) for all n <= 42 and k <= 12
+const BINOMIALS: [[u64; PIECES_PER_BOARD as usize + 1]; BOARD_TOTAL_PIECES as usize + 1] =
+    make_binomials();
+
+const fn make_binomials() -> [[u64; PIECES_PER_BOARD as usize + 1]; BOARD_TOTAL_PIECES as usize + 1]
+{
+    let mut binomials = [[0; PIECES_PER_BOARD as usize + 1]; BOARD_TOTAL_PIECES as usize + 1];
+
+    let mut n = 0;
+    while n <= BOARD_TOTAL_PIECES as usize {
+        binomials[n][0] = 1;
+
+        let mut k = 1;
+        while k <= PIECES_PER_BOARD as usize && n > 0 {
+            binomials[n][k] = binomials[n - 1][k - 1] + binomials[n - 1][k];
             k += 1;
         }
+        n += 1;
+    }
 
-        let t = (v | (v - 1)) as i64;
-        let w = (t + 1) | (((!t & -!t) - 1) >> (v.trailing_zeros() + 1));
-        v = w as u64;
-
-        if i % 100000000 == 0 {
-            println!("{} {}/{} ({}%) {:042b}", j, k, i, k as f32 / i as f32, w);
-            j += 1;
-
-            //if (j == 2) {
-            //    for ppp in 0..10 {
-            //        println!("{:042b}", connected_boards[ppp + 1000]);
-            //        print_bit_board(connected_boards[ppp + 1000]);
-            //        println!("");
-            //    }
-            //    file.write_all(&boards_to_bytes(&connected_boards));
-            //    return;
-            //}
+    binomials
+}
+
+// The choices of 12 out of n squares, as n bit numbers with 12 ones, are
+// numbered in ascending order. The range of choices with these numbers is
+// enumerated from the first one with Gosper's hack, base algorithm from
+// https://stackoverflow.com/a/2075867/5460583
+fn get_combination(mut rank: u64) -> BitBoard {
+    let mut combination = 0;
+
+    for k in (1..=PIECES_PER_BOARD as usize).rev() {
+        let mut n = k - 1;
+        while BINOMIALS[n + 1][k] <= rank {
+            n += 1;
         }
+
+        combination |= 1 << n;
+        rank -= BINOMIALS[n][k];
     }
 
-    file.write_all(&boards_to_bytes(&connected_boards))
-        .unwrap();
+    combination
 }
 
-pub fn make_board(s: &str) -> BitBoard {
-    BitBoard::from_str_radix(s, 2).unwrap()
+fn get_next_combination(v: BitBoard) -> BitBoard {
+    let t = (v | (v - 1)) as i64;
+    let w = (t + 1) | (((!t & -!t) - 1) >> (v.trailing_zeros() + 1));
+
+    w as u64
 }
 
-pub fn is_connected(bit_board: BitBoard, bit_index: BoardIndex) -> bool {
-    let x = bit_index % BOARD_WIDTH;
-    let y = bit_index / BOARD_WIDTH;
+// the squares of the region chosen by the ones of a combination
+fn place_combination(combination: BitBoard, squares: &[BoardIndex]) -> BitBoard {
+    let mut bit_board = 0;
+
+    let mut rest = combination;
+    while rest != 0 {
+        bit_board |= 1 << squares[rest.trailing_zeros() as usize];
+        rest &= rest - 1;
+    }
 
-    flood_fill(bit_board, x, y) == 0
+    bit_board
 }
 
-fn flood_fill(bit_board: BitBoard, x: BoardIndex, y: BoardIndex) -> BitBoard {
-    let mut bb = bit_board;
+fn enumerate_range(
+    squares: &[BoardIndex],
+    first: u64,
+    len: u64,
+    canonical_only: bool,
+) -> Vec<BitBoard> {
+    let mut connected_boards = Vec::new();
+    let mut combination = get_combination(first);
+
+    for i in 0..len {
+        let bit_board = place_combination(combination, squares);
+
+        if is_connected(bit_board, bit_board.trailing_zeros() as BoardIndex)
+            && is_kept(bit_board, canonical_only)
+        {
+            connected_boards.push(bit_board);
+        }
 
-    if !is_board_coord_set(bb, x, y) {
-        return bit_board;
+        if i + 1 < len {
+            combination = get_next_combination(combination);
+        }
     }
 
-    bb = unset_bit(bb, x, y);
-    bb = flood_fill(bb, x.wrapping_sub(1), y);
-    bb = flood_fill(bb, x + 1, y);
-    bb = flood_fill(bb, x, y.wrapping_sub(1));
-    bb = flood_fill(bb, x, y + 1);
+    connected_boards
+}
 
-    bb
+// All choices of 12 squares of the region are split into ranges that the
+// threads take one after another. Every finished range is saved as a boards
+// file in the checkpoint directory, so an interrupted run only repeats the
+// ranges that were not finished. The options of the run are saved there too,
+// a run with other options does not mix its ranges with the saved ones.
+pub fn enumerate_combinations(
+    region: BitBoard,
+    range_len: u64,
+    canonical_only: bool,
+    checkpoint_dir: &str,
+) -> Result<Vec<BitBoard>, String> {
+    let squares: Vec<BoardIndex> = (0..BOARD_TOTAL_PIECES)
+        .filter(|i| region & 1 << i != 0)
+        .collect();
+    let total = BINOMIALS[squares.len()][PIECES_PER_BOARD as usize];
+    let range_count = total.div_ceil(range_len) as usize;
+
+    fs::create_dir_all(checkpoint_dir)
+        .map_err(|error| format!("could not create '{checkpoint_dir}': {error}"))?;
+
+    let options_path = format!("{checkpoint_dir}/options");
+    let options = format!("region {region:#x} range {range_len} canonical {canonical_only}\n");
+    match fs::read_to_string(&options_path) {
+        Ok(saved) if saved != options => {
+            return Err(format!(
+                "{checkpoint_dir} belongs to a run with other options ({}), remove it first",
+                saved.trim()
+            ));
+        }
+        Ok(_) => {}
+        Err(_) => fs::write(&options_path, &options)
+            .map_err(|error| format!("could not write '{options_path}': {error}"))?,
+    }
+
+    let ranges: Vec<Mutex<Option<Vec<BitBoard>>>> = (0..range_count)
+        .map(|i| Mutex::new(load_valid_boards(&get_range_path(checkpoint_dir, i)).ok()))
+        .collect();
+    let next_range = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(
+        ranges
+            .iter()
+            .filter(|range| range.lock().unwrap().is_some())
+            .count(),
+    );
+
+    if finished.load(Ordering::Relaxed) > 0 {
+        println!(
+            "resuming with {} of {range_count} ranges from {checkpoint_dir}",
+            finished.load(Ordering::Relaxed)
+        );
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+    let errors: Vec<String> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| -> Result<(), String> {
+                    loop {
+                        let i = next_range.fetch_add(1, Ordering::Relaxed);
+                        if i >= range_count {
+                            return Ok(());
+                        }
+
+                        if ranges[i].lock().unwrap().is_some() {
+                            continue;
+                        }
+
+                        let first = i as u64 * range_len;
+                        let len = range_len.min(total - first);
+                        let boards = enumerate_range(&squares, first, len, canonical_only);
+
+                        // an interrupted write must not leave a damaged range
+                        let path = get_range_path(checkpoint_dir, i);
+                        let temporary_path = format!("{path}.tmp");
+                        write_valid_boards(&temporary_path, &boards)?;
+                        fs::rename(&temporary_path, &path)
+                            .map_err(|error| format!("could not write '{path}': {error}"))?;
+
+                        let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                        println!(
+                            "range {i} done with {} boards ({done}/{range_count})",
+                            boards.len()
+                        );
+
+                        *ranges[i].lock().unwrap() = Some(boards);
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .filter_map(|worker| worker.join().unwrap().err())
+            .collect()
+    });
+
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+
+    // the ranges are in ascending order and so are their boards
+    Ok(ranges
+        .into_iter()
+        .flat_map(|range| range.into_inner().unwrap().unwrap())
+        .collect())
 }
 
-fn unset_bit(bit_board: BitBoard, x: BoardIndex, y: BoardIndex) -> BitBoard {
-    let mask = 1 << (BOARD_WIDTH * y + x);
-    bit_board & !mask
+fn get_range_path(checkpoint_dir: &str, i: usize) -> String {
+    format!("{checkpoint_dir}/range-{i:05}.dat")
 }
 
-pub fn bytes_to_boards(bytes: &[u8]) -> Vec<BitBoard> {
-    let bit_boards_len = bytes.len() / 8; // if not aligned, some data is lost
-    let mut bit_boards = Vec::with_capacity(bit_boards_len);
-
-    for i in 0..bit_boards_len {
-        let bit_board = unsafe {
-            std::mem::transmute::<[u8; 8], BitBoard>([
-                bytes[i * 8 + 7],
-                bytes[i * 8 + 6],
-                bytes[i * 8 + 5],
-                bytes[i * 8 + 4],
-                bytes[i * 8 + 3],
-                bytes[i * 8 + 2],
-                bytes[i * 8 + 1],
-                bytes[i * 8],
-            ])
-        };
+fn get_neighbours(i: BoardIndex) -> BitBoard {
+    let (x, y) = (i % BOARD_WIDTH, i / BOARD_WIDTH);
+    let mut neighbours = 0;
 
-        bit_boards.push(bit_board);
+    if x > 0 {
+        neighbours |= 1 << (i - 1);
+    }
+    if x + 1 < BOARD_WIDTH {
+        neighbours |= 1 << (i + 1);
+    }
+    if y > 0 {
+        neighbours |= 1 << (i - BOARD_WIDTH);
+    }
+    if y + 1 < BOARD_HEIGHT {
+        neighbours |= 1 << (i + BOARD_WIDTH);
+    }
+
+    neighbours
+}
+
+// Redelmeier's algorithm: every connected shape is grown from its lowest
+// square, adding one square of the untried neighbours at a time. A square
+// that was once offered is never offered again on the same path, which makes
+// every shape appear exactly once.
+pub fn grow_polyominoes(region: BitBoard, canonical_only: bool) -> Vec<BitBoard> {
+    let neighbours: Vec<BitBoard> = (0..BOARD_TOTAL_PIECES)
+        .map(|i| get_neighbours(i) & region)
+        .collect();
+
+    let mut connected_boards = Vec::new();
+
+    for origin in (0..BOARD_TOTAL_PIECES).filter(|i| region & 1 << i != 0) {
+        let below_origin = (1 << origin) - 1;
+        grow_shape(
+            &neighbours,
+            0,
+            1 << origin,
+            below_origin | 1 << origin,
+            canonical_only,
+            &mut connected_boards,
+        );
     }
 
-    bit_boards
+    connected_boards.sort_unstable();
+    connected_boards
 }
 
-#[allow(unused)]
+fn grow_shape(
+    neighbours: &[BitBoard],
+    shape: BitBoard,
+    mut untried: BitBoard,
+    seen: BitBoard,
+    canonical_only: bool,
+    connected_boards: &mut Vec<BitBoard>,
+) {
+    while untried != 0 {
+        let i = untried.trailing_zeros() as usize;
+        untried &= untried - 1;
+
+        let grown = shape | 1 << i;
+
+        if grown.count_ones() == PIECES_PER_BOARD as u32 {
+            if is_kept(grown, canonical_only) {
+                connected_boards.push(grown);
+            }
+            continue;
+        }
+
+        let new_neighbours = neighbours[i] & !seen;
+        grow_shape(
+            neighbours,
+            grown,
+            untried | new_neighbours,
+            seen | new_neighbours,
+            canonical_only,
+            connected_boards,
+        );
+    }
+}
+
+// Grows the group of the piece on bit_index by a step in every direction at
+// once until it stops growing, the file masks keep the steps from wrapping
+// around the edges of the board.
+pub fn is_connected(bit_board: BitBoard, bit_index: BoardIndex) -> bool {
+    let geometry = get_geometry();
+    let (width, first_file, last_file) = (geometry.width, geometry.first_file, geometry.last_file);
+    let is_diagonal = geometry.rules.connectivity == Connectivity::Eight;
+    let mut group = bit_board & 1 << bit_index;
+
+    loop {
+        let mut grown = group
+            | (group << 1 & !first_file)
+            | (group >> 1 & !last_file)
+            | group << width
+            | group >> width;
+
+        if is_diagonal {
+            grown |= (group << (width + 1) & !first_file)
+                | (group << (width - 1) & !last_file)
+                | (group >> (width + 1) & !last_file)
+                | (group >> (width - 1) & !first_file);
+        }
+
+        grown &= bit_board;
+
+        if grown == group {
+            return group == bit_board;
+        }
+
+        group = grown;
+    }
+}
+
+// A piece is a cut vertex when removing it splits the remaining pieces into
+// several groups, so it can never leave its square.
+pub fn get_cut_vertices(bit_board: BitBoard) -> BitBoard {
+    let mut cut_vertices = 0;
+
+    let mut rest = bit_board;
+    while rest != 0 {
+        let i = rest.trailing_zeros() as BoardIndex;
+        rest &= rest - 1;
+
+        let without_piece = bit_board & !(1 << i);
+        if without_piece != 0
+            && !is_connected(without_piece, without_piece.trailing_zeros() as BoardIndex)
+        {
+            cut_vertices |= 1 << i;
+        }
+    }
+
+    cut_vertices
+}
+
+// The boards are stored sorted, each one as the difference to the previous
+// one in a LEB128 varint (7 bits per byte), which takes about 3 bytes instead
+// of 8. The header holds the board size and piece count the boards were made
+// for, their number and an FNV-1a checksum of the data behind it.
 pub fn boards_to_bytes(bit_boards: &[BitBoard]) -> Vec<u8> {
-    let mut bytes = Vec::with_capacity(8 * bit_boards.len());
+    let mut sorted = bit_boards.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut data = Vec::with_capacity(3 * sorted.len());
+    let mut previous = 0;
+    for bit_board in sorted.iter() {
+        let mut delta = bit_board - previous;
+        previous = *bit_board;
+
+        while delta >= 0x80 {
+            data.push((delta & 0x7f) as u8 | 0x80);
+            delta >>= 7;
+        }
+        data.push(delta as u8);
+    }
+
+    let mut bytes = Vec::with_capacity(BOARDS_HEADER_BYTES + data.len());
+    bytes.extend_from_slice(BOARDS_MAGIC);
+    bytes.push(BOARDS_VERSION);
+    bytes.push(BOARD_WIDTH);
+    bytes.push(BOARD_HEIGHT);
+    bytes.push(PIECES_PER_BOARD);
+    bytes.extend_from_slice(&(sorted.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&get_checksum(&data).to_le_bytes());
+    bytes.extend_from_slice(&data);
+
+    bytes
+}
+
+pub fn bytes_to_boards(bytes: &[u8]) -> Result<Vec<BitBoard>, String> {
+    if bytes.len() < BOARDS_HEADER_BYTES || &bytes[..8] != BOARDS_MAGIC {
+        return Err("not a boards file".to_string());
+    }
+
+    if bytes[8] != BOARDS_VERSION {
+        return Err(format!("unsupported boards version {}", bytes[8]));
+    }
+
+    let (width, height, pieces) = (bytes[9], bytes[10], bytes[11]);
+    if (width, height, pieces) != (BOARD_WIDTH, BOARD_HEIGHT, PIECES_PER_BOARD) {
+        return Err(format!(
+            "the boards are {width}x{height} with {pieces} pieces, expected \
+             {BOARD_WIDTH}x{BOARD_HEIGHT} with {PIECES_PER_BOARD} pieces"
+        ));
+    }
+
+    let count = u64::from_le_bytes(bytes[12..20].try_into().unwrap()) as usize;
+    let checksum = u64::from_le_bytes(bytes[20..28].try_into().unwrap());
+
+    let data = &bytes[BOARDS_HEADER_BYTES..];
+    if get_checksum(data) != checksum {
+        return Err("the checksum does not match, the file is damaged".to_string());
+    }
+
+    // every board takes at least one byte
+    let mut bit_boards = Vec::with_capacity(count.min(data.len()));
+    let mut previous: BitBoard = 0;
+    let mut delta: BitBoard = 0;
+    let mut shift = 0;
+
+    for byte in data {
+        if shift >= BitBoard::BITS || (byte & 0x7f) as BitBoard > BitBoard::MAX >> shift {
+            return Err(format!("board {} is too large", bit_boards.len()));
+        }
+
+        delta |= ((byte & 0x7f) as BitBoard) << shift;
+        shift += 7;
+
+        if byte & 0x80 != 0 {
+            continue;
+        }
+
+        let i = bit_boards.len();
+        if i > 0 && delta == 0 {
+            return Err(format!("board {i} is not larger than the one before"));
+        }
 
-    for bit_board in bit_boards {
-        bytes.extend(&bit_board.to_be_bytes());
+        let bit_board = previous
+            .checked_add(delta)
+            .filter(|bit_board| bit_board & !ALL_SQUARES == 0)
+            .ok_or(format!("board {i} has pieces outside of the board"))?;
+
+        if bit_board.count_ones() != PIECES_PER_BOARD as u32 {
+            return Err(format!(
+                "board {i} has {} pieces instead of {PIECES_PER_BOARD}",
+                bit_board.count_ones()
+            ));
+        }
+
+        bit_boards.push(bit_board);
+        previous = bit_board;
+        delta = 0;
+        shift = 0;
+    }
+
+    if shift != 0 {
+        return Err("the last board is cut off".to_string());
     }
 
+    if bit_boards.len() != count {
+        return Err(format!(
+            "expected {count} boards, the file has {}",
+            bit_boards.len()
+        ));
+    }
+
+    Ok(bit_boards)
+}
+
+// 64 bit FNV-1a
+fn get_checksum(bytes: &[u8]) -> u64 {
     bytes
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
 }
 
-pub fn read_valid_boards() -> HashSet<BitBoard> {
-    let mut file = File::open("connected_boards.dat").unwrap();
+pub fn write_valid_boards(path: &str, bit_boards: &[BitBoard]) -> Result<(), String> {
+    fs::write(path, boards_to_bytes(bit_boards))
+        .map_err(|error| format!("could not write '{path}': {error}"))
+}
 
-    let mut d = Vec::<u8>::new();
-    file.read_to_end(&mut d).unwrap();
+// the boards in ascending order
+pub fn load_valid_boards(path: &str) -> Result<Vec<BitBoard>, String> {
+    let bytes = fs::read(path).map_err(|error| format!("could not read '{path}': {error}"))?;
 
-    let data = bytes_to_boards(&d);
+    bytes_to_boards(&bytes).map_err(|error| format!("{path}: {error}"))
+}
 
-    data.iter().cloned().collect()
+pub fn read_valid_boards(path: &str) -> Result<HashSet<BitBoard>, String> {
+    Ok(load_valid_boards(path)?
+        .into_iter()
+        .collect())
 }
 
 // TODO
@@ -142,9 +563,9 @@ pub fn read_valid_boards() -> HashSet<BitBoard> {
 // contains: 4777
 //
 // WHY??
-#[allow(unused)]
+#[allow(unused, clippy::manual_is_multiple_of)]
 pub fn benchmark_valid_board_hashing() {
-    let mut connected_boards = read_valid_boards();
+    let mut connected_boards = read_valid_boards(VALID_BOARDS_PATH).unwrap();
 
     let x: BitBoard = 0b000100000111000011111000001000000100000010;
 
@@ -174,22 +595,197 @@ pub fn benchmark_valid_board_hashing() {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::tablebase::parse_region;
+
+    // the first connected boards in the order of generate_valid_boards
+    fn get_connected_boards(count: usize) -> Vec<BitBoard> {
+        let mut v = get_combination(0);
+        let mut boards = Vec::new();
+
+        while boards.len() < count {
+            if is_connected(v, v.trailing_zeros() as BoardIndex) {
+                boards.push(v);
+            }
+            v = get_next_combination(v);
+        }
+
+        boards
+    }
+
+    fn get_checkpoint_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        let dir = dir.to_str().unwrap().to_string();
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn make_board(s: &str) -> BitBoard {
+        BitBoard::from_str_radix(s, 2).unwrap()
+    }
+
+    #[test]
+    fn test_combination_ranks() {
+        assert_eq!(get_combination(0), 4096 - 1);
+        assert_eq!(BINOMIALS[42][12], 11058116888);
+        assert_eq!(get_combination(11058116888 - 1), 4095 << 30);
+
+        for first in [0, 1000, 123456789, 11058116888 - 100] {
+            let mut combination = get_combination(first);
+            for rank in first + 1..first + 100 {
+                combination = get_next_combination(combination);
+                assert_eq!(get_combination(rank), combination);
+            }
+        }
+
+        let squares = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 41];
+        assert_eq!(
+            place_combination(get_combination(0), &squares),
+            0b11111111111 | 1 << 41
+        );
+    }
+
+    #[test]
+    fn test_generators_agree() {
+        for (region, canonical_only) in [("a1:e4", false), ("a1:g3", false), ("a1:g3", true)] {
+            let region = parse_region(region).unwrap();
+            let dir = get_checkpoint_dir("uisge-generators-test");
+
+            let combinations = enumerate_combinations(region, 10000, canonical_only, &dir).unwrap();
+            let polyominoes = grow_polyominoes(region, canonical_only);
+            fs::remove_dir_all(&dir).unwrap();
+
+            assert!(!polyominoes.is_empty());
+            assert!(polyominoes.is_sorted_by(|a, b| a < b));
+            assert_eq!(
+                boards_to_bytes(&combinations),
+                boards_to_bytes(&polyominoes)
+            );
+        }
+    }
+
+    #[test]
+    fn test_resume_enumeration() {
+        let region = parse_region("a1:e4").unwrap();
+        let dir = get_checkpoint_dir("uisge-resume-test");
+
+        let boards = enumerate_combinations(region, 10000, false, &dir).unwrap();
+
+        // 13 ranges of C(20, 12) = 125970 choices, lose some of them
+        fs::remove_file(get_range_path(&dir, 0)).unwrap();
+        fs::remove_file(get_range_path(&dir, 12)).unwrap();
+        fs::write(get_range_path(&dir, 5), b"damaged").unwrap();
+        assert_eq!(
+            enumerate_combinations(region, 10000, false, &dir).unwrap(),
+            boards
+        );
+
+        assert!(enumerate_combinations(region, 10000, true, &dir)
+            .unwrap_err()
+            .contains("other options"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
     #[test]
     fn test_boards_to_bytes() {
-        let mut x = Vec::<BitBoard>::new();
-        x.push(5345345345234243);
-        x.push(143954759751381111);
+        let boards = get_connected_boards(1000);
+
+        let mut shuffled = boards.clone();
+        shuffled.reverse();
+        shuffled.push(boards[10]);
 
-        let a = boards_to_bytes(&x);
-        let b = bytes_to_boards(&a);
+        let bytes = boards_to_bytes(&shuffled);
+        assert!(bytes.len() < BOARDS_HEADER_BYTES + 3 * boards.len());
+        assert_eq!(bytes_to_boards(&bytes).unwrap(), boards);
 
-        assert_eq!(a.len(), 16);
-        assert_eq!(b.len(), 2);
-        assert_eq!(x, b);
+        let empty = boards_to_bytes(&[]);
+        assert_eq!(empty.len(), BOARDS_HEADER_BYTES);
+        assert!(bytes_to_boards(&empty)
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
+    fn test_invalid_boards_files() {
+        let boards = get_connected_boards(100);
+        let bytes = boards_to_bytes(&boards);
+
+        let with_checksum = |mut bytes: Vec<u8>| {
+            let checksum = get_checksum(&bytes[BOARDS_HEADER_BYTES..]);
+            bytes[20..28].copy_from_slice(&checksum.to_le_bytes());
+            bytes
+        };
+
+        assert!(bytes_to_boards(&bytes[..20]).is_err());
+        assert!(bytes_to_boards(b"uisge-tb\x01").is_err());
+
+        let mut other_version = bytes.clone();
+        other_version[8] = 2;
+        assert!(bytes_to_boards(&other_version)
+            .unwrap_err()
+            .contains("version"));
+
+        let mut other_size = bytes.clone();
+        other_size[9] = 8;
+        assert!(bytes_to_boards(&other_size)
+            .unwrap_err()
+            .contains("8x6"));
+
+        let mut damaged = bytes.clone();
+        damaged[40] ^= 1;
+        assert!(bytes_to_boards(&damaged)
+            .unwrap_err()
+            .contains("checksum"));
+
+        let mut other_count = bytes.clone();
+        other_count[12] = 99;
+        assert!(bytes_to_boards(&other_count)
+            .unwrap_err()
+            .contains("expected 99 boards"));
+
+        let cut_off = with_checksum(bytes[..bytes.len() - 1].to_vec());
+        assert!(bytes_to_boards(&cut_off).is_err());
+
+        let mut repeated = bytes.clone();
+        repeated.push(0);
+        assert!(bytes_to_boards(&with_checksum(repeated))
+            .unwrap_err()
+            .contains("not larger"));
+
+        let mut too_many_pieces = bytes.clone();
+        too_many_pieces.push(1);
+        assert!(bytes_to_boards(&with_checksum(too_many_pieces))
+            .unwrap_err()
+            .contains("pieces instead of 12"));
+
+        let mut outside = bytes.clone();
+        outside.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]);
+        assert!(bytes_to_boards(&with_checksum(outside))
+            .unwrap_err()
+            .contains("too large"));
+    }
+
+    #[test]
+    fn test_read_valid_boards() {
+        let path =
+            std::env::temp_dir().join(format!("uisge-boards-test-{}.dat", std::process::id()));
+        let path = path.to_str().unwrap();
+        let boards = get_connected_boards(10);
+
+        write_valid_boards(path, &boards).unwrap();
+        let read = read_valid_boards(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(read.len(), 10);
+        assert!(boards
+            .iter()
+            .all(|board| read.contains(board)));
+        assert!(read_valid_boards(path)
+            .unwrap_err()
+            .contains("could not read"));
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_is_connected() {
         let x = make_board(
             "\
@@ -235,33 +831,41 @@ mod test {
     }
 
     #[test]
-    fn test_unset_bit() {
+    fn test_get_cut_vertices() {
         let x = make_board(
             "\
-            0001000\
-            0011100\
-            0011111\
-            0000010\
-            0000010\
-            0000010\
+            0000000\
+            0000000\
+            0000000\
+            0000000\
+            0000011\
+            0000011\
         ",
         );
-        assert_eq!(x, 0b000100000111000011111000001000000100000010);
-        assert_eq!(
-            unset_bit(x, 0, 0),
-            0b000100000111000011111000001000000100000010
-        );
-        assert_eq!(
-            unset_bit(x, 1, 0),
-            0b000100000111000011111000001000000100000000
-        );
-        assert_eq!(
-            unset_bit(x, 1, 1),
-            0b000100000111000011111000001000000000000010
+        assert_eq!(get_cut_vertices(x), 0);
+
+        let x = make_board(
+            "\
+            0000000\
+            0000000\
+            0000000\
+            0000000\
+            0000000\
+            0000111\
+        ",
         );
-        assert_eq!(
-            unset_bit(x, 1, 2),
-            0b000100000111000011111000000000000100000010
+        assert_eq!(get_cut_vertices(x), 1 << 1);
+
+        let x = make_board(
+            "\
+            0000000\
+            0000000\
+            0000000\
+            0000000\
+            0000111\
+            0000101\
+        ",
         );
+        assert_eq!(get_cut_vertices(x), 0b111 << 7);
     }
 }
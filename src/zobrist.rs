@@ -0,0 +1,112 @@
+use crate::constants::BOARD_TOTAL_PIECES;
+use crate::geometry::MAX_SQUARES;
+use crate::piece_list::PieceList;
+use crate::state::GameState;
+use crate::state::Piece;
+
+// The keys are computed at compile time from a fixed seed, so hashes stay the
+// same between runs and can be stored in files like the opening book.
+const ZOBRIST_SEED: u64 = 0x75697367655f6b65;
+
+const PIECE_KEYS: [[u64; 4]; MAX_SQUARES as usize] = make_piece_keys();
+const BLACK_TO_MOVE_KEY: u64 = split_mix(ZOBRIST_SEED, 4 * BOARD_TOTAL_PIECES as u64);
+
+// the n-th number of a SplitMix64 sequence
+const fn split_mix(seed: u64, n: u64) -> u64 {
+    let mut z = seed.wrapping_add((n + 1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// The squares beyond the standard board come after the black to move key, so
+// the hashes of the standard board stay the same.
+const fn make_piece_keys() -> [[u64; 4]; MAX_SQUARES as usize] {
+    let mut keys = [[0; 4]; MAX_SQUARES as usize];
+
+    let mut i = 0;
+    while i < MAX_SQUARES as usize {
+        let n = match i < BOARD_TOTAL_PIECES as usize {
+            true => i * 4,
+            false => i * 4 + 1,
+        };
+
+        let mut piece = 0;
+        while piece < 4 {
+            keys[i][piece] = split_mix(ZOBRIST_SEED, (n + piece) as u64);
+            piece += 1;
+        }
+        i += 1;
+    }
+
+    keys
+}
+
+impl GameState {
+    // The hash covers the pieces and the side to move but not the move count,
+    // so that transpositions get the same hash.
+    pub fn get_hash(&self) -> u64 {
+        let mut hash = 0;
+
+        let mut add_pieces = |pieces: PieceList, piece: Piece| {
+            for i in pieces {
+                hash ^= PIECE_KEYS[i as usize][piece as usize];
+            }
+        };
+
+        add_pieces(self.board.black_kings, Piece::BlackKing);
+        add_pieces(self.board.black_pawns, Piece::BlackPawn);
+        add_pieces(self.board.white_kings, Piece::WhiteKing);
+        add_pieces(self.board.white_pawns, Piece::WhitePawn);
+
+        if !self.is_active_player_white {
+            hash ^= BLACK_TO_MOVE_KEY;
+        }
+
+        hash
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash_transpositions() {
+        let game_state = GameState::new();
+        let moves = game_state.generate_moves();
+        assert_ne!(game_state.get_hash(), 0);
+
+        // the side to move is part of the hash
+        let mut other_side = game_state.clone();
+        other_side.is_active_player_white = false;
+        assert_ne!(game_state.get_hash(), other_side.get_hash());
+
+        let mut hashes = Vec::new();
+        for mov in moves {
+            let mut game_state_move = game_state.clone();
+            game_state_move.make_move(mov);
+            hashes.push(game_state_move.get_hash());
+        }
+        hashes.sort();
+        hashes.dedup();
+        assert_eq!(hashes.len(), game_state.generate_moves().len());
+
+        // the same position reached with a different move count has the same hash
+        let mut later = game_state.clone();
+        later.move_count += 2;
+        assert_eq!(game_state.get_hash(), later.get_hash());
+    }
+
+    #[test]
+    fn test_hash_is_stable() {
+        // book files rely on the hashes never changing
+        assert_eq!(GameState::new().get_hash(), 0x4d4fce06b30b1aa3);
+        assert_eq!(
+            GameState::new().get_hash(),
+            GameState::from_notation("7/2PP3/1PPPP2/2pppp1/3pp2/7 w 0")
+                .unwrap()
+                .get_hash()
+        );
+    }
+}